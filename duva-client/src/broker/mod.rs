@@ -46,13 +46,20 @@ impl Broker {
                         continue;
                     };
 
-                    if let Some(index) = self.need_index_increase(&input.kind, &query_io) {
-                        if index > self.latest_known_index {
-                            self.latest_known_index = index;
-                        }
+                    if let Some(index) = self.need_index_increase(&input.kind, &query_io)
+                        && index > self.latest_known_index
+                    {
+                        self.latest_known_index = index;
                     }
                     self.may_update_request_id(&input.kind);
 
+                    // a follower refused the write, so go straight to the leader it named
+                    if let Some(leader) = query_io.moved_to()
+                        && !self.connect_to_leader(&leader).await
+                    {
+                        let _ = self.discover_leader().await;
+                    }
+
                     input.callback.send((input.kind, query_io)).unwrap_or_else(|_| {
                         println!("Failed to send response to input callback");
                     });
@@ -91,18 +98,42 @@ impl Broker {
     }
 
     fn need_index_increase(&mut self, kind: &ClientAction, query_io: &QueryIO) -> Option<u64> {
-        if matches!(kind, ClientAction::Set { .. } | ClientAction::Delete { .. }) {
-            if let QueryIO::SimpleString(v) = query_io {
-                let rindex = v.split_whitespace().last().unwrap();
-                return rindex.parse::<u64>().ok();
-            }
+        if matches!(kind, ClientAction::Set { .. } | ClientAction::Delete { .. })
+            && let QueryIO::SimpleString(v) = query_io
+        {
+            let rindex = v.split_whitespace().last().unwrap();
+            return rindex.parse::<u64>().ok();
         }
         None
     }
 
     fn may_update_request_id(&mut self, input: &ClientAction) {
         match input {
-            ClientAction::Set { .. } | ClientAction::Delete { .. } | ClientAction::Save => {
+            ClientAction::Set { .. }
+            | ClientAction::SetWithExpiry { .. }
+            | ClientAction::Delete { .. }
+            | ClientAction::Incr { .. }
+            | ClientAction::Decr { .. }
+            | ClientAction::LPush { .. }
+            | ClientAction::RPush { .. }
+            | ClientAction::LPop { .. }
+            | ClientAction::RPop { .. }
+            | ClientAction::HSet { .. }
+            | ClientAction::HDel { .. }
+            | ClientAction::HIncrBy { .. }
+            | ClientAction::SAdd { .. }
+            | ClientAction::SRem { .. }
+            | ClientAction::SInterStore { .. }
+            | ClientAction::SUnionStore { .. }
+            | ClientAction::SDiffStore { .. }
+            | ClientAction::ZAdd { .. }
+            | ClientAction::ZRem { .. }
+            | ClientAction::ZIncrBy { .. }
+            | ClientAction::XAdd { .. }
+            | ClientAction::XGroupCreate { .. }
+            | ClientAction::XReadGroup { .. }
+            | ClientAction::XAck { .. }
+            | ClientAction::Save => {
                 self.request_id += 1;
             },
             _ => {},
@@ -132,28 +163,34 @@ impl Broker {
 
     // pull-based leader discovery
     async fn discover_leader(&mut self) -> Result<(), IoError> {
-        for node in &self.cluster_nodes {
+        for node in self.cluster_nodes.clone() {
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
             println!("Trying to connect to node: {}...", node);
 
-            let auth_req = AuthRequest {
-                client_id: Some(self.client_id.to_string()),
-                request_id: self.request_id,
-            };
-            let Ok((r, w, auth_response)) = Self::authenticate(node, Some(auth_req)).await else {
-                continue;
-            };
-
-            if auth_response.connected_to_leader {
-                println!("Connected to a new leader: {}", node);
-                self.replace_stream(r, w).await;
-                self.cluster_nodes = auth_response.cluster_nodes;
-
+            if self.connect_to_leader(&node).await {
                 return Ok(());
             }
         }
         Err(IoError::Custom("No leader found in the cluster".to_string()))
     }
+
+    async fn connect_to_leader(&mut self, node: &PeerIdentifier) -> bool {
+        let auth_req = AuthRequest {
+            client_id: Some(self.client_id.to_string()),
+            request_id: self.request_id,
+        };
+        let Ok((r, w, auth_response)) = Self::authenticate(node, Some(auth_req)).await else {
+            return false;
+        };
+        if !auth_response.connected_to_leader {
+            return false;
+        }
+
+        println!("Connected to a new leader: {}", node);
+        self.replace_stream(r, w).await;
+        self.cluster_nodes = auth_response.cluster_nodes;
+        true
+    }
 }
 
 pub enum BrokerMessage {
@@ -24,6 +24,9 @@ pub(crate) static COMMANDS: &[&str] = &[
     "cluster info",
     "cluster nodes",
     "cluster forget",
+    "cluster failover",
+    "cluster promote",
+    "cluster demote",
     "info replication",
 ];
 
@@ -77,7 +80,7 @@ impl Completer for DuvaHinter {
             "cluster" => {
                 if previous_words.len() == 1 {
                     // Suggest subcommands for cluster that start with current_prefix
-                    let subcommands = ["info", "nodes", "forget"];
+                    let subcommands = ["info", "nodes", "forget", "failover", "promote", "demote"];
                     candidates.extend(
                         subcommands
                             .iter()
@@ -86,8 +89,8 @@ impl Completer for DuvaHinter {
                     );
                 } else if previous_words.len() == 2 {
                     let subcommand = previous_words[1].to_lowercase();
-                    if subcommand == "forget" {
-                        // Suggest "node" for cluster forget
+                    if matches!(subcommand.as_str(), "forget" | "failover" | "promote" | "demote") {
+                        // Suggest "node" for cluster subcommands that take one
                         candidates.push(new_pair!("node"));
                     }
                 }
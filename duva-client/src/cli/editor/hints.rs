@@ -126,6 +126,9 @@ pub(crate) fn default_hints() -> HashSet<CommandHint> {
     set.insert(CommandHint::new("cluster info", "cluster "));
     set.insert(CommandHint::new("cluster nodes", "cluster "));
     set.insert(CommandHint::new("cluster forget node", "cluster "));
+    set.insert(CommandHint::new("cluster failover node", "cluster "));
+    set.insert(CommandHint::new("cluster promote node", "cluster "));
+    set.insert(CommandHint::new("cluster demote node", "cluster "));
     set.insert(CommandHint::new("ping", ""));
     set.insert(CommandHint::new("keys pattern", "keys "));
     set.insert(CommandHint::new("info [section]", ""));
@@ -163,6 +166,9 @@ pub(crate) fn dynamic_hints() -> HashMap<&'static str, Vec<DynamicHint>> {
     );
 
     map.insert("cluster forget", vec![hint!("node", 0)]);
+    map.insert("cluster failover", vec![hint!("node", 0)]);
+    map.insert("cluster promote", vec![hint!("node", 0)]);
+    map.insert("cluster demote", vec![hint!("node", 0)]);
     map.insert("keys", vec![hint!("pattern", 0)]);
     map.insert("get", vec![hint!("key", 0)]);
     map.insert("exists", vec![hint!("key [key ...]", 0, repeat), hint!("[key ...]", 1, repeat)]);
@@ -3,7 +3,7 @@ mod editor;
 use clap::Parser;
 use duva::{
     prelude::tokio::{self, sync::oneshot},
-    presentation::clients::request::extract_action,
+    presentation::clients::request::{extract_action, extract_write_timeout},
 };
 use duva_client::{
     broker::BrokerMessage,
@@ -35,7 +35,9 @@ async fn main() {
         // and the rest are arguments
         let (cmd, args) = separate_command_and_args(args);
 
-        match extract_action(cmd, &args) {
+        let action = extract_write_timeout(cmd, &args)
+            .and_then(|(action, action_args, _)| extract_action(action, action_args));
+        match action {
             Ok(input) => {
                 let (tx, rx) = oneshot::channel();
                 let input = Input::new(input, tx);
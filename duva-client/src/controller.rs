@@ -45,8 +45,17 @@ impl<T> ClientController<T> {
             | Config { .. }
             | Info
             | ClusterForget { .. }
+            | ClusterFailover { .. }
+            | ClusterPromote { .. }
+            | ClusterDemote { .. }
             | Role
             | ReplicaOf { .. }
+            | LIndex { .. }
+            | HGet { .. }
+            | ZIncrBy { .. }
+            | ZScore { .. }
+            | XAdd { .. }
+            | XGroupCreate { .. }
             | ClusterInfo => match query_io {
                 QueryIO::Null => Response::Null,
                 QueryIO::SimpleString(value) => Response::String(value),
@@ -76,6 +85,54 @@ impl<T> ClientController<T> {
                     _ => Response::FormatError,
                 }
             },
+            LPush { .. }
+            | RPush { .. }
+            | LLen { .. }
+            | HSet { .. }
+            | HDel { .. }
+            | HIncrBy { .. }
+            | SAdd { .. }
+            | SRem { .. }
+            | SIsMember { .. }
+            | SCard { .. }
+            | SInterStore { .. }
+            | SUnionStore { .. }
+            | SDiffStore { .. }
+            | ZAdd { .. }
+            | ZRem { .. }
+            | ZCard { .. }
+            | ZRank { .. }
+            | XLen { .. }
+            | XAck { .. } => match query_io {
+                QueryIO::Null => Response::Null,
+                QueryIO::SimpleString(value) => match value.parse::<i64>() {
+                    Ok(int) => Response::Integer(int),
+                    Err(_) => Response::FormatError,
+                },
+                QueryIO::Err(value) => Response::Error(value),
+                _ => Response::FormatError,
+            },
+            LPop { .. }
+            | RPop { .. }
+            | LRange { .. }
+            | HMGet { .. }
+            | HGetAll { .. }
+            | SMembers { .. }
+            | SInter { .. }
+            | SUnion { .. }
+            | SDiff { .. }
+            | ZRange { .. }
+            | ZRangeByScore { .. }
+            | XRange { .. }
+            | XRead { .. }
+            | XReadGroup { .. }
+            | XPending { .. } => match query_io {
+                QueryIO::Null => Response::Null,
+                QueryIO::BulkString(value) => Response::String(value),
+                QueryIO::Array(values) => render_numbered(values),
+                QueryIO::Err(value) => Response::Error(value),
+                _ => Response::FormatError,
+            },
             Save => {
                 let QueryIO::Null = query_io else {
                     return Response::FormatError;
@@ -91,14 +148,7 @@ impl<T> ClientController<T> {
                 let QueryIO::Array(value) = query_io else {
                     return Response::FormatError;
                 };
-                let mut keys = Vec::new();
-                for (i, item) in value.into_iter().enumerate() {
-                    let QueryIO::BulkString(value) = item else {
-                        return Response::FormatError;
-                    };
-                    keys.push(Response::String(format!("{i}) \"{value}\"")));
-                }
-                Response::Array(keys)
+                render_numbered(value)
             },
             ClusterNodes => {
                 let QueryIO::Array(value) = query_io else {
@@ -122,6 +172,39 @@ impl<T> ClientController<T> {
     }
 }
 
+fn render_numbered(values: Vec<QueryIO>) -> Response {
+    match numbered_lines(values) {
+        Some(lines) => Response::Array(lines.into_iter().map(Response::String).collect()),
+        None => Response::FormatError,
+    }
+}
+
+// Nested arrays are indented under the number of the item they are, as redis-cli does
+fn numbered_lines(values: Vec<QueryIO>) -> Option<Vec<String>> {
+    let mut lines = Vec::new();
+    for (i, item) in values.into_iter().enumerate() {
+        let number = format!("{i}) ");
+        match item {
+            QueryIO::BulkString(value) => lines.push(format!("{number}\"{value}\"")),
+            QueryIO::Null => lines.push(format!("{number}(nil)")),
+            // integers in an array are sent as simple strings
+            QueryIO::SimpleString(value) => lines.push(format!("{number}(integer) {value}")),
+            QueryIO::Array(values) if values.is_empty() => {
+                lines.push(format!("{number}(empty array)"))
+            },
+            QueryIO::Array(values) => {
+                let indent = " ".repeat(number.len());
+                for (j, line) in numbered_lines(values)?.into_iter().enumerate() {
+                    let prefix = if j == 0 { &number } else { &indent };
+                    lines.push(format!("{prefix}{line}"));
+                }
+            },
+            _ => return None,
+        }
+    }
+    Some(lines)
+}
+
 enum Response {
     Null,
     FormatError,
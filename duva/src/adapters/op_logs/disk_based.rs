@@ -1,84 +1,429 @@
-#![allow(dead_code, unused_variables)]
+use crate::domains::operation_logs::WriteOperation;
 use crate::domains::operation_logs::interfaces::TWriteAheadLog;
-use crate::domains::operation_logs::{WriteOperation, WriteRequest};
+use crate::domains::query_parsers::query_io::{REPLICATE_PREFIX, SERDE_CONFIG};
 use anyhow::Result;
-use bytes::BytesMut;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use tokio::fs::{File, OpenOptions};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncWriteExt, BufWriter};
 
-/// A local write-ahead-log file (WAL) implementation.
+const MANIFEST_FILE_NAME: &str = "manifest";
+const MANIFEST_TMP_FILE_NAME: &str = "manifest.tmp";
+const LEGACY_FILE_SUFFIX: &str = ".legacy";
+const DEFAULT_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A local write-ahead-log (WAL) implementation split into segment files.
+///
+/// The WAL lives in its own directory. A manifest lists the live segments in log order,
+/// and every change to that list (rolling, overwriting or compacting) takes effect by atomically replacing the manifest.
 pub struct FileOpLogs {
-    /// The file path where the WAL data is stored.
+    /// The directory where the manifest and segments are stored.
     path: PathBuf,
-    /// A buffered writer for the underlying file.
+    /// Live segments in log order. The last one is the active segment receiving appends.
+    segments: Vec<Segment>,
+    /// A buffered writer for the active segment.
     writer: BufWriter<File>,
+    /// Size in bytes after which the active segment is sealed and a new one is started.
+    max_segment_size: u64,
+}
+
+/// A single segment file and the offset index of the entries it holds.
+struct Segment {
+    id: u64,
+    path: PathBuf,
+    /// A read handle used for positional reads of indexed entries.
+    reader: std::fs::File,
+    /// Offset index of every entry in the segment, ordered by log index.
+    index: Vec<LogPosition>,
+    /// Byte length of the valid portion of the segment.
+    size: u64,
+}
+
+/// Location of a single serialized `WriteOperation` within a segment file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogPosition {
+    log_index: u64,
+    offset: u64,
+    len: u64,
+}
+
+#[derive(Debug, Default, bincode::Encode, bincode::Decode)]
+struct Manifest {
+    segment_ids: Vec<u64>,
 }
 
 impl FileOpLogs {
-    /// Creates a new `LocalAof` by opening the specified `path`.
+    /// Opens the WAL directory at `path` with the default segment size, creating it if needed.
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be created or opened.
+    /// Returns an error if the directory cannot be created or opened.
     pub async fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path_buf = path.as_ref().to_path_buf();
-        let file = OpenOptions::new().create(true).append(true).read(true).open(&path_buf).await?;
+        Self::with_segment_size(path, DEFAULT_SEGMENT_SIZE).await
+    }
 
-        Ok(Self { path: path_buf, writer: BufWriter::new(file) })
+    /// Opens the WAL directory at `path`, starting a new segment whenever the active one exceeds `max_segment_size` bytes.
+    ///
+    /// Segments are scanned to rebuild the offset index. An incomplete record at the tail of a segment,
+    /// left behind by a crash in the middle of a write, is cut off.
+    /// Segment files that are not listed in the manifest are leftovers of an interrupted change and are removed.
+    /// A WAL written as a single file by earlier versions becomes the first segment.
+    pub async fn with_segment_size<P: AsRef<Path>>(path: P, max_segment_size: u64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Self::migrate_single_file(&path).await?;
+        match tokio::fs::create_dir(&path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::AlreadyExists => return Err(err.into()),
+            _ => {},
+        }
+
+        let mut segment_ids = Self::read_manifest(&path).await?.segment_ids;
+        Self::remove_orphans(&path, &segment_ids).await?;
+        if segment_ids.is_empty() {
+            segment_ids.push(0);
+            Self::write_manifest(&path, &segment_ids).await?;
+        }
+
+        let mut segments = Vec::with_capacity(segment_ids.len());
+        let mut active = None;
+        for id in segment_ids {
+            let (segment, file) = Segment::open(&path, id).await?;
+            segments.push(segment);
+            active = Some(file);
+        }
+        let writer = BufWriter::new(active.expect("manifest lists at least one segment"));
+
+        Ok(Self { path, segments, writer, max_segment_size })
+    }
+
+    /// Turns a single-file WAL at `path` into a directory whose only segment holds its entries.
+    ///
+    /// The file is first moved aside, and is moved in as the first segment only after the manifest lists it,
+    /// so a crash at any step is picked up again by the next start.
+    async fn migrate_single_file(path: &Path) -> Result<()> {
+        let mut legacy_path = path.to_path_buf().into_os_string();
+        legacy_path.push(LEGACY_FILE_SUFFIX);
+        let legacy_path = PathBuf::from(legacy_path);
+
+        if tokio::fs::metadata(path).await.is_ok_and(|meta| meta.is_file()) {
+            println!("[INFO] Migrating single-file WAL {} to segments", path.display());
+            tokio::fs::rename(path, &legacy_path).await?;
+        }
+        if !tokio::fs::try_exists(&legacy_path).await? {
+            return Ok(());
+        }
+
+        match tokio::fs::create_dir(path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::AlreadyExists => return Err(err.into()),
+            _ => {},
+        }
+        Self::write_manifest(path, &[0]).await?;
+        tokio::fs::rename(&legacy_path, path.join(segment_file_name(0))).await?;
+        File::open(path).await?.sync_all().await?;
+        Ok(())
+    }
+
+    async fn read_manifest(dir: &Path) -> Result<Manifest> {
+        match tokio::fs::read(dir.join(MANIFEST_FILE_NAME)).await {
+            Ok(bytes) => Ok(bincode::decode_from_slice(&bytes, SERDE_CONFIG)?.0),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn write_manifest(dir: &Path, segment_ids: &[u64]) -> Result<()> {
+        let manifest = Manifest { segment_ids: segment_ids.to_vec() };
+        let tmp_path = dir.join(MANIFEST_TMP_FILE_NAME);
+
+        let mut tmp = File::create(&tmp_path).await?;
+        tmp.write_all(&bincode::encode_to_vec(&manifest, SERDE_CONFIG)?).await?;
+        tmp.sync_all().await?;
+        drop(tmp);
+
+        tokio::fs::rename(&tmp_path, dir.join(MANIFEST_FILE_NAME)).await?;
+        File::open(dir).await?.sync_all().await?;
+        Ok(())
+    }
+
+    async fn remove_orphans(dir: &Path, live_ids: &[u64]) -> Result<()> {
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            let is_orphan = match parse_segment_id(&file_name) {
+                Some(id) => !live_ids.contains(&id),
+                None => file_name == MANIFEST_TMP_FILE_NAME,
+            };
+            if is_orphan {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+        Ok(())
     }
+
+    fn segment_ids(&self) -> Vec<u64> {
+        self.segments.iter().map(|s| s.id).collect()
+    }
+
+    fn active(&self) -> &Segment {
+        self.segments.last().expect("at least one segment is always kept")
+    }
+
+    fn active_mut(&mut self) -> &mut Segment {
+        self.segments.last_mut().expect("at least one segment is always kept")
+    }
+
+    /// Writes `ops` to the active segment, sealing it and starting a new one whenever it grows past the size limit.
+    /// New segments are not listed in the manifest; callers are responsible for recording them.
+    async fn write_entries(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
+        for op in ops {
+            let log_index = op.log_index;
+            let bytes = op.serialize();
+            let len = bytes.len() as u64;
+
+            let active = self.active();
+            if active.size > 0 && active.size + len > self.max_segment_size {
+                self.roll().await?;
+            }
+
+            self.writer.write_all(&bytes).await?;
+            let active = self.active_mut();
+            active.index.push(LogPosition { log_index, offset: active.size, len });
+            active.size += len;
+        }
+        self.fsync().await
+    }
+
+    async fn roll(&mut self) -> Result<()> {
+        // Seal the current segment before moving on
+        self.fsync().await?;
+
+        let (segment, file) = Segment::open(&self.path, self.active().id + 1).await?;
+        self.segments.push(segment);
+        self.writer = BufWriter::new(file);
+        Ok(())
+    }
+
+    async fn append_entries(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
+        let segment_count = self.segments.len();
+        self.write_entries(ops).await?;
+        if self.segments.len() != segment_count {
+            Self::write_manifest(&self.path, &self.segment_ids()).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_segments(segments: Vec<Segment>) {
+        for segment in segments {
+            if let Err(err) = tokio::fs::remove_file(&segment.path).await {
+                eprintln!(
+                    "[ERROR] failed to remove WAL segment {}: {:?}",
+                    segment.path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    async fn try_truncate_after(&mut self, log_index: u64) -> Result<()> {
+        // Whole segments that start after `log_index` go away, but one segment is always kept
+        let keep = self
+            .segments
+            .iter()
+            .position(|s| s.first_index().is_some_and(|first| first > log_index))
+            .unwrap_or(self.segments.len())
+            .max(1);
+        if keep < self.segments.len() {
+            Self::write_manifest(&self.path, &self.segment_ids()[..keep]).await?;
+            let removed = self.segments.split_off(keep);
+            self.writer = BufWriter::new(Segment::open_for_append(&self.active().path).await?);
+            Self::remove_segments(removed).await;
+        }
+
+        let active = self.active();
+        let keep_entries = active.index.partition_point(|p| p.log_index <= log_index);
+        let Some(first_removed) = active.index.get(keep_entries) else {
+            return Ok(());
+        };
+        let new_size = first_removed.offset;
+
+        self.writer.flush().await?;
+        let file = self.writer.get_mut();
+        file.set_len(new_size).await?;
+        file.sync_all().await?;
+
+        let active = self.active_mut();
+        active.index.truncate(keep_entries);
+        active.size = new_size;
+        Ok(())
+    }
+}
+
+impl Segment {
+    /// Opens the segment `id` in `dir`, creating it if needed, and returns it with an append handle.
+    async fn open(dir: &Path, id: u64) -> Result<(Self, File)> {
+        let path = dir.join(segment_file_name(id));
+        let file = Self::open_for_append(&path).await?;
+
+        let bytes = tokio::fs::read(&path).await?;
+        let (index, size) = Self::build_index(&bytes);
+        if size < bytes.len() as u64 {
+            println!(
+                "[WARN] Discarding {} bytes of incomplete entries at the tail of {}",
+                bytes.len() as u64 - size,
+                path.display()
+            );
+            file.set_len(size).await?;
+            file.sync_all().await?;
+        }
+
+        let reader = std::fs::File::open(&path)?;
+        Ok((Self { id, path, reader, index, size }, file))
+    }
+
+    async fn open_for_append(path: &Path) -> Result<File> {
+        Ok(OpenOptions::new().create(true).append(true).read(true).open(path).await?)
+    }
+
+    /// Scans serialized entries and returns their positions along with the length of the valid prefix.
+    fn build_index(bytes: &[u8]) -> (Vec<LogPosition>, u64) {
+        let mut index = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let Ok((op, len)) = decode_entry(&bytes[offset..]) else {
+                break;
+            };
+            index.push(LogPosition {
+                log_index: op.log_index,
+                offset: offset as u64,
+                len: len as u64,
+            });
+            offset += len;
+        }
+        (index, offset as u64)
+    }
+
+    fn first_index(&self) -> Option<u64> {
+        self.index.first().map(|p| p.log_index)
+    }
+
+    fn last_index(&self) -> Option<u64> {
+        self.index.last().map(|p| p.log_index)
+    }
+
+    /// Finds the slot of `log_index` in the offset index.
+    fn position_of(&self, log_index: u64) -> Option<usize> {
+        let first = self.first_index()?;
+
+        // Entries are normally contiguous, so the slot can be computed directly.
+        let guess = log_index.checked_sub(first)? as usize;
+        if self.index.get(guess).is_some_and(|p| p.log_index == log_index) {
+            return Some(guess);
+        }
+        self.index.binary_search_by_key(&log_index, |p| p.log_index).ok()
+    }
+
+    fn range(&self, start_exclusive: u64, end_inclusive: u64) -> Result<Vec<WriteOperation>> {
+        let from = self.index.partition_point(|p| p.log_index <= start_exclusive);
+        let to = self.index.partition_point(|p| p.log_index <= end_inclusive);
+        if from >= to {
+            return Ok(vec![]);
+        }
+        self.read_entries(&self.index[from..to])
+    }
+
+    /// Reads and decodes the given contiguous run of entries with a single read.
+    fn read_entries(&self, positions: &[LogPosition]) -> Result<Vec<WriteOperation>> {
+        let (Some(first), Some(last)) = (positions.first(), positions.last()) else {
+            return Ok(vec![]);
+        };
+
+        let mut buf = vec![0; (last.offset + last.len - first.offset) as usize];
+        let mut reader = &self.reader;
+        reader.seek(SeekFrom::Start(first.offset))?;
+        reader.read_exact(&mut buf)?;
+
+        let mut ops = Vec::with_capacity(positions.len());
+        let mut cursor = 0;
+        while cursor < buf.len() {
+            let (op, len) = decode_entry(&buf[cursor..])?;
+            ops.push(op);
+            cursor += len;
+        }
+        Ok(ops)
+    }
+}
+
+fn segment_file_name(id: u64) -> String {
+    format!("segment-{:020}.log", id)
+}
+
+fn parse_segment_id(file_name: &str) -> Option<u64> {
+    file_name.strip_prefix("segment-")?.strip_suffix(".log")?.parse().ok()
+}
+
+/// Decodes a single serialized `WriteOperation`, returning it with the number of bytes it spans.
+fn decode_entry(bytes: &[u8]) -> Result<(WriteOperation, usize)> {
+    let Some((&prefix, body)) = bytes.split_first() else {
+        return Err(anyhow::anyhow!("empty entry"));
+    };
+    if prefix as char != REPLICATE_PREFIX {
+        return Err(anyhow::anyhow!("unexpected entry prefix: {}", prefix));
+    }
+    let (op, len): (WriteOperation, usize) = bincode::decode_from_slice(body, SERDE_CONFIG)?;
+    Ok((op, len + 1))
 }
 
 impl TWriteAheadLog for FileOpLogs {
-    /// Appends a single `WriteOperation` to the file.
+    /// Appends a single `WriteOperation` to the active segment.
     ///
     /// # Errors
     ///
     /// Returns an error if writing to or syncing the underlying file fails.
     async fn append(&mut self, op: WriteOperation) -> Result<()> {
-        self.writer.write_all(&op.serialize()).await?;
-        self.fsync().await?;
-
-        Ok(())
+        self.append_entries(vec![op]).await
     }
 
     async fn append_many(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
-        // merge all operations into a single buffer
-        let mut buf = BytesMut::new();
-        for op in ops {
-            buf.extend_from_slice(&op.clone().serialize());
+        if ops.is_empty() {
+            return Ok(());
         }
-
-        // write all operations to the file
-        self.writer.write_all(&buf).await?;
-
-        Ok(())
+        self.append_entries(ops).await
     }
 
     fn range(&self, start_exclusive: u64, end_inclusive: u64) -> Vec<WriteOperation> {
-        todo!()
+        let mut ops = Vec::new();
+        for segment in &self.segments {
+            let (Some(first), Some(last)) = (segment.first_index(), segment.last_index()) else {
+                continue;
+            };
+            if last <= start_exclusive || first > end_inclusive {
+                continue;
+            }
+
+            match segment.range(start_exclusive, end_inclusive) {
+                Ok(entries) => ops.extend(entries),
+                Err(err) => {
+                    eprintln!("[ERROR] failed to read WAL range: {:?}", err);
+                    return vec![];
+                },
+            }
+        }
+        ops
     }
 
     /// Replays all existing operations in the WAL, invoking a callback for each.
     ///
     /// # Errors
     ///
-    /// Returns an error if reading or deserializing from the file fails.
+    /// Returns an error if reading or deserializing from a segment fails.
     async fn replay<F>(&mut self, mut f: F) -> Result<()>
     where
         F: FnMut(WriteOperation) + Send,
     {
-        let file = OpenOptions::new().read(true).open(&self.path).await?;
-        let mut reader = BufReader::new(file);
-
-        let mut buf = Vec::new();
-
-        reader.read_to_end(&mut buf).await?;
-
-        let bytes = BytesMut::from(&buf[..]);
-
-        for op in WriteRequest::deserialize(bytes)? {
-            f(op);
+        for segment in &self.segments {
+            for op in segment.read_entries(&segment.index)? {
+                f(op);
+            }
         }
         Ok(())
     }
@@ -99,31 +444,89 @@ impl TWriteAheadLog for FileOpLogs {
         Ok(())
     }
 
-    // TODO create tmp file then rename
+    /// Replaces the whole log with `ops`.
+    ///
+    /// The new entries are written to fresh segments and only take effect once the manifest points at them,
+    /// so a crash leaves either the old or the new log in place but never a mix of both.
     async fn overwrite(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
-        todo!()
+        let (segment, file) = Segment::open(&self.path, self.active().id + 1).await?;
+        let previous = std::mem::replace(&mut self.segments, vec![segment]);
+        let previous_writer = std::mem::replace(&mut self.writer, BufWriter::new(file));
+
+        let result = match self.write_entries(ops).await {
+            Ok(()) => Self::write_manifest(&self.path, &self.segment_ids()).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            let abandoned = std::mem::replace(&mut self.segments, previous);
+            self.writer = previous_writer;
+            Self::remove_segments(abandoned).await;
+            return Err(err);
+        }
+
+        Self::remove_segments(previous).await;
+        Ok(())
     }
 
     async fn read_at(&self, prev_log_index: u64) -> Option<WriteOperation> {
-        todo!()
+        let segment = self.segments.iter().find(|s| {
+            s.first_index().is_some_and(|first| first <= prev_log_index)
+                && s.last_index().is_some_and(|last| prev_log_index <= last)
+        })?;
+        let slot = segment.position_of(prev_log_index)?;
+        segment.read_entries(&segment.index[slot..=slot]).ok()?.pop()
     }
 
     fn log_start_index(&self) -> u64 {
-        todo!()
+        self.segments.iter().find_map(|s| s.first_index()).unwrap_or(0)
     }
 
     fn is_empty(&self) -> bool {
-        todo!()
+        self.segments.iter().all(|s| s.index.is_empty())
+    }
+
+    async fn truncate_after(&mut self, log_index: u64) {
+        if let Err(err) = self.try_truncate_after(log_index).await {
+            eprintln!("[ERROR] failed to truncate WAL: {:?}", err);
+        }
     }
 
-    async fn truncate_after(&mut self, log_index: u64) {}
+    /// Deletes sealed segments whose entries are all at or below `log_index`. The active segment is always kept.
+    async fn compact_up_to(&mut self, log_index: u64) -> Result<()> {
+        let removable = self.segments[..self.segments.len() - 1]
+            .iter()
+            .take_while(|s| s.last_index().is_none_or(|last| last <= log_index))
+            .count();
+        if removable == 0 {
+            return Ok(());
+        }
+
+        Self::write_manifest(&self.path, &self.segment_ids()[removable..]).await?;
+        let removed = self.segments.drain(..removable).collect();
+        Self::remove_segments(removed).await;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domains::operation_logs::WriteRequest;
     use anyhow::Result;
     use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+
+    fn first_segment(path: &Path) -> PathBuf {
+        path.join(segment_file_name(0))
+    }
+
+    fn write_op(key: &str, log_index: u64, term: u64) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Set { key: key.into(), value: key.into() },
+            log_index,
+            term,
+        }
+    }
 
     #[tokio::test]
     async fn test_new_creates_aof() -> Result<()> {
@@ -144,7 +547,7 @@ mod tests {
 
         assert!(!path.exists());
 
-        tokio::fs::File::create(&path).await?;
+        tokio::fs::create_dir(&path).await?;
         assert!(path.exists());
 
         assert!(FileOpLogs::new(&path).await.is_ok());
@@ -164,6 +567,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_new_migrates_single_file_wal() -> Result<()> {
+        // GIVEN a WAL written as a single file
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+        let ops = [write_op("a", 1, 0), write_op("b", 2, 1)];
+        let bytes: Vec<u8> = ops.iter().flat_map(|op| op.clone().serialize()).collect();
+        tokio::fs::write(&path, bytes).await?;
+
+        // WHEN
+        let mut wal = FileOpLogs::new(&path).await?;
+        wal.append(write_op("c", 3, 1)).await?;
+        drop(wal);
+
+        // THEN
+        assert!(path.is_dir());
+        assert!(!dir.path().join("local.wal.legacy").exists());
+        let wal = FileOpLogs::new(&path).await?;
+        assert_eq!(wal.range(0, 3), vec![ops[0].clone(), ops[1].clone(), write_op("c", 3, 1)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_resumes_interrupted_migration() -> Result<()> {
+        // GIVEN a migration that crashed after moving the file aside and writing the manifest
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+        let op = write_op("a", 1, 0);
+        tokio::fs::write(dir.path().join("local.wal.legacy"), op.clone().serialize()).await?;
+        tokio::fs::create_dir(&path).await?;
+        FileOpLogs::write_manifest(&path, &[0]).await?;
+
+        // WHEN
+        let wal = FileOpLogs::new(&path).await?;
+
+        // THEN
+        assert_eq!(wal.range(0, 1), vec![op]);
+        assert!(!dir.path().join("local.wal.legacy").exists());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_append_stores_to_disk() -> Result<()> {
         let dir = TempDir::new()?;
@@ -176,7 +620,7 @@ mod tests {
         wal.append(write_op).await?;
         drop(wal);
 
-        let mut file = tokio::fs::File::open(&path).await?;
+        let mut file = tokio::fs::File::open(first_segment(&path)).await?;
         let mut buf = Vec::new();
         file.read_to_end(&mut buf).await?;
 
@@ -187,6 +631,7 @@ mod tests {
             WriteRequest::Set { key, .. } => key,
             WriteRequest::SetWithExpiry { key, .. } => key,
             WriteRequest::Delete { keys: key } => key[0].clone(),
+            _ => unreachable!(),
         };
         assert_eq!(key, "foo");
 
@@ -258,7 +703,221 @@ mod tests {
     }
 
     #[tokio::test]
-    #[ignore = "This is desired behavior. However, currently deserialize fails if any part of the file is corrupted."]
+    async fn test_range_and_read_at() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        let mut wal = FileOpLogs::new(&path).await?;
+        assert!(wal.is_empty());
+        assert_eq!(wal.log_start_index(), 0);
+
+        wal.append(write_op("a", 1, 0)).await?;
+        wal.append_many(vec![write_op("b", 2, 0), write_op("c", 3, 1), write_op("d", 4, 1)])
+            .await?;
+
+        assert!(!wal.is_empty());
+        assert_eq!(wal.log_start_index(), 1);
+        assert_eq!(wal.range(1, 3), vec![write_op("b", 2, 0), write_op("c", 3, 1)]);
+        assert_eq!(wal.range(0, u64::MAX).len(), 4);
+        assert!(wal.range(4, 10).is_empty());
+        assert_eq!(wal.read_at(3).await, Some(write_op("c", 3, 1)));
+        assert_eq!(wal.read_at(5).await, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reopen_rebuilds_index() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        {
+            let mut wal = FileOpLogs::new(&path).await?;
+            wal.append_many(vec![write_op("a", 5, 1), write_op("b", 6, 1)]).await?;
+        }
+
+        let mut wal = FileOpLogs::new(&path).await?;
+        assert_eq!(wal.log_start_index(), 5);
+        assert_eq!(wal.read_at(6).await, Some(write_op("b", 6, 1)));
+
+        wal.append(write_op("c", 7, 2)).await?;
+        assert_eq!(wal.range(5, 7), vec![write_op("b", 6, 1), write_op("c", 7, 2)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_truncate_after_removes_entries_from_disk() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        {
+            let mut wal = FileOpLogs::new(&path).await?;
+            wal.append_many(vec![write_op("a", 1, 0), write_op("b", 2, 0), write_op("c", 3, 1)])
+                .await?;
+
+            wal.truncate_after(1).await;
+            assert_eq!(wal.read_at(2).await, None);
+
+            // appends must follow the last remaining entry
+            wal.append(write_op("d", 2, 2)).await?;
+        }
+
+        let mut wal = FileOpLogs::new(&path).await?;
+        let mut ops = Vec::new();
+        wal.replay(|op| ops.push(op)).await?;
+        assert_eq!(ops, vec![write_op("a", 1, 0), write_op("d", 2, 2)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_replaces_log() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        {
+            let mut wal = FileOpLogs::new(&path).await?;
+            wal.append_many(vec![write_op("a", 1, 0), write_op("b", 2, 0)]).await?;
+
+            wal.overwrite(vec![write_op("x", 10, 3), write_op("y", 11, 3)]).await?;
+            assert_eq!(wal.log_start_index(), 10);
+            assert_eq!(wal.read_at(1).await, None);
+
+            wal.append(write_op("z", 12, 3)).await?;
+        }
+        assert!(!path.join(MANIFEST_TMP_FILE_NAME).exists());
+        assert!(!first_segment(&path).exists());
+
+        let wal = FileOpLogs::new(&path).await?;
+        assert_eq!(
+            wal.range(0, u64::MAX),
+            vec![write_op("x", 10, 3), write_op("y", 11, 3), write_op("z", 12, 3)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_discards_incomplete_tail() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        {
+            let mut wal = FileOpLogs::new(&path).await?;
+            wal.append_many(vec![write_op("a", 1, 0), write_op("b", 2, 0)]).await?;
+        }
+
+        // Simulate a torn write by cutting the last entry short.
+        let len = tokio::fs::metadata(first_segment(&path)).await?.len();
+        OpenOptions::new().write(true).open(first_segment(&path)).await?.set_len(len - 2).await?;
+
+        let mut wal = FileOpLogs::new(&path).await?;
+        assert_eq!(wal.range(0, u64::MAX), vec![write_op("a", 1, 0)]);
+
+        wal.append(write_op("c", 2, 1)).await?;
+        let wal = FileOpLogs::new(&path).await?;
+        assert_eq!(wal.range(0, u64::MAX), vec![write_op("a", 1, 0), write_op("c", 2, 1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_segments_roll_when_size_limit_is_reached() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+        let entry_size = write_op("a", 1, 0).serialize().len() as u64;
+
+        {
+            // two entries per segment
+            let mut wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+            for i in 1..=5 {
+                wal.append(write_op("a", i, 0)).await?;
+            }
+            assert_eq!(wal.segment_ids(), vec![0, 1, 2]);
+            assert_eq!(wal.range(1, 4).len(), 3);
+            assert_eq!(wal.read_at(3).await, Some(write_op("a", 3, 0)));
+        }
+
+        let wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+        assert_eq!(wal.segment_ids(), vec![0, 1, 2]);
+        assert_eq!(wal.range(0, u64::MAX).len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compact_up_to_removes_covered_segments() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+        let entry_size = write_op("a", 1, 0).serialize().len() as u64;
+
+        let mut wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+        for i in 1..=5 {
+            wal.append(write_op("a", i, 0)).await?;
+        }
+
+        // index 3 sits in the middle of the second segment, which has to be kept
+        wal.compact_up_to(3).await?;
+        assert_eq!(wal.segment_ids(), vec![1, 2]);
+        assert_eq!(wal.log_start_index(), 3);
+        assert!(!first_segment(&path).exists());
+
+        // the active segment is never removed
+        wal.compact_up_to(100).await?;
+        assert_eq!(wal.segment_ids(), vec![2]);
+        assert_eq!(wal.log_start_index(), 5);
+        assert!(!wal.is_empty());
+
+        let wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+        assert_eq!(wal.range(0, u64::MAX), vec![write_op("a", 5, 0)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_truncate_after_removes_later_segments() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+        let entry_size = write_op("a", 1, 0).serialize().len() as u64;
+
+        let mut wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+        for i in 1..=5 {
+            wal.append(write_op("a", i, 0)).await?;
+        }
+
+        wal.truncate_after(3).await;
+        assert_eq!(wal.segment_ids(), vec![0, 1]);
+        assert_eq!(wal.range(0, u64::MAX).len(), 3);
+
+        wal.append(write_op("b", 4, 1)).await?;
+        let wal = FileOpLogs::with_segment_size(&path, entry_size * 2).await?;
+        assert_eq!(wal.read_at(4).await, Some(write_op("b", 4, 1)));
+        assert_eq!(wal.read_at(5).await, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_removes_segments_missing_from_manifest() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("local.wal");
+
+        {
+            let mut wal = FileOpLogs::new(&path).await?;
+            wal.append(write_op("a", 1, 0)).await?;
+        }
+        // left behind by an overwrite that crashed before the manifest was replaced
+        tokio::fs::write(path.join(segment_file_name(7)), write_op("x", 9, 9).serialize()).await?;
+
+        let wal = FileOpLogs::new(&path).await?;
+        assert_eq!(wal.range(0, u64::MAX), vec![write_op("a", 1, 0)]);
+        assert!(!path.join(segment_file_name(7)).exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
     async fn test_replay_partial_data() -> Result<()> {
         let dir = TempDir::new()?;
         let path = dir.path().join("local.wal");
@@ -288,28 +947,25 @@ mod tests {
 
         // Corrupt file content by truncating to the first half.
         // We should only have one complete op.
-        {
-            let mut file = OpenOptions::new().read(true).open(&path).await?;
+        let torn_len = {
+            let mut file = OpenOptions::new().read(true).open(first_segment(&path)).await?;
             let mut data = Vec::new();
             file.read_to_end(&mut data).await?;
 
             data.truncate(data.len() / 2);
 
-            let mut file = OpenOptions::new().write(true).truncate(true).open(&path).await?;
+            let mut file =
+                OpenOptions::new().write(true).truncate(true).open(first_segment(&path)).await?;
             file.write_all(&data).await?;
-        }
+            data.len() as u64
+        };
 
+        // Opening drops the partial op at the tail, so the complete one still replays
         let mut wal = FileOpLogs::new(&path).await?;
         let mut ops = Vec::new();
+        wal.replay(|op| ops.push(op)).await?;
 
-        assert!(
-            wal.replay(|op| {
-                ops.push(op);
-            })
-            .await
-            .is_err()
-        );
-
+        assert!(tokio::fs::metadata(first_segment(&path)).await?.len() < torn_len);
         assert_eq!(ops.len(), 1);
         assert_eq!(
             ops[0],
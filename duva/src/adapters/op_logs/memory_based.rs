@@ -61,4 +61,10 @@ impl TWriteAheadLog for MemoryOpLogs {
     async fn truncate_after(&mut self, log_index: u64) {
         self.writer.retain(|op| op.log_index <= log_index);
     }
+
+    async fn compact_up_to(&mut self, log_index: u64) -> Result<()> {
+        let last_log_index = self.writer.last().map(|op| op.log_index).unwrap_or(0);
+        self.writer.retain(|op| op.log_index > log_index || op.log_index == last_log_index);
+        Ok(())
+    }
 }
@@ -1,9 +1,12 @@
-use super::cache_objects::{CacheEntry, CacheValue};
+use super::cache_objects::{CacheEntry, CacheValue, ListEnd, WRONGTYPE_ERR};
 use super::command::CacheCommand;
-use crate::domains::caches::read_queue::ReadQueue;
+use super::sorted_set::{Score, SortedSet};
+use super::stream::{GroupReadFrom, Stream, StreamEntry, StreamId, StreamIdSpec, no_group_err};
+use crate::domains::caches::read_queue::{ReadFn, ReadQueue};
 use crate::domains::query_parsers::QueryIO;
 use crate::make_smart_pointer;
-use std::collections::HashMap;
+use anyhow::Context;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use tokio::sync::mpsc::{self, Sender};
@@ -68,7 +71,11 @@ impl CacheActor {
         }
     }
     pub(crate) fn get(&self, key: &str, callback: oneshot::Sender<Option<CacheValue>>) {
-        let _ = callback.send(self.cache.get(key).map(|v| v.clone()));
+        let _ = callback.send(self.cache.get(key).cloned());
+    }
+
+    pub(crate) fn read(&self, key: &str, read: ReadFn) {
+        read(self.cache.get(key));
     }
 
     pub(crate) fn set(&mut self, cache_entry: CacheEntry) {
@@ -80,7 +87,274 @@ impl CacheActor {
                 self.cache.keys_with_expiry += 1;
                 self.cache.insert(key.clone(), CacheValue::ValueWithExpiry { value, expiry });
             },
+            CacheEntry::KeyList { key, list } => {
+                self.cache.insert(key, CacheValue::List(list));
+            },
+            CacheEntry::KeyHash { key, hash } => {
+                self.cache.insert(key, CacheValue::Hash(hash));
+            },
+            CacheEntry::KeySet { key, set } => {
+                self.cache.insert(key, CacheValue::Set(set));
+            },
+            CacheEntry::KeySortedSet { key, zset } => {
+                self.cache.insert(key, CacheValue::SortedSet(zset));
+            },
+            CacheEntry::KeyStream { key, stream } => {
+                self.cache.insert(key, CacheValue::Stream(stream));
+            },
+        }
+    }
+
+    /// Pushes `values` one after another, creating the list if absent. Returns its new length.
+    pub(crate) fn push(
+        &mut self,
+        key: String,
+        values: Vec<String>,
+        end: ListEnd,
+    ) -> anyhow::Result<usize> {
+        let CacheValue::List(list) =
+            self.cache.entry(key).or_insert_with(|| CacheValue::List(VecDeque::new()))
+        else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        for value in values {
+            match end {
+                ListEnd::Head => list.push_front(value),
+                ListEnd::Tail => list.push_back(value),
+            }
+        }
+        Ok(list.len())
+    }
+
+    /// Pops up to `count` elements. A list left empty is removed along with its key.
+    pub(crate) fn pop(
+        &mut self,
+        key: &str,
+        count: usize,
+        end: ListEnd,
+    ) -> anyhow::Result<Vec<String>> {
+        let Some(value) = self.cache.get_mut(key) else {
+            return Ok(vec![]);
+        };
+        let CacheValue::List(list) = value else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        let popped = (0..count)
+            .map_while(|_| match end {
+                ListEnd::Head => list.pop_front(),
+                ListEnd::Tail => list.pop_back(),
+            })
+            .collect();
+        if list.is_empty() {
+            self.cache.remove(key);
+        }
+        Ok(popped)
+    }
+
+    fn hash_mut(&mut self, key: String) -> anyhow::Result<&mut HashMap<String, String>> {
+        match self.cache.entry(key).or_insert_with(|| CacheValue::Hash(HashMap::new())) {
+            CacheValue::Hash(hash) => Ok(hash),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+
+    /// Sets every field, creating the hash if absent. Returns how many fields are new.
+    pub(crate) fn hset(
+        &mut self,
+        key: String,
+        entries: Vec<(String, String)>,
+    ) -> anyhow::Result<usize> {
+        let hash = self.hash_mut(key)?;
+        let mut added = 0;
+        for (field, value) in entries {
+            if hash.insert(field, value).is_none() {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Removes the fields and returns how many existed. A hash left empty is removed along with its key.
+    pub(crate) fn hdel(&mut self, key: &str, fields: Vec<String>) -> anyhow::Result<usize> {
+        let Some(value) = self.cache.get_mut(key) else {
+            return Ok(0);
+        };
+        let CacheValue::Hash(hash) = value else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        let removed = fields.iter().filter(|field| hash.remove(*field).is_some()).count();
+        if hash.is_empty() {
+            self.cache.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// Adds `increment` to the integer in `field`, which counts as 0 when absent.
+    pub(crate) fn hincrby(
+        &mut self,
+        key: String,
+        field: String,
+        increment: i64,
+    ) -> anyhow::Result<i64> {
+        let hash = self.hash_mut(key)?;
+        let current = match hash.get(&field) {
+            Some(value) => value.parse::<i64>().context("ERR hash value is not an integer")?,
+            None => 0,
+        };
+        let updated =
+            current.checked_add(increment).context("ERR increment or decrement would overflow")?;
+        hash.insert(field, updated.to_string());
+        Ok(updated)
+    }
+
+    /// Adds the members, creating the set if absent. Returns how many were not in it yet.
+    pub(crate) fn sadd(&mut self, key: String, members: Vec<String>) -> anyhow::Result<usize> {
+        let CacheValue::Set(set) =
+            self.cache.entry(key).or_insert_with(|| CacheValue::Set(HashSet::new()))
+        else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        Ok(members.into_iter().filter(|member| set.insert(member.clone())).count())
+    }
+
+    /// Removes the members and returns how many were in it. A set left empty is removed along with its key.
+    pub(crate) fn srem(&mut self, key: &str, members: Vec<String>) -> anyhow::Result<usize> {
+        let Some(value) = self.cache.get_mut(key) else {
+            return Ok(0);
+        };
+        let CacheValue::Set(set) = value else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        let removed = members.iter().filter(|member| set.remove(*member)).count();
+        if set.is_empty() {
+            self.cache.remove(key);
+        }
+        Ok(removed)
+    }
+
+    fn sorted_set_mut(&mut self, key: String) -> anyhow::Result<&mut SortedSet> {
+        match self.cache.entry(key).or_insert_with(|| CacheValue::SortedSet(SortedSet::default())) {
+            CacheValue::SortedSet(zset) => Ok(zset),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+
+    /// Sets the scores of the members, creating the sorted set if absent. Returns how many were not in it yet.
+    pub(crate) fn zadd(
+        &mut self,
+        key: String,
+        entries: Vec<(Score, String)>,
+    ) -> anyhow::Result<usize> {
+        let zset = self.sorted_set_mut(key)?;
+        Ok(entries
+            .into_iter()
+            .filter(|(score, member)| zset.insert(member.clone(), *score))
+            .count())
+    }
+
+    /// Removes the members and returns how many were in it. A sorted set left empty is removed along with its key.
+    pub(crate) fn zrem(&mut self, key: &str, members: Vec<String>) -> anyhow::Result<usize> {
+        let Some(value) = self.cache.get_mut(key) else {
+            return Ok(0);
+        };
+        let CacheValue::SortedSet(zset) = value else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+        if zset.is_empty() {
+            self.cache.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// Adds `increment` to the score of `member`, which starts from 0 when absent.
+    pub(crate) fn zincrby(
+        &mut self,
+        key: String,
+        increment: Score,
+        member: String,
+    ) -> anyhow::Result<Score> {
+        let zset = self.sorted_set_mut(key)?;
+        let current = zset.score(&member).unwrap_or(Score::ZERO);
+        let updated =
+            current.incr(increment).context("ERR resulting score is not a number (NaN)")?;
+        zset.insert(member, updated);
+        Ok(updated)
+    }
+
+    // Unlike the other types, a stream is only created by XADD and XGROUP CREATE MKSTREAM
+    fn existing_stream_mut(&mut self, key: &str) -> anyhow::Result<Option<&mut Stream>> {
+        match self.cache.get_mut(key) {
+            Some(CacheValue::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+            None => Ok(None),
+        }
+    }
+
+    /// Appends an entry to the stream, creating it if absent.
+    pub(crate) fn xadd(
+        &mut self,
+        key: String,
+        id: StreamIdSpec,
+        fields: Vec<(String, String)>,
+    ) -> anyhow::Result<StreamId> {
+        if let Some(stream) = self.existing_stream_mut(&key)? {
+            return stream.add(id, fields);
+        }
+        // a rejected first entry leaves no stream behind
+        let mut stream = Stream::default();
+        let added = stream.add(id, fields)?;
+        self.cache.insert(key, CacheValue::Stream(stream));
+        Ok(added)
+    }
+
+    pub(crate) fn xgroup_create(
+        &mut self,
+        key: String,
+        group: String,
+        start: Option<StreamId>,
+        mkstream: bool,
+    ) -> anyhow::Result<()> {
+        if !mkstream && !self.cache.contains_key(&key) {
+            return Err(anyhow::anyhow!(
+                "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+            ));
         }
+        let CacheValue::Stream(stream) =
+            self.cache.entry(key).or_insert_with(|| CacheValue::Stream(Stream::default()))
+        else {
+            return Err(anyhow::anyhow!(WRONGTYPE_ERR));
+        };
+        stream.create_group(group, start)
+    }
+
+    pub(crate) fn xreadgroup(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: String,
+        from: GroupReadFrom,
+        count: Option<usize>,
+        now_ms: u64,
+    ) -> anyhow::Result<Vec<StreamEntry>> {
+        let Some(stream) = self.existing_stream_mut(key)? else {
+            return Err(no_group_err(group));
+        };
+        stream.read_group(group, consumer, from, count, now_ms)
+    }
+
+    pub(crate) fn xack(
+        &mut self,
+        key: &str,
+        group: &str,
+        ids: Vec<StreamId>,
+    ) -> anyhow::Result<usize> {
+        Ok(self.existing_stream_mut(key)?.map_or(0, |stream| stream.ack(group, &ids)))
+    }
+
+    /// Whether the stream at `key` has an entry after `after`.
+    pub(crate) fn has_stream_entries_after(&self, key: &str, after: StreamId) -> bool {
+        matches!(self.cache.get(key), Some(CacheValue::Stream(stream)) if stream.last_id > after)
     }
 
     pub(crate) async fn try_send_ttl(&self, cache_entry: &CacheEntry) -> anyhow::Result<()> {
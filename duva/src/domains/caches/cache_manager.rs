@@ -2,6 +2,7 @@ use crate::domains::caches::actor::CacheActor;
 use crate::domains::caches::actor::CacheCommandSender;
 use crate::domains::caches::cache_objects::CacheEntry;
 use crate::domains::caches::command::CacheCommand;
+use crate::domains::caches::read_queue::ReadFn;
 use crate::domains::cluster_actors::replication::ReplicationId;
 use crate::domains::operation_logs::WriteRequest;
 use crate::domains::query_parsers::QueryIO;
@@ -12,10 +13,11 @@ use crate::domains::saves::snapshot::Snapshot;
 use anyhow::Result;
 use chrono::Utc;
 use futures::StreamExt;
-use futures::future::join_all;
+use futures::future::{join_all, try_join_all};
 use futures::stream::FuturesUnordered;
 use tokio::sync::oneshot::error::RecvError;
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 
@@ -23,7 +25,8 @@ use std::{hash::Hasher, iter::Zip};
 use tokio::sync::oneshot::Sender;
 use tokio::task::JoinHandle;
 
-use super::cache_objects::CacheValue;
+use super::cache_objects::{CacheValue, ListEnd, SetOp, combine_sets};
+use super::stream::{GroupReadFrom, StreamId, no_group_err, streams_reply};
 
 type OneShotSender<T> = tokio::sync::oneshot::Sender<T>;
 type OneShotReceiverJoinHandle<T> =
@@ -54,6 +57,23 @@ impl CacheManager {
         Ok(rx.await?)
     }
 
+    /// Runs `read` on the value at `key` inside its shard, so only what it picks out is copied.
+    /// With `read_idx`, the read waits for that index to be applied.
+    pub(crate) async fn route_read<T: Send + 'static>(
+        &self,
+        key: String,
+        read_idx: Option<u64>,
+        read: impl FnOnce(Option<&CacheValue>) -> T + Send + Sync + 'static,
+    ) -> Result<T> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let read: ReadFn = Box::new(move |value| {
+            let _ = tx.send(read(value));
+        });
+        self.select_shard(&key).send(CacheCommand::Read { key, read_idx, read }).await?;
+
+        Ok(rx.await?)
+    }
+
     pub(crate) async fn route_set(&self, kvs: CacheEntry) -> Result<()> {
         self.select_shard(kvs.key()).send(CacheCommand::Set { cache_entry: kvs }).await?;
         Ok(())
@@ -81,29 +101,260 @@ impl CacheManager {
         Ok(tokio::spawn(save_actor.run(inbox)))
     }
 
-    pub(crate) async fn apply_log(&self, msg: WriteRequest) -> Result<()> {
-        match msg {
+    /// Applies a committed entry and returns what the client that wrote it gets back.
+    pub(crate) async fn apply_log(&self, msg: WriteRequest, log_index: u64) -> Result<QueryIO> {
+        let res = match msg {
             WriteRequest::Set { key, value } => {
+                let res = QueryIO::SimpleString(format!("s:{}|idx:{}", value, log_index));
                 self.route_set(CacheEntry::KeyValue { key, value }).await?;
+                res
             },
             WriteRequest::SetWithExpiry { key, value, expires_at } => {
+                let res = QueryIO::SimpleString(format!("s:{}|idx:{}", value, log_index));
                 self.route_set(CacheEntry::KeyValueExpiry {
                     key,
                     value,
                     expiry: StoredDuration::Milliseconds(expires_at).to_datetime(),
                 })
                 .await?;
+                res
             },
             WriteRequest::Delete { keys } => {
-                self.route_delete(keys).await?;
+                QueryIO::SimpleString(self.route_delete(keys).await?.to_string())
+            },
+            WriteRequest::LPush { key, values } => {
+                let push = |key, callback| CacheCommand::ListPush {
+                    key,
+                    values,
+                    end: ListEnd::Head,
+                    callback,
+                };
+                self.route_update(key, push, integer_reply).await?
+            },
+            WriteRequest::RPush { key, values } => {
+                let push = |key, callback| CacheCommand::ListPush {
+                    key,
+                    values,
+                    end: ListEnd::Tail,
+                    callback,
+                };
+                self.route_update(key, push, integer_reply).await?
+            },
+            WriteRequest::LPop { key, count } => {
+                let pop = |key, callback| CacheCommand::ListPop {
+                    key,
+                    count: count.unwrap_or(1) as usize,
+                    end: ListEnd::Head,
+                    callback,
+                };
+                self.route_update(key, pop, |popped| popped_reply(popped, count)).await?
+            },
+            WriteRequest::RPop { key, count } => {
+                let pop = |key, callback| CacheCommand::ListPop {
+                    key,
+                    count: count.unwrap_or(1) as usize,
+                    end: ListEnd::Tail,
+                    callback,
+                };
+                self.route_update(key, pop, |popped| popped_reply(popped, count)).await?
+            },
+            WriteRequest::HSet { key, entries } => {
+                let hset = |key, callback| CacheCommand::HashSet { key, entries, callback };
+                self.route_update(key, hset, integer_reply).await?
             },
+            WriteRequest::HDel { key, fields } => {
+                let hdel = |key, callback| CacheCommand::HashDel { key, fields, callback };
+                self.route_update(key, hdel, integer_reply).await?
+            },
+            WriteRequest::HIncrBy { key, field, increment } => {
+                let hincrby =
+                    |key, callback| CacheCommand::HashIncrBy { key, field, increment, callback };
+                self.route_update(key, hincrby, integer_reply).await?
+            },
+            WriteRequest::SAdd { key, members } => {
+                let sadd = |key, callback| CacheCommand::SetAdd { key, members, callback };
+                self.route_update(key, sadd, integer_reply).await?
+            },
+            WriteRequest::SRem { key, members } => {
+                let srem = |key, callback| CacheCommand::SetRem { key, members, callback };
+                self.route_update(key, srem, integer_reply).await?
+            },
+            WriteRequest::SInterStore { destination, keys } => {
+                self.route_store_sets(SetOp::Inter, destination, keys).await?
+            },
+            WriteRequest::SUnionStore { destination, keys } => {
+                self.route_store_sets(SetOp::Union, destination, keys).await?
+            },
+            WriteRequest::SDiffStore { destination, keys } => {
+                self.route_store_sets(SetOp::Diff, destination, keys).await?
+            },
+            WriteRequest::ZAdd { key, entries } => {
+                let zadd = |key, callback| CacheCommand::SortedSetAdd { key, entries, callback };
+                self.route_update(key, zadd, integer_reply).await?
+            },
+            WriteRequest::ZRem { key, members } => {
+                let zrem = |key, callback| CacheCommand::SortedSetRem { key, members, callback };
+                self.route_update(key, zrem, integer_reply).await?
+            },
+            WriteRequest::ZIncrBy { key, increment, member } => {
+                let zincrby = |key, callback| CacheCommand::SortedSetIncrBy {
+                    key,
+                    increment,
+                    member,
+                    callback,
+                };
+                self.route_update(key, zincrby, |score| QueryIO::BulkString(score.to_string()))
+                    .await?
+            },
+            WriteRequest::XAdd { key, id, fields } => {
+                let xadd = |key, callback| CacheCommand::StreamAdd { key, id, fields, callback };
+                self.route_update(key, xadd, |id| QueryIO::BulkString(id.to_string())).await?
+            },
+            WriteRequest::XGroupCreate { key, group, start, mkstream } => {
+                let create = |key, callback| CacheCommand::StreamGroupCreate {
+                    key,
+                    group,
+                    start,
+                    mkstream,
+                    callback,
+                };
+                self.route_update(key, create, |_| QueryIO::SimpleString("OK".into())).await?
+            },
+            WriteRequest::XReadGroup { group, consumer, streams, count, now_ms } => {
+                self.route_read_group(group, consumer, streams, count.map(|c| c as usize), now_ms)
+                    .await?
+            },
+            WriteRequest::XAck { key, group, ids } => {
+                let xack = |key, callback| CacheCommand::StreamAck { key, group, ids, callback };
+                self.route_update(key, xack, integer_reply).await?
+            },
+            // Membership is tracked by the cluster actor, not the cache
+            WriteRequest::Configuration { .. } | WriteRequest::NoOp => QueryIO::Null,
         };
 
         self.pings().await;
 
-        Ok(())
+        Ok(res)
     }
-    async fn pings(&self) {
+    /// Sends an update of the value at `key` to its shard. An update the value's type rejects
+    /// still got committed, so it becomes an error reply rather than an error.
+    async fn route_update<T>(
+        &self,
+        key: String,
+        command: impl FnOnce(String, OneShotSender<Result<T>>) -> CacheCommand,
+        reply: impl FnOnce(T) -> QueryIO,
+    ) -> Result<QueryIO> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(&key).send(command(key, tx)).await?;
+
+        Ok(match rx.await? {
+            Ok(res) => reply(res),
+            Err(err) => QueryIO::Err(err.to_string()),
+        })
+    }
+
+    /// Collects the sets at `keys` from the shards owning them, in the order of `keys`.
+    /// An absent key reads as an empty set. With `read_idx`, each read waits for that index to be applied.
+    pub(crate) async fn route_gather_sets(
+        &self,
+        keys: Vec<String>,
+        read_idx: Option<u64>,
+    ) -> Result<Vec<HashSet<String>>> {
+        let values = try_join_all(keys.into_iter().map(|key| async move {
+            match read_idx {
+                Some(index) => self.route_index_get(key, index).await,
+                None => self.route_get(key).await,
+            }
+        }))
+        .await?;
+
+        values
+            .into_iter()
+            .map(|value| match value {
+                Some(value) => Ok(value.set()?.clone()),
+                None => Ok(HashSet::new()),
+            })
+            .collect()
+    }
+
+    // Committed entries are applied one at a time, so no write lands between the gathering and the store
+    async fn route_store_sets(
+        &self,
+        op: SetOp,
+        destination: String,
+        keys: Vec<String>,
+    ) -> Result<QueryIO> {
+        let set = match self.route_gather_sets(keys, None).await {
+            Ok(sets) => combine_sets(op, sets),
+            Err(err) => return Ok(QueryIO::Err(err.to_string())),
+        };
+        let cardinality = set.len();
+        if set.is_empty() {
+            self.route_delete(vec![destination]).await?;
+        } else {
+            self.route_set(CacheEntry::KeySet { key: destination, set }).await?;
+        }
+        Ok(integer_reply(cardinality))
+    }
+
+    // The streams are read one after another, once the group is known to exist on all of them, so
+    // that a stream without it fails the command before any entry is delivered
+    async fn route_read_group(
+        &self,
+        group: String,
+        consumer: String,
+        streams: Vec<(String, GroupReadFrom)>,
+        count: Option<usize>,
+        now_ms: u64,
+    ) -> Result<QueryIO> {
+        for (key, _) in &streams {
+            let group = group.clone();
+            let has_group = self
+                .route_read(key.clone(), None, move |value| match value {
+                    Some(value) => value.stream()?.group(&group).map(|_| ()),
+                    None => Err(no_group_err(&group)),
+                })
+                .await?;
+            if let Err(err) = has_group {
+                return Ok(QueryIO::Err(err.to_string()));
+            }
+        }
+
+        let mut read = Vec::new();
+        for (key, from) in streams {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            self.select_shard(&key)
+                .send(CacheCommand::StreamReadGroup {
+                    key: key.clone(),
+                    group: group.clone(),
+                    consumer: consumer.clone(),
+                    from,
+                    count,
+                    now_ms,
+                    callback: tx,
+                })
+                .await?;
+            match rx.await? {
+                Ok(entries) if entries.is_empty() => {},
+                Ok(entries) => read.push((key, entries)),
+                Err(err) => return Ok(QueryIO::Err(err.to_string())),
+            }
+        }
+        Ok(streams_reply(read))
+    }
+
+    /// Resolves once the stream at `key` has an entry after `after`, right away if it already has.
+    pub(crate) async fn route_wait_stream(
+        &self,
+        key: String,
+        after: StreamId,
+    ) -> Result<tokio::sync::oneshot::Receiver<()>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(&key).send(CacheCommand::StreamWait { key, after, callback: tx }).await?;
+        Ok(rx)
+    }
+
+    pub(crate) async fn pings(&self) {
         join_all(self.inboxes.iter().map(|shard| shard.send(CacheCommand::Ping))).await;
     }
 
@@ -241,7 +492,7 @@ impl CacheManager {
         )
         .await;
 
-        join_all(rxs.into_iter()).await;
+        join_all(rxs).await;
     }
 
     pub(crate) async fn route_ttl(&self, key: String) -> Result<String> {
@@ -255,3 +506,16 @@ impl CacheManager {
         Ok(ttl)
     }
 }
+
+fn integer_reply(n: impl ToString) -> QueryIO {
+    QueryIO::SimpleString(n.to_string())
+}
+
+// A single element without `count`, an array of them with it
+fn popped_reply(mut popped: Vec<String>, count: Option<u64>) -> QueryIO {
+    match count {
+        _ if popped.is_empty() => QueryIO::Null,
+        None => QueryIO::BulkString(popped.remove(0)),
+        Some(_) => popped.into(),
+    }
+}
@@ -1,11 +1,22 @@
+use super::sorted_set::SortedSet;
+use super::stream::Stream;
 use anyhow::Context;
 use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::time::Duration;
 
+pub(crate) const WRONGTYPE_ERR: &str =
+    "WRONGTYPE Operation against a key holding the wrong kind of value";
+
 #[derive(Debug, Clone)]
 pub enum CacheEntry {
     KeyValue { key: String, value: String },
     KeyValueExpiry { key: String, value: String, expiry: DateTime<Utc> },
+    KeyList { key: String, list: VecDeque<String> },
+    KeyHash { key: String, hash: HashMap<String, String> },
+    KeySet { key: String, set: HashSet<String> },
+    KeySortedSet { key: String, zset: SortedSet },
+    KeyStream { key: String, stream: Stream },
 }
 
 impl CacheEntry {
@@ -27,6 +38,11 @@ impl CacheEntry {
         match &self {
             CacheEntry::KeyValue { key, .. } => key,
             CacheEntry::KeyValueExpiry { key, .. } => key,
+            CacheEntry::KeyList { key, .. } => key,
+            CacheEntry::KeyHash { key, .. } => key,
+            CacheEntry::KeySet { key, .. } => key,
+            CacheEntry::KeySortedSet { key, .. } => key,
+            CacheEntry::KeyStream { key, .. } => key,
         }
     }
 
@@ -46,19 +62,62 @@ impl CacheEntry {
     }
 }
 
+/// End of a list that elements are pushed to or popped from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ListEnd {
+    Head,
+    Tail,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) enum CacheValue {
     Value(String),
     ValueWithExpiry { value: String, expiry: DateTime<Utc> },
+    List(VecDeque<String>),
+    Hash(HashMap<String, String>),
+    Set(HashSet<String>),
+    SortedSet(SortedSet),
+    Stream(Stream),
 }
 impl CacheValue {
     pub(crate) fn has_expiry(&self) -> bool {
         matches!(self, CacheValue::ValueWithExpiry { .. })
     }
-    pub(crate) fn value(&self) -> &str {
+    pub(crate) fn value(&self) -> anyhow::Result<&str> {
+        match self {
+            CacheValue::Value(v) => Ok(v),
+            CacheValue::ValueWithExpiry { value: v, .. } => Ok(v),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+    pub(crate) fn list(&self) -> anyhow::Result<&VecDeque<String>> {
+        match self {
+            CacheValue::List(list) => Ok(list),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+    pub(crate) fn hash(&self) -> anyhow::Result<&HashMap<String, String>> {
+        match self {
+            CacheValue::Hash(hash) => Ok(hash),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+    pub(crate) fn set(&self) -> anyhow::Result<&HashSet<String>> {
         match self {
-            CacheValue::Value(v) => v,
-            CacheValue::ValueWithExpiry { value: v, .. } => v,
+            CacheValue::Set(set) => Ok(set),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+    pub(crate) fn sorted_set(&self) -> anyhow::Result<&SortedSet> {
+        match self {
+            CacheValue::SortedSet(zset) => Ok(zset),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
+        }
+    }
+    pub(crate) fn stream(&self) -> anyhow::Result<&Stream> {
+        match self {
+            CacheValue::Stream(stream) => Ok(stream),
+            _ => Err(anyhow::anyhow!(WRONGTYPE_ERR)),
         }
     }
 
@@ -70,6 +129,119 @@ impl CacheValue {
                 value: v.clone(),
                 expiry: *expiry,
             },
+            CacheValue::List(list) => CacheEntry::KeyList { key: key.into(), list: list.clone() },
+            CacheValue::Hash(hash) => CacheEntry::KeyHash { key: key.into(), hash: hash.clone() },
+            CacheValue::Set(set) => CacheEntry::KeySet { key: key.into(), set: set.clone() },
+            CacheValue::SortedSet(zset) => {
+                CacheEntry::KeySortedSet { key: key.into(), zset: zset.clone() }
+            },
+            CacheValue::Stream(stream) => {
+                CacheEntry::KeyStream { key: key.into(), stream: stream.clone() }
+            },
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetOp {
+    Inter,
+    Union,
+    // members of the first set missing from all the others
+    Diff,
+}
+
+/// Combines `sets` in order. No sets at all combine into an empty one.
+pub(crate) fn combine_sets(op: SetOp, sets: Vec<HashSet<String>>) -> HashSet<String> {
+    let mut sets = sets.into_iter();
+    let Some(first) = sets.next() else {
+        return HashSet::new();
+    };
+    sets.fold(first, |acc, set| match op {
+        SetOp::Inter => acc.into_iter().filter(|member| set.contains(member)).collect(),
+        SetOp::Union => acc.into_iter().chain(set).collect(),
+        SetOp::Diff => acc.into_iter().filter(|member| !set.contains(member)).collect(),
+    })
+}
+
+/// Elements between `start` and `stop` inclusive, where negative offsets count from the tail as in LRANGE.
+pub(crate) fn list_range(list: &VecDeque<String>, start: i64, stop: i64) -> Vec<String> {
+    let len = list.len() as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop {
+        return vec![];
+    }
+    list.range(start as usize..=stop as usize).cloned().collect()
+}
+
+/// Element at `index`, where a negative index counts from the tail as in LINDEX.
+pub(crate) fn list_index(list: &VecDeque<String>, index: i64) -> Option<&String> {
+    let index = if index < 0 { list.len() as i64 + index } else { index };
+    list.get(usize::try_from(index).ok()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn list_of(values: &[&str]) -> VecDeque<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_list_range_takes_offsets_from_either_end() {
+        // GIVEN
+        let list = list_of(&["a", "b", "c", "d"]);
+
+        // WHEN
+        let all = list_range(&list, 0, -1);
+        let tail = list_range(&list, -2, 10);
+        let empty = list_range(&list, 3, 1);
+
+        // THEN
+        assert_eq!(all, vec!["a", "b", "c", "d"]);
+        assert_eq!(tail, vec!["c", "d"]);
+        assert!(empty.is_empty());
+        assert!(list_range(&VecDeque::new(), 0, -1).is_empty());
+    }
+
+    #[test]
+    fn test_list_index_counts_negative_index_from_tail() {
+        // GIVEN
+        let list = list_of(&["a", "b", "c"]);
+
+        // THEN
+        assert_eq!(list_index(&list, 0).map(String::as_str), Some("a"));
+        assert_eq!(list_index(&list, -1).map(String::as_str), Some("c"));
+        assert_eq!(list_index(&list, 3), None);
+        assert_eq!(list_index(&list, -4), None);
+    }
+
+    #[test]
+    fn test_combine_sets_in_order() {
+        // GIVEN
+        let set = |members: &[&str]| members.iter().map(|m| m.to_string()).collect();
+        let sets: Vec<HashSet<String>> = vec![set(&["a", "b", "c"]), set(&["b", "c", "d"])];
+
+        // WHEN
+        let inter = combine_sets(SetOp::Inter, sets.clone());
+        let union = combine_sets(SetOp::Union, sets.clone());
+        let diff = combine_sets(SetOp::Diff, sets);
+
+        // THEN
+        assert_eq!(inter, set(&["b", "c"]));
+        assert_eq!(union, set(&["a", "b", "c", "d"]));
+        assert_eq!(diff, set(&["a"]));
+        assert!(combine_sets(SetOp::Union, vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_string_accessors_reject_list() {
+        // GIVEN
+        let value = CacheValue::List(list_of(&["a"]));
+
+        // THEN
+        assert_eq!(value.value().unwrap_err().to_string(), WRONGTYPE_ERR);
+        assert!(CacheValue::Value("a".into()).list().is_err());
+    }
+}
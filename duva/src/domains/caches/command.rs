@@ -1,15 +1,134 @@
-use super::cache_objects::{CacheEntry, CacheValue};
+use super::cache_objects::{CacheEntry, CacheValue, ListEnd};
+use super::read_queue::ReadFn;
+use super::sorted_set::Score;
+use super::stream::{GroupReadFrom, StreamEntry, StreamId, StreamIdSpec};
 use crate::domains::{query_parsers::QueryIO, saves::command::SaveCommand};
 use tokio::sync::{mpsc, oneshot};
 
 pub(crate) enum CacheCommand {
-    Set { cache_entry: CacheEntry },
-    Save { outbox: mpsc::Sender<SaveCommand> },
-    Get { key: String, callback: oneshot::Sender<Option<CacheValue>> },
-    Keys { pattern: Option<String>, callback: oneshot::Sender<QueryIO> },
-    Delete { key: String, callback: oneshot::Sender<bool> },
-    IndexGet { key: String, read_idx: u64, callback: oneshot::Sender<Option<CacheValue>> },
+    Set {
+        cache_entry: CacheEntry,
+    },
+    Save {
+        outbox: mpsc::Sender<SaveCommand>,
+    },
+    Get {
+        key: String,
+        callback: oneshot::Sender<Option<CacheValue>>,
+    },
+    Keys {
+        pattern: Option<String>,
+        callback: oneshot::Sender<QueryIO>,
+    },
+    Delete {
+        key: String,
+        callback: oneshot::Sender<bool>,
+    },
+    IndexGet {
+        key: String,
+        read_idx: u64,
+        callback: oneshot::Sender<Option<CacheValue>>,
+    },
+    // Runs `read` on the value at `key` once `read_idx`, when given, is applied
+    Read {
+        key: String,
+        read_idx: Option<u64>,
+        read: ReadFn,
+    },
     Ping,
-    Drop { callback: oneshot::Sender<()> },
-    Exists { key: String, callback: oneshot::Sender<bool> },
+    Drop {
+        callback: oneshot::Sender<()>,
+    },
+    Exists {
+        key: String,
+        callback: oneshot::Sender<bool>,
+    },
+    ListPush {
+        key: String,
+        values: Vec<String>,
+        end: ListEnd,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    ListPop {
+        key: String,
+        count: usize,
+        end: ListEnd,
+        callback: oneshot::Sender<anyhow::Result<Vec<String>>>,
+    },
+    HashSet {
+        key: String,
+        entries: Vec<(String, String)>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    HashDel {
+        key: String,
+        fields: Vec<String>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    HashIncrBy {
+        key: String,
+        field: String,
+        increment: i64,
+        callback: oneshot::Sender<anyhow::Result<i64>>,
+    },
+    SetAdd {
+        key: String,
+        members: Vec<String>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    SetRem {
+        key: String,
+        members: Vec<String>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    SortedSetAdd {
+        key: String,
+        entries: Vec<(Score, String)>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    SortedSetRem {
+        key: String,
+        members: Vec<String>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    SortedSetIncrBy {
+        key: String,
+        increment: Score,
+        member: String,
+        callback: oneshot::Sender<anyhow::Result<Score>>,
+    },
+    StreamAdd {
+        key: String,
+        id: StreamIdSpec,
+        fields: Vec<(String, String)>,
+        callback: oneshot::Sender<anyhow::Result<StreamId>>,
+    },
+    StreamGroupCreate {
+        key: String,
+        group: String,
+        start: Option<StreamId>,
+        mkstream: bool,
+        callback: oneshot::Sender<anyhow::Result<()>>,
+    },
+    StreamReadGroup {
+        key: String,
+        group: String,
+        consumer: String,
+        from: GroupReadFrom,
+        count: Option<usize>,
+        now_ms: u64,
+        callback: oneshot::Sender<anyhow::Result<Vec<StreamEntry>>>,
+    },
+    StreamAck {
+        key: String,
+        group: String,
+        ids: Vec<StreamId>,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    // Answered once the stream at `key` has an entry after `after`
+    StreamWait {
+        key: String,
+        after: StreamId,
+        callback: oneshot::Sender<()>,
+    },
 }
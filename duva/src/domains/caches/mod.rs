@@ -3,3 +3,5 @@ pub mod cache_manager;
 pub mod cache_objects;
 pub mod command;
 pub mod read_queue;
+pub mod sorted_set;
+pub mod stream;
@@ -1,18 +1,20 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::oneshot::Sender;
 
 use super::cache_objects::CacheValue;
 
 pub struct ReadQueue {
     pub(crate) hwm: Arc<AtomicU64>,
-    inner: HashMap<u64, Vec<DeferredRead>>,
+    inner: BTreeMap<u64, Vec<DeferredRead>>,
 }
 
+/// Runs against the value at the key read, None when the key is absent, and answers whoever asked for it.
+pub(crate) type ReadFn = Box<dyn FnOnce(Option<&CacheValue>) + Send + Sync>;
+
 pub(crate) struct DeferredRead {
     pub(crate) key: String,
-    pub(crate) callback: Sender<Option<CacheValue>>,
+    pub(crate) read: ReadFn,
 }
 
 impl ReadQueue {
@@ -27,35 +29,56 @@ impl ReadQueue {
         &mut self,
         read_idx: u64,
         key: &str,
-        callback: Sender<Option<CacheValue>>,
-    ) -> Option<Sender<Option<CacheValue>>> {
+        read: ReadFn,
+    ) -> Option<ReadFn> {
         let current_hwm = self.hwm.load(Ordering::Relaxed);
         if current_hwm < read_idx {
-            self.push(read_idx, DeferredRead { key: key.into(), callback });
+            self.push(read_idx, DeferredRead { key: key.into(), read });
             None
         } else {
-            Some(callback)
+            Some(read)
         }
     }
 
+    /// Takes every read waiting for an index the commit index has reached, as it may advance by more than one at a time.
     pub(crate) fn take_pending_requests(&mut self) -> Option<Vec<DeferredRead>> {
         let current_hwm = self.hwm.load(Ordering::Relaxed);
-        self.inner.remove(&current_hwm)
+        let pending = self.inner.split_off(&(current_hwm + 1));
+        let ready = std::mem::replace(&mut self.inner, pending);
+        if ready.is_empty() { None } else { Some(ready.into_values().flatten().collect()) }
     }
 }
 
 #[test]
 fn test_push() {
     //GIVEN
-    let (tx1, _) = tokio::sync::oneshot::channel();
-    let (tx2, _) = tokio::sync::oneshot::channel();
     let hwm = Arc::new(AtomicU64::new(1));
     let mut rq = ReadQueue::new(hwm.clone());
 
     //WHEN
-    rq.push(1, DeferredRead { key: "migo".into(), callback: tx1 });
-    rq.push(1, DeferredRead { key: "migo2".into(), callback: tx2 });
+    rq.push(1, DeferredRead { key: "migo".into(), read: Box::new(|_| {}) });
+    rq.push(1, DeferredRead { key: "migo2".into(), read: Box::new(|_| {}) });
 
     //THEN
     assert_eq!(rq.inner[&1].len(), 2)
 }
+
+#[test]
+fn test_take_pending_requests_up_to_hwm() {
+    //GIVEN
+    let hwm = Arc::new(AtomicU64::new(0));
+    let mut rq = ReadQueue::new(hwm.clone());
+    for idx in 1..=3 {
+        rq.push(idx, DeferredRead { key: idx.to_string(), read: Box::new(|_| {}) });
+    }
+
+    //WHEN - commit index skips over index 1
+    hwm.store(2, Ordering::Relaxed);
+
+    //THEN
+    let keys: Vec<String> =
+        rq.take_pending_requests().unwrap().into_iter().map(|r| r.key).collect();
+    assert_eq!(keys, vec!["1", "2"]);
+    assert!(rq.take_pending_requests().is_none());
+    assert_eq!(rq.inner.len(), 1);
+}
@@ -0,0 +1,273 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+
+/// Score of a sorted set member. NaN is never a score, so scores are totally ordered.
+#[derive(Debug, Clone, Copy, bincode::Encode, bincode::Decode)]
+pub struct Score(f64);
+
+impl Score {
+    pub(crate) const ZERO: Score = Score(0.0);
+
+    pub(crate) fn new(score: f64) -> Option<Self> {
+        // adding 0.0 turns -0.0 into 0.0, so the two don't order apart
+        (!score.is_nan()).then_some(Self(score + 0.0))
+    }
+
+    /// None when the sum is not a number, as when adding -inf to inf.
+    pub(crate) fn incr(self, increment: Score) -> Option<Self> {
+        Self::new(self.0 + increment.0)
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Score {}
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// One end of a score range, as in ZRANGEBYSCORE where `(` makes it exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreBound {
+    Inclusive(Score),
+    Exclusive(Score),
+}
+
+impl ScoreBound {
+    fn admits_from_below(&self, score: Score) -> bool {
+        match self {
+            ScoreBound::Inclusive(min) => *min <= score,
+            ScoreBound::Exclusive(min) => *min < score,
+        }
+    }
+
+    fn admits_from_above(&self, score: Score) -> bool {
+        match self {
+            ScoreBound::Inclusive(max) => score <= *max,
+            ScoreBound::Exclusive(max) => score < *max,
+        }
+    }
+}
+
+/// Scores from `min` up to `max`, as ZRANGEBYSCORE takes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreRange {
+    pub(crate) min: ScoreBound,
+    pub(crate) max: ScoreBound,
+}
+
+/// Members ordered by score, then lexicographically among equal scores.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortedSet {
+    scores: HashMap<String, Score>,
+    ordered: BTreeSet<(Score, String)>,
+}
+
+impl SortedSet {
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub(crate) fn score(&self, member: &str) -> Option<Score> {
+        self.scores.get(member).copied()
+    }
+
+    /// Sets the score of `member`, moving it if it was already in. Returns whether it was new.
+    pub(crate) fn insert(&mut self, member: String, score: Score) -> bool {
+        let previous = self.scores.insert(member.clone(), score);
+        if let Some(previous) = previous {
+            self.ordered.remove(&(previous, member.clone()));
+        }
+        self.ordered.insert((score, member));
+        previous.is_none()
+    }
+
+    pub(crate) fn remove(&mut self, member: &str) -> bool {
+        let Some(score) = self.scores.remove(member) else {
+            return false;
+        };
+        self.ordered.remove(&(score, member.to_string()));
+        true
+    }
+
+    /// Position of `member` counted from the lowest score, or from the highest with `rev`.
+    /// The tree keeps no subtree sizes, so this walks every member ranked below it: O(N).
+    pub(crate) fn rank(&self, member: &str, rev: bool) -> Option<usize> {
+        let score = self.score(member)?;
+        let rank = self.ordered.range(..(score, member.to_string())).count();
+        Some(if rev { self.len() - 1 - rank } else { rank })
+    }
+
+    /// Members ranked between `start` and `stop` inclusive, where negative ranks count from the end as in ZRANGE.
+    /// Reaching `start` walks every member ranked before it, so this is O(stop) rather than O(stop - start).
+    pub(crate) fn range_by_rank(&self, start: i64, stop: i64, rev: bool) -> Vec<(&str, Score)> {
+        let len = self.len() as i64;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop {
+            return vec![];
+        }
+        self.entries(rev).skip(start as usize).take((stop - start + 1) as usize).collect()
+    }
+
+    /// Members scored between `min` and `max`, from the highest score down with `rev`.
+    /// `limit` is the offset and count of the members to take out of those.
+    pub(crate) fn range_by_score(
+        &self,
+        min: ScoreBound,
+        max: ScoreBound,
+        rev: bool,
+        limit: Option<(usize, usize)>,
+    ) -> Vec<(&str, Score)> {
+        let (offset, count) = limit.unwrap_or((0, usize::MAX));
+        let before_range = |score: Score| match rev {
+            true => !max.admits_from_above(score),
+            false => !min.admits_from_below(score),
+        };
+        let in_range = |score: Score| min.admits_from_below(score) && max.admits_from_above(score);
+
+        self.entries(rev)
+            .skip_while(|(_, score)| before_range(*score))
+            .take_while(|(_, score)| in_range(*score))
+            .skip(offset)
+            .take(count)
+            .collect()
+    }
+
+    pub(crate) fn entries(&self, rev: bool) -> Box<dyn Iterator<Item = (&str, Score)> + '_> {
+        let entries = self.ordered.iter().map(|(score, member)| (member.as_str(), *score));
+        if rev { Box::new(entries.rev()) } else { Box::new(entries) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(score: f64) -> Score {
+        Score::new(score).unwrap()
+    }
+
+    fn zset_of(entries: &[(&str, f64)]) -> SortedSet {
+        let mut zset = SortedSet::default();
+        for (member, s) in entries {
+            zset.insert(member.to_string(), score(*s));
+        }
+        zset
+    }
+
+    fn members(entries: Vec<(&str, Score)>) -> Vec<&str> {
+        entries.into_iter().map(|(member, _)| member).collect()
+    }
+
+    #[test]
+    fn test_insert_moves_existing_member() {
+        // GIVEN
+        let mut zset = zset_of(&[("a", 1.0), ("b", 2.0), ("c", 3.0)]);
+
+        // WHEN
+        let is_new = zset.insert("a".into(), score(5.0));
+
+        // THEN
+        assert!(!is_new);
+        assert_eq!(zset.len(), 3);
+        assert_eq!(members(zset.range_by_rank(0, -1, false)), vec!["b", "c", "a"]);
+        assert_eq!(zset.rank("a", false), Some(2));
+        assert_eq!(zset.rank("a", true), Some(0));
+        assert_eq!(zset.rank("missing", false), None);
+    }
+
+    #[test]
+    fn test_equal_scores_order_by_member() {
+        // GIVEN
+        let zset = zset_of(&[("b", 1.0), ("a", 1.0), ("c", 0.0)]);
+
+        // WHEN
+        let ranked = members(zset.range_by_rank(0, -1, false));
+
+        // THEN
+        assert_eq!(ranked, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_range_by_rank_takes_offsets_from_either_end() {
+        // GIVEN
+        let zset = zset_of(&[("a", 1.0), ("b", 2.0), ("c", 3.0), ("d", 4.0)]);
+
+        // THEN
+        assert_eq!(members(zset.range_by_rank(1, -2, false)), vec!["b", "c"]);
+        assert_eq!(members(zset.range_by_rank(0, 1, true)), vec!["d", "c"]);
+        assert_eq!(members(zset.range_by_rank(-100, 100, false)).len(), 4);
+        assert!(zset.range_by_rank(3, 1, false).is_empty());
+    }
+
+    #[test]
+    fn test_rank_and_range_by_rank_agree_on_a_large_set() {
+        // GIVEN
+        const LEN: usize = 100_000;
+        let mut zset = SortedSet::default();
+        for i in (0..LEN).rev() {
+            zset.insert(format!("m{i}"), score(i as f64));
+        }
+
+        // THEN
+        for i in [0, 1, LEN / 2, LEN - 2, LEN - 1] {
+            let member = format!("m{i}");
+            assert_eq!(zset.rank(&member, false), Some(i));
+            assert_eq!(zset.rank(&member, true), Some(LEN - 1 - i));
+            assert_eq!(
+                members(zset.range_by_rank(i as i64, i as i64, false)),
+                vec![member.as_str()]
+            );
+        }
+        assert_eq!(members(zset.range_by_rank(-2, -1, true)), vec!["m1", "m0"]);
+        assert_eq!(zset.range_by_rank(0, -1, false).len(), LEN);
+    }
+
+    #[test]
+    fn test_range_by_score_with_bounds_limit_and_rev() {
+        // GIVEN
+        let zset = zset_of(&[("a", 1.0), ("b", 2.0), ("c", 3.0), ("d", 4.0)]);
+        let inclusive = |s| ScoreBound::Inclusive(score(s));
+        let exclusive = |s| ScoreBound::Exclusive(score(s));
+
+        // THEN
+        let by_score = |min, max, rev, limit| members(zset.range_by_score(min, max, rev, limit));
+        assert_eq!(by_score(inclusive(2.0), inclusive(3.0), false, None), vec!["b", "c"]);
+        assert_eq!(by_score(exclusive(1.0), exclusive(4.0), false, None), vec!["b", "c"]);
+        assert_eq!(by_score(exclusive(1.0), inclusive(4.0), true, None), vec!["d", "c", "b"]);
+        assert_eq!(
+            by_score(inclusive(f64::NEG_INFINITY), inclusive(f64::INFINITY), false, Some((1, 2))),
+            vec!["b", "c"]
+        );
+        assert!(by_score(inclusive(5.0), inclusive(6.0), false, None).is_empty());
+    }
+
+    #[test]
+    fn test_score_rejects_nan() {
+        assert!(Score::new(f64::NAN).is_none());
+        assert!(score(f64::INFINITY).incr(score(f64::NEG_INFINITY)).is_none());
+        assert_eq!(score(-0.0), score(0.0));
+        assert_eq!(score(1.5).incr(score(1.0)).unwrap().to_string(), "2.5");
+    }
+}
@@ -0,0 +1,557 @@
+use crate::domains::query_parsers::QueryIO;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::ops::Bound;
+use tokio::sync::oneshot;
+
+const INVALID_ID_ERR: &str = "ERR Invalid stream ID specified as stream command argument";
+
+/// ID of a stream entry, `ms-seq`: the millisecond it was added at and its sequence number within that millisecond.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct StreamId {
+    pub(crate) ms: u64,
+    pub(crate) seq: u64,
+}
+
+impl StreamId {
+    pub(crate) const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub(crate) const MAX: StreamId = StreamId { ms: u64::MAX, seq: u64::MAX };
+
+    /// Parses `ms-seq`, or a bare `ms` that takes `default_seq` as its sequence number.
+    pub(crate) fn parse(id: &str, default_seq: u64) -> anyhow::Result<Self> {
+        let (ms, seq) = match id.split_once('-') {
+            Some((ms, seq)) => (ms.parse().ok(), seq.parse().ok()),
+            None => (id.parse().ok(), Some(default_seq)),
+        };
+        match (ms, seq) {
+            (Some(ms), Some(seq)) => Ok(StreamId { ms, seq }),
+            _ => Err(anyhow::anyhow!(INVALID_ID_ERR)),
+        }
+    }
+}
+
+impl Display for StreamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// ID an XADD asks for. `Auto` carries the time the command was taken at, so that every replica
+/// generates the same ID when applying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum StreamIdSpec {
+    Auto { now_ms: u64 },
+    Explicit(StreamId),
+}
+
+/// Where XREADGROUP reads from: entries never delivered to the group (`>`), or the consumer's own
+/// pending entries after the given ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum GroupReadFrom {
+    New,
+    Pending(StreamId),
+}
+
+/// Range and count of the extended XPENDING form, optionally narrowed to one consumer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRange {
+    pub(crate) start: StreamId,
+    pub(crate) end: StreamId,
+    pub(crate) count: usize,
+    pub(crate) consumer: Option<String>,
+}
+
+/// ID of an entry and its fields, None when a pending entry was delivered but no longer exists.
+pub(crate) type StreamEntry = (StreamId, Option<Vec<(String, String)>>);
+
+/// Entry delivered to a consumer of a group and not acknowledged yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub(crate) consumer: String,
+    pub(crate) delivered_at: u64,
+    pub(crate) deliveries: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsumerGroup {
+    pub(crate) last_delivered: StreamId,
+    pub(crate) pending: BTreeMap<StreamId, PendingEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stream {
+    pub(crate) entries: BTreeMap<StreamId, Vec<(String, String)>>,
+    // highest ID ever added, which the next one must exceed
+    pub(crate) last_id: StreamId,
+    pub(crate) groups: HashMap<String, ConsumerGroup>,
+}
+
+impl Stream {
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Appends an entry and returns its ID, generated after the last one for `StreamIdSpec::Auto`.
+    pub(crate) fn add(
+        &mut self,
+        id: StreamIdSpec,
+        fields: Vec<(String, String)>,
+    ) -> anyhow::Result<StreamId> {
+        let id = match id {
+            StreamIdSpec::Auto { now_ms } if now_ms > self.last_id.ms => {
+                StreamId { ms: now_ms, seq: 0 }
+            },
+            // the clock may lag behind the last ID, so the ID goes on from it
+            StreamIdSpec::Auto { .. } => match self.last_id.seq.checked_add(1) {
+                Some(seq) => StreamId { ms: self.last_id.ms, seq },
+                None => match self.last_id.ms.checked_add(1) {
+                    Some(ms) => StreamId { ms, seq: 0 },
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "ERR The stream has exhausted the last possible ID, unable to add more items"
+                        ));
+                    },
+                },
+            },
+            StreamIdSpec::Explicit(StreamId::MIN) => {
+                return Err(anyhow::anyhow!(
+                    "ERR The ID specified in XADD must be greater than 0-0"
+                ));
+            },
+            StreamIdSpec::Explicit(id) if id <= self.last_id => {
+                return Err(anyhow::anyhow!(
+                    "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+                ));
+            },
+            StreamIdSpec::Explicit(id) => id,
+        };
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        Ok(id)
+    }
+
+    /// Entries with IDs between `start` and `end` inclusive.
+    pub(crate) fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    ) -> Vec<StreamEntry> {
+        if start > end {
+            return vec![];
+        }
+        self.collect(self.entries.range(start..=end), count)
+    }
+
+    /// Entries added after `id`, as XREAD reads them.
+    pub(crate) fn after(&self, id: StreamId, count: Option<usize>) -> Vec<StreamEntry> {
+        self.collect(self.entries.range((Bound::Excluded(id), Bound::Unbounded)), count)
+    }
+
+    fn collect<'a>(
+        &self,
+        entries: impl Iterator<Item = (&'a StreamId, &'a Vec<(String, String)>)>,
+        count: Option<usize>,
+    ) -> Vec<StreamEntry> {
+        entries
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, Some(fields.clone())))
+            .collect()
+    }
+
+    /// Creates a group that delivers the entries after `start`, or after the last entry when it's None.
+    pub(crate) fn create_group(
+        &mut self,
+        group: String,
+        start: Option<StreamId>,
+    ) -> anyhow::Result<()> {
+        if self.groups.contains_key(&group) {
+            return Err(anyhow::anyhow!("BUSYGROUP Consumer Group name already exists"));
+        }
+        let last_delivered = start.unwrap_or(self.last_id);
+        self.groups.insert(group, ConsumerGroup { last_delivered, pending: BTreeMap::new() });
+        Ok(())
+    }
+
+    pub(crate) fn group(&self, group: &str) -> anyhow::Result<&ConsumerGroup> {
+        self.groups.get(group).ok_or_else(|| no_group_err(group))
+    }
+
+    /// Delivers entries to `consumer`. New entries are added to the pending entries of the group,
+    /// to stay there until acknowledged. Pending entries read again count as delivered once more.
+    pub(crate) fn read_group(
+        &mut self,
+        group: &str,
+        consumer: String,
+        from: GroupReadFrom,
+        count: Option<usize>,
+        now_ms: u64,
+    ) -> anyhow::Result<Vec<StreamEntry>> {
+        let consumer_group = self.groups.get_mut(group).ok_or_else(|| no_group_err(group))?;
+        let count = count.unwrap_or(usize::MAX);
+
+        match from {
+            GroupReadFrom::New => {
+                let entries = self
+                    .entries
+                    .range((Bound::Excluded(consumer_group.last_delivered), Bound::Unbounded))
+                    .take(count)
+                    .map(|(id, fields)| (*id, Some(fields.clone())))
+                    .collect::<Vec<_>>();
+                for (id, _) in &entries {
+                    let delivered = PendingEntry {
+                        consumer: consumer.clone(),
+                        delivered_at: now_ms,
+                        deliveries: 1,
+                    };
+                    consumer_group.pending.insert(*id, delivered);
+                    consumer_group.last_delivered = *id;
+                }
+                Ok(entries)
+            },
+            GroupReadFrom::Pending(after) => Ok(consumer_group
+                .pending
+                .range_mut((Bound::Excluded(after), Bound::Unbounded))
+                .filter(|(_, pending)| pending.consumer == consumer)
+                .take(count)
+                .map(|(id, pending)| {
+                    pending.delivered_at = now_ms;
+                    pending.deliveries += 1;
+                    (*id, self.entries.get(id).cloned())
+                })
+                .collect()),
+        }
+    }
+
+    /// Removes the entries from the pending entries of the group and returns how many were in it.
+    pub(crate) fn ack(&mut self, group: &str, ids: &[StreamId]) -> usize {
+        let Some(consumer_group) = self.groups.get_mut(group) else {
+            return 0;
+        };
+        ids.iter().filter(|id| consumer_group.pending.remove(id).is_some()).count()
+    }
+}
+
+pub(crate) fn no_group_err(group: &str) -> anyhow::Error {
+    anyhow::anyhow!("NOGROUP No such key or consumer group '{group}'")
+}
+
+/// XREADs blocked until an entry is added to the stream they wait on.
+#[derive(Default)]
+pub(crate) struct StreamWaiters(HashMap<String, Vec<oneshot::Sender<()>>>);
+
+impl StreamWaiters {
+    pub(crate) fn wait(&mut self, key: String, callback: oneshot::Sender<()>) {
+        self.0.entry(key).or_default().push(callback);
+    }
+
+    /// Drops the XREADs that stopped listening, as they timed out or their client went away.
+    pub(crate) fn prune(&mut self) {
+        self.0.retain(|_, waiters| {
+            waiters.retain(|waiter| !waiter.is_closed());
+            !waiters.is_empty()
+        });
+    }
+
+    pub(crate) fn wake(&mut self, key: &str) {
+        for waiter in self.0.remove(key).into_iter().flatten() {
+            let _ = waiter.send(());
+        }
+    }
+}
+
+/// Each entry as its ID followed by its fields and values, or by null when it no longer exists.
+pub(crate) fn entries_reply(entries: Vec<StreamEntry>) -> QueryIO {
+    QueryIO::Array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| {
+                let fields = fields.map_or(QueryIO::Null, |fields| {
+                    fields
+                        .into_iter()
+                        .flat_map(|(field, value)| [field, value])
+                        .collect::<Vec<_>>()
+                        .into()
+                });
+                QueryIO::Array(vec![QueryIO::BulkString(id.to_string()), fields])
+            })
+            .collect(),
+    )
+}
+
+/// Entries read from each stream, under its key. Null when no stream had any.
+pub(crate) fn streams_reply(streams: Vec<(String, Vec<StreamEntry>)>) -> QueryIO {
+    if streams.is_empty() {
+        return QueryIO::Null;
+    }
+    QueryIO::Array(
+        streams
+            .into_iter()
+            .map(|(key, entries)| {
+                QueryIO::Array(vec![QueryIO::BulkString(key), entries_reply(entries)])
+            })
+            .collect(),
+    )
+}
+
+/// Number of pending entries, the lowest and highest of their IDs, and how many each consumer has.
+pub(crate) fn pending_summary_reply(group: &ConsumerGroup) -> QueryIO {
+    let (Some(first), Some(last)) = (group.pending.keys().next(), group.pending.keys().next_back())
+    else {
+        return QueryIO::Array(vec![
+            QueryIO::SimpleString("0".into()),
+            QueryIO::Null,
+            QueryIO::Null,
+            QueryIO::Null,
+        ]);
+    };
+    let mut per_consumer = BTreeMap::<&str, usize>::new();
+    for pending in group.pending.values() {
+        *per_consumer.entry(&pending.consumer).or_default() += 1;
+    }
+    QueryIO::Array(vec![
+        QueryIO::SimpleString(group.pending.len().to_string()),
+        QueryIO::BulkString(first.to_string()),
+        QueryIO::BulkString(last.to_string()),
+        QueryIO::Array(
+            per_consumer
+                .into_iter()
+                .map(|(consumer, count)| vec![consumer.to_string(), count.to_string()].into())
+                .collect(),
+        ),
+    ])
+}
+
+/// Each pending entry in `range` as its ID, consumer, milliseconds since it was delivered and times delivered.
+pub(crate) fn pending_entries_reply(
+    group: &ConsumerGroup,
+    range: PendingRange,
+    now_ms: u64,
+) -> QueryIO {
+    if range.start > range.end {
+        return QueryIO::Array(vec![]);
+    }
+    QueryIO::Array(
+        group
+            .pending
+            .range(range.start..=range.end)
+            .filter(|(_, pending)| range.consumer.as_ref().is_none_or(|c| *c == pending.consumer))
+            .take(range.count)
+            .map(|(id, pending)| {
+                QueryIO::Array(vec![
+                    QueryIO::BulkString(id.to_string()),
+                    QueryIO::BulkString(pending.consumer.clone()),
+                    QueryIO::SimpleString(now_ms.saturating_sub(pending.delivered_at).to_string()),
+                    QueryIO::SimpleString(pending.deliveries.to_string()),
+                ])
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    fn fields(field: &str, value: &str) -> Vec<(String, String)> {
+        vec![(field.to_string(), value.to_string())]
+    }
+
+    fn ids(entries: Vec<StreamEntry>) -> Vec<StreamId> {
+        entries.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn test_parse_stream_id() {
+        assert_eq!(StreamId::parse("5-3", 0).unwrap(), id(5, 3));
+        assert_eq!(StreamId::parse("5", u64::MAX).unwrap(), id(5, u64::MAX));
+        assert!(StreamId::parse("5-x", 0).is_err());
+        assert_eq!(id(1526919030474, 55).to_string(), "1526919030474-55");
+    }
+
+    #[test]
+    fn test_auto_ids_keep_increasing_when_clock_lags() {
+        // GIVEN
+        let mut stream = Stream::default();
+
+        // WHEN
+        let first = stream.add(StreamIdSpec::Auto { now_ms: 100 }, fields("f", "1")).unwrap();
+        let same_ms = stream.add(StreamIdSpec::Auto { now_ms: 100 }, fields("f", "2")).unwrap();
+        let lagging = stream.add(StreamIdSpec::Auto { now_ms: 90 }, fields("f", "3")).unwrap();
+        let later = stream.add(StreamIdSpec::Auto { now_ms: 200 }, fields("f", "4")).unwrap();
+
+        // THEN
+        assert_eq!(
+            vec![first, same_ms, lagging, later],
+            vec![id(100, 0), id(100, 1), id(100, 2), id(200, 0)]
+        );
+    }
+
+    #[test]
+    fn test_auto_id_fails_once_the_last_possible_id_is_taken() {
+        // GIVEN
+        let mut stream = Stream::default();
+        stream.add(StreamIdSpec::Explicit(id(u64::MAX, u64::MAX - 1)), fields("f", "1")).unwrap();
+        let last = stream.add(StreamIdSpec::Auto { now_ms: 100 }, fields("f", "2")).unwrap();
+
+        // WHEN
+        let exhausted = stream.add(StreamIdSpec::Auto { now_ms: 100 }, fields("f", "3"));
+
+        // THEN
+        assert_eq!(last, StreamId::MAX);
+        assert_eq!(
+            exhausted.unwrap_err().to_string(),
+            "ERR The stream has exhausted the last possible ID, unable to add more items"
+        );
+        assert_eq!(stream.len(), 2);
+    }
+
+    #[test]
+    fn test_explicit_ids_must_increase() {
+        // GIVEN
+        let mut stream = Stream::default();
+        stream.add(StreamIdSpec::Explicit(id(5, 0)), fields("f", "v")).unwrap();
+
+        // THEN
+        assert!(stream.add(StreamIdSpec::Explicit(id(0, 0)), fields("f", "v")).is_err());
+        assert!(stream.add(StreamIdSpec::Explicit(id(5, 0)), fields("f", "v")).is_err());
+        assert_eq!(
+            stream.add(StreamIdSpec::Explicit(id(5, 1)), fields("f", "v")).unwrap(),
+            id(5, 1)
+        );
+        assert_eq!(stream.len(), 2);
+    }
+
+    #[test]
+    fn test_range_and_after() {
+        // GIVEN
+        let mut stream = Stream::default();
+        for ms in 1..=4 {
+            stream.add(StreamIdSpec::Explicit(id(ms, 0)), fields("f", "v")).unwrap();
+        }
+
+        // THEN
+        assert_eq!(ids(stream.range(id(2, 0), id(3, u64::MAX), None)), vec![id(2, 0), id(3, 0)]);
+        assert_eq!(ids(stream.range(StreamId::MIN, StreamId::MAX, Some(1))), vec![id(1, 0)]);
+        assert!(stream.range(id(3, 0), id(2, 0), None).is_empty());
+        assert_eq!(ids(stream.after(id(3, 0), None)), vec![id(4, 0)]);
+    }
+
+    #[test]
+    fn test_group_delivers_new_entries_once_and_tracks_them_until_acked() {
+        // GIVEN
+        let mut stream = Stream::default();
+        for ms in 1..=3 {
+            stream.add(StreamIdSpec::Explicit(id(ms, 0)), fields("f", "v")).unwrap();
+        }
+        stream.create_group("g".into(), Some(StreamId::MIN)).unwrap();
+        assert!(stream.create_group("g".into(), None).is_err());
+
+        // WHEN
+        let alice =
+            stream.read_group("g", "alice".into(), GroupReadFrom::New, Some(2), 10).unwrap();
+        let bob = stream.read_group("g", "bob".into(), GroupReadFrom::New, None, 20).unwrap();
+
+        // THEN
+        assert_eq!(ids(alice), vec![id(1, 0), id(2, 0)]);
+        assert_eq!(ids(bob), vec![id(3, 0)]);
+        let history =
+            stream.read_group("g", "alice".into(), GroupReadFrom::Pending(StreamId::MIN), None, 30);
+        assert_eq!(ids(history.unwrap()), vec![id(1, 0), id(2, 0)]);
+
+        // WHEN
+        let acked = stream.ack("g", &[id(1, 0), id(3, 0), id(9, 0)]);
+
+        // THEN
+        assert_eq!(acked, 2);
+        let pending = &stream.group("g").unwrap().pending;
+        assert_eq!(pending.keys().copied().collect::<Vec<_>>(), vec![id(2, 0)]);
+        assert_eq!(pending[&id(2, 0)].delivered_at, 30);
+        assert!(stream.read_group("missing", "alice".into(), GroupReadFrom::New, None, 0).is_err());
+    }
+
+    #[test]
+    fn test_reading_pending_entries_delivers_them_again() {
+        // GIVEN
+        let mut stream = Stream::default();
+        for ms in 1..=2 {
+            stream.add(StreamIdSpec::Explicit(id(ms, 0)), fields("f", "v")).unwrap();
+        }
+        stream.create_group("g".into(), Some(StreamId::MIN)).unwrap();
+        stream.read_group("g", "alice".into(), GroupReadFrom::New, None, 10).unwrap();
+        stream.entries.remove(&id(1, 0));
+
+        // WHEN
+        let history = stream
+            .read_group("g", "alice".into(), GroupReadFrom::Pending(StreamId::MIN), None, 20)
+            .unwrap();
+
+        // THEN
+        assert_eq!(history, vec![(id(1, 0), None), (id(2, 0), Some(fields("f", "v")))]);
+        let pending = &stream.group("g").unwrap().pending;
+        assert!(pending.values().all(|p| p.delivered_at == 20 && p.deliveries == 2));
+        assert_eq!(
+            entries_reply(history),
+            QueryIO::Array(vec![
+                QueryIO::Array(vec![QueryIO::BulkString("1-0".into()), QueryIO::Null]),
+                QueryIO::Array(vec![
+                    QueryIO::BulkString("2-0".into()),
+                    QueryIO::Array(vec![
+                        QueryIO::BulkString("f".into()),
+                        QueryIO::BulkString("v".into())
+                    ]),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_group_created_at_last_entry_skips_existing_ones() {
+        // GIVEN
+        let mut stream = Stream::default();
+        stream.add(StreamIdSpec::Explicit(id(1, 0)), fields("f", "v")).unwrap();
+        stream.create_group("g".into(), None).unwrap();
+
+        // WHEN
+        stream.add(StreamIdSpec::Explicit(id(2, 0)), fields("f", "v")).unwrap();
+        let read = stream.read_group("g", "alice".into(), GroupReadFrom::New, None, 0).unwrap();
+
+        // THEN
+        assert_eq!(ids(read), vec![id(2, 0)]);
+    }
+
+    #[test]
+    fn test_prune_drops_waiters_that_stopped_listening() {
+        // GIVEN
+        let mut waiters = StreamWaiters::default();
+        let (gone, gone_rx) = oneshot::channel();
+        let (waiting, mut waiting_rx) = oneshot::channel();
+        waiters.wait("timed_out".into(), gone);
+        waiters.wait("s".into(), waiting);
+        drop(gone_rx);
+
+        // WHEN
+        waiters.prune();
+
+        // THEN
+        assert_eq!(waiters.0.keys().collect::<Vec<_>>(), vec!["s"]);
+        waiters.wake("s");
+        assert!(waiting_rx.try_recv().is_ok());
+    }
+}
@@ -1,13 +1,24 @@
 use super::commands::AddPeer;
 use super::commands::ClusterCommand;
 use super::commands::ConsensusClientResponse;
+use super::commands::ForwardReply;
+use super::commands::ForwardRequest;
+use super::commands::ForwardedKind;
+use super::commands::ForwardedResponse;
+use super::commands::InstallSnapshot;
+use super::commands::LogConflict;
+use super::commands::PreVote;
+use super::commands::PreVoteReply;
 use super::commands::RejectionReason;
 use super::commands::ReplicationResponse;
 use super::commands::RequestVote;
 use super::commands::RequestVoteReply;
+use super::commands::TimeoutNow;
 use super::heartbeats::heartbeat::AppendEntriesRPC;
 use super::heartbeats::heartbeat::ClusterHeartBeat;
+use super::heartbeats::scheduler::ELECTION_TIMEOUT_MIN;
 use super::heartbeats::scheduler::HeartBeatScheduler;
+use super::heartbeats::scheduler::LEADER_HEARTBEAT_INTERVAL_MAX;
 use super::peer_connections::inbound::stream::InboundStream;
 use super::peer_connections::outbound::stream::OutboundStream;
 use super::replication::BannedPeer;
@@ -20,17 +31,35 @@ use super::session::ClientSessions;
 use super::session::SessionRequest;
 use super::*;
 use crate::domains::cluster_actors::consensus::ElectionState;
+use crate::domains::cluster_actors::consensus::ElectionVoting;
+use crate::domains::cluster_actors::consensus::ForwardCallback;
+use crate::domains::cluster_actors::consensus::ForwardTracker;
+use crate::domains::cluster_actors::consensus::LeaderLease;
+use crate::domains::cluster_actors::consensus::LeadershipTransfer;
+use crate::domains::cluster_actors::consensus::MAX_BATCH_ENTRIES;
+use crate::domains::cluster_actors::consensus::Membership;
+use crate::domains::cluster_actors::consensus::ReadIndexCallback;
+use crate::domains::cluster_actors::consensus::ReadIndexTracker;
+use crate::domains::cluster_actors::consensus::take_batch;
+use crate::domains::cluster_actors::hard_state::HardState;
+use crate::domains::cluster_actors::hard_state::HardStateStore;
+use crate::domains::cluster_actors::hard_state::replace_file;
+use crate::domains::config_actors::config_manager::ConfigManager;
 use crate::domains::operation_logs::WriteOperation;
 use crate::domains::operation_logs::WriteRequest;
 use crate::domains::operation_logs::interfaces::TWriteAheadLog;
 use crate::domains::operation_logs::logger::ReplicatedLogs;
 use crate::domains::peers::cluster_peer::ClusterNode;
 use crate::domains::peers::cluster_peer::NodeKind;
+use crate::domains::saves::actor::SaveTarget;
+use crate::domains::saves::snapshot::snapshot_loader::SnapshotLoader;
 use crate::domains::{caches::cache_manager::CacheManager, query_parsers::QueryIO};
 use anyhow::Context;
+use std::collections::BTreeSet;
 use std::collections::VecDeque;
 use std::iter;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::AsyncSeekExt;
 use tokio::io::AsyncWriteExt;
@@ -47,14 +76,41 @@ pub struct ClusterActor {
     pub(crate) heartbeat_scheduler: HeartBeatScheduler,
     pub(crate) topology_writer: tokio::fs::File,
     pub(crate) node_change_broadcast: tokio::sync::broadcast::Sender<Vec<PeerIdentifier>>,
+    pub(crate) hard_state: HardStateStore,
+    // peers a snapshot is currently being prepared for
+    pub(crate) snapshot_transfers: BTreeSet<PeerIdentifier>,
+    // snapshot chunks received from the leader so far
+    pub(crate) incoming_snapshot: Vec<u8>,
+    // pre-votes gathered before running for election
+    pub(crate) pre_vote: Option<ElectionVoting>,
+    pub(crate) leader_contacted_at: Instant,
+    pub(crate) leadership_transfer: Option<LeadershipTransfer>,
+    pub(crate) membership: Membership,
+    pub(crate) read_index: ReadIndexTracker,
+    // set when reads are served under a leader lease instead of a ReadIndex round
+    pub(crate) lease: Option<LeaderLease>,
+    // how long a write may wait for consensus unless the request overrides it
+    pub(crate) write_timeout: Duration,
+    // leader this node last heard from while following
+    pub(crate) leader: Option<PeerIdentifier>,
+    // set when clients of a follower get their writes, or linearizable reads, served through the leader
+    pub(crate) forward_writes: bool,
+    pub(crate) forward_reads: bool,
+    pub(crate) forwarded: ForwardTracker,
 }
 
 impl ClusterActor {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         node_timeout: u128,
         init_repl_info: ReplicationState,
         heartbeat_interval_in_mills: u64,
         topology_writer: File,
+        hard_state: HardStateStore,
+        lease_reads: bool,
+        write_timeout: Duration,
+        forward_writes: bool,
+        forward_reads: bool,
     ) -> Self {
         let (self_handler, receiver) = tokio::sync::mpsc::channel(100);
         let heartbeat_scheduler = HeartBeatScheduler::run(
@@ -65,6 +121,14 @@ impl ClusterActor {
 
         let (tx, _) = tokio::sync::broadcast::channel::<Vec<PeerIdentifier>>(100);
 
+        let HardState { commit_index, voters, .. } = hard_state.state().clone();
+        // A node that starts out leading with no configuration bootstraps one with itself as the only voter
+        let membership = if voters.is_empty() && init_repl_info.is_leader_mode {
+            Membership::new([init_repl_info.self_identifier()], commit_index)
+        } else {
+            Membership::new(voters, commit_index)
+        };
+
         Self {
             heartbeat_scheduler,
             replication: init_repl_info,
@@ -75,6 +139,20 @@ impl ClusterActor {
             consensus_tracker: LogConsensusTracker::default(),
             topology_writer,
             node_change_broadcast: tx,
+            hard_state,
+            snapshot_transfers: BTreeSet::new(),
+            incoming_snapshot: Vec::new(),
+            pre_vote: None,
+            leader_contacted_at: Instant::now(),
+            leadership_transfer: None,
+            membership,
+            read_index: ReadIndexTracker::default(),
+            lease: lease_reads.then(LeaderLease::default),
+            write_timeout,
+            leader: None,
+            forward_writes,
+            forward_reads,
+            forwarded: ForwardTracker::default(),
         }
     }
 
@@ -103,6 +181,12 @@ impl ClusterActor {
         self.members.get_mut(peer_id).filter(|peer| matches!(peer.kind, PeerState::Replica { .. }))
     }
 
+    /// Connected peers that vote in the committed configuration.
+    fn voters_mut(&mut self) -> impl Iterator<Item = &mut Peer> {
+        let voters = self.membership.voters();
+        self.members.iter_mut().filter(move |(id, _)| voters.contains(*id)).map(|(_, peer)| peer)
+    }
+
     pub(crate) async fn send_cluster_heartbeat(
         &mut self,
         hop_count: u8,
@@ -294,8 +378,10 @@ impl ClusterActor {
         if let Some(peer) = self.members.get_mut(from) {
             peer.last_seen = Instant::now();
 
+            // Late acks and gossiped commit indexes lag behind, so they must not move it back.
+            // Only a rejection does, through `decrease_match_index`.
             if let PeerState::Replica { match_index, .. } = &mut peer.kind {
-                *match_index = log_index;
+                *match_index = (*match_index).max(log_index);
             }
         }
     }
@@ -303,24 +389,23 @@ impl ClusterActor {
         &mut self,
         logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
         log: &WriteRequest,
-    ) -> Result<Vec<WriteOperation>, ConsensusClientResponse> {
+    ) -> Result<WriteOperation, ConsensusClientResponse> {
         if !self.replication.is_leader_mode {
-            return Err(ConsensusClientResponse::Err("Write given to follower".into()));
+            return Err(match self.known_leader() {
+                Some(leader) => ConsensusClientResponse::Moved(leader),
+                None => ConsensusClientResponse::Err("Write given to follower".into()),
+            });
+        }
+        if self.leadership_transfer.is_some() {
+            return Err(ConsensusClientResponse::Err("Leadership transfer in progress".into()));
         }
 
-        let Ok(append_entries) = logger
-            .leader_write_entries(log, self.take_low_watermark(), self.replication.term)
-            .await
-        else {
+        let Ok(entry) = logger.leader_write_entry(log, self.replication.term).await else {
             return Err(ConsensusClientResponse::Err("Write operation failed".into()));
         };
+        self.membership.record(std::slice::from_ref(&entry));
 
-        // Skip consensus for no replicas
-        if self.replicas().count() == 0 {
-            return Err(ConsensusClientResponse::LogIndex(Some(logger.last_log_index)));
-        }
-
-        Ok(append_entries)
+        Ok(entry)
     }
 
     pub(crate) async fn req_consensus(
@@ -329,29 +414,22 @@ impl ClusterActor {
         log: WriteRequest,
         callback: tokio::sync::oneshot::Sender<ConsensusClientResponse>,
         session_req: Option<SessionRequest>,
+        timeout: Option<Duration>,
     ) {
-        let (prev_log_index, prev_term) = (logger.last_log_index, logger.last_log_term);
-        let append_entries = match self.try_create_append_entries(logger, &log).await {
-            Ok(entries) => entries,
-            Err(err) => {
-                let _ = callback.send(err);
-                return;
-            },
-        };
+        if let Err(err) = self.try_create_append_entries(logger, &log).await {
+            let _ = callback.send(err);
+            return;
+        }
 
         self.consensus_tracker.add(
             logger.last_log_index,
             callback,
-            self.replicas().count(),
             session_req,
+            timeout.unwrap_or(self.write_timeout),
         );
 
-        // dbg!(self.replicas().count()); // CHECKED. replica count right.
-        self.generate_follower_entries(append_entries, prev_log_index, prev_term)
-            .map(|(peer, hb)| peer.send_to_peer(AppendEntriesRPC(hb)))
-            .collect::<FuturesUnordered<_>>()
-            .for_each(|_| async {})
-            .await;
+        // Followers whose window is full get the entry batched with later ones once they catch up
+        self.replicate_to_followers(logger).await;
     }
 
     pub(crate) async fn install_leader_state(
@@ -360,61 +438,258 @@ impl ClusterActor {
         cache_manager: &CacheManager,
     ) {
         println!("[INFO] Received Leader State - length {}", logs.len());
+        // The log was replaced as a whole, so are the configurations it carries
+        self.membership = Membership::default();
+        self.membership.record(&logs);
         if logs.is_empty() {
             return;
         }
         let last_log_idx = logs.last().unwrap().log_index;
         for log in logs {
-            let _ = cache_manager.apply_log(log.request).await;
+            let _ = cache_manager.apply_log(log.request, log.log_index).await;
         }
         self.replication.hwm.store(last_log_idx, Ordering::Release);
+        self.membership.commit(last_log_idx);
+        self.persist_commit_index().await;
     }
 
-    /// create entries per follower.
-    pub(crate) fn generate_follower_entries(
+    /// Re-applies entries that were committed before a restart but are not covered by the loaded snapshot.
+    pub(crate) async fn replay_committed_logs(
         &mut self,
-        append_entries: Vec<WriteOperation>,
-        prev_log_index: u64,
-        prev_term: u64,
-    ) -> impl Iterator<Item = (&mut Peer, HeartBeatMessage)> {
-        let default_heartbeat: HeartBeatMessage =
-            self.replication.default_heartbeat(0, prev_log_index, prev_term);
-        self.replicas_mut().map(move |(peer, hwm)| {
-            let logs =
-                append_entries.iter().filter(|op| op.log_index > hwm).cloned().collect::<Vec<_>>();
-            (peer, default_heartbeat.clone().set_append_entries(logs))
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        let applied = self.replication.hwm.load(Ordering::Acquire);
+        let commit_index = self.hard_state.state().commit_index.min(logger.last_log_index);
+        if commit_index <= applied {
+            return;
+        }
+
+        println!("[INFO] Replaying committed logs from {} to {}", applied + 1, commit_index);
+        for log in logger.range(applied, commit_index) {
+            if let Err(e) = cache_manager.apply_log(log.request, log.log_index).await {
+                println!("[ERROR] Failed to apply log: {:?}", e);
+                return;
+            }
+            self.replication.hwm.store(log.log_index, Ordering::Release);
+        }
+    }
+
+    /// Picks up configurations appended before a restart that were not committed yet.
+    pub(crate) fn restore_membership(&mut self, logger: &ReplicatedLogs<impl TWriteAheadLog>) {
+        let commit_index = self.hard_state.state().commit_index;
+        self.membership.record(&logger.range(commit_index, logger.last_log_index));
+    }
+
+    /// Drops log entries covered by a snapshot taken at `snapshot_index`.
+    /// Replicas that still needed any of them are caught up with a snapshot instead,
+    /// so a dead or lagging replica never holds compaction back.
+    pub(crate) async fn compact_logs(
+        &mut self,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+        snapshot_index: u64,
+    ) {
+        let Some(term) = logger.term_at(snapshot_index).await else {
+            println!("[ERROR] No term known for snapshot index {}", snapshot_index);
+            return;
+        };
+        if self.persist_snapshot_point(snapshot_index, term).await.is_err() {
+            return;
+        }
+        if let Err(e) = logger.compact_up_to(snapshot_index, term).await {
+            println!("[ERROR] Failed to compact logs: {:?}", e);
+            return;
+        }
+        if !self.replication.is_leader_mode {
+            return;
+        }
+
+        let log_start_index = logger.log_start_index();
+        let behind = self
+            .replicas()
+            .filter(|(_, _, match_index)| match_index + 1 < log_start_index)
+            .map(|(id, _, _)| id.clone())
+            .collect::<Vec<_>>();
+        for id in behind {
+            self.prepare_snapshot_for(id, logger, cache_manager).await;
+        }
+    }
+
+    async fn persist_commit_index(&mut self) {
+        let commit_index = self.replication.hwm.load(Ordering::Acquire);
+        let state = HardState {
+            commit_index,
+            voters: self.membership.voters().iter().cloned().collect(),
+            ..self.hard_state.state().clone()
+        };
+        if let Err(e) = self.hard_state.save(state).await {
+            println!("[ERROR] Failed to persist commit index: {:?}", e);
+        }
+    }
+
+    /// Durably records where the snapshot on disk ends. Must succeed before the entries it covers are dropped.
+    async fn persist_snapshot_point(
+        &mut self,
+        last_included_index: u64,
+        last_included_term: u64,
+    ) -> anyhow::Result<()> {
+        let state = HardState {
+            last_included_index,
+            last_included_term,
+            ..self.hard_state.state().clone()
+        };
+        self.hard_state.save(state).await.inspect_err(|e| {
+            println!("[ERROR] Failed to persist snapshot point: {:?}", e);
         })
     }
 
-    pub(crate) fn take_low_watermark(&self) -> Option<u64> {
-        self.members
-            .values()
-            .filter_map(|peer| match &peer.kind {
-                PeerState::Replica { match_index: watermark, .. } => Some(*watermark),
-                _ => None,
-            })
-            .min()
+    /// Durably records `term` and `voted_for`. Must succeed before the node acts on either of them.
+    async fn persist_term_and_vote(
+        &mut self,
+        term: u64,
+        voted_for: Option<PeerIdentifier>,
+    ) -> anyhow::Result<()> {
+        let state = HardState { term, voted_for, ..self.hard_state.state().clone() };
+        self.hard_state.save(state).await.inspect_err(|e| {
+            println!("[ERROR] Failed to persist hard state: {:?}", e);
+        })
     }
 
-    pub(crate) fn track_replication_progress(
+    /// Commits the highest entry a majority of voters has replicated, as long as it belongs to the current term,
+    /// and applies every entry up to it in order. Entries of earlier terms get committed along with it.
+    pub(crate) async fn advance_commit_index(
         &mut self,
-        res: ReplicationResponse,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
         sessions: &mut ClientSessions,
     ) {
-        let Some(mut consensus) = self.consensus_tracker.remove(&res.log_idx) else {
+        if !self.replication.is_leader_mode {
             return;
-        };
+        }
+
+        let self_id = self.replication.self_identifier();
+        let match_indexes: BTreeMap<_, _> =
+            self.replicas().map(|(id, _, match_index)| (id.clone(), match_index)).collect();
+        let commit_index = self
+            .membership
+            .quorum_index(|voter| {
+                if voter == &self_id {
+                    logger.last_log_index
+                } else {
+                    match_indexes.get(voter).copied().unwrap_or_default()
+                }
+            })
+            .unwrap_or(logger.last_log_index);
+
+        let applied = self.replication.hwm.load(Ordering::Acquire);
+        if commit_index <= applied {
+            return;
+        }
+        match logger.read_at(commit_index).await {
+            Some(log) if log.term == self.replication.term => {},
+            _ => return,
+        }
+
+        let mut replies = Vec::new();
+        for log in logger.range(applied, commit_index) {
+            let log_index = log.log_index;
+            let res = match cache_manager.apply_log(log.request, log_index).await {
+                Ok(res) => ConsensusClientResponse::Result(res),
+                Err(e) => {
+                    println!("[ERROR] Failed to apply log: {:?}", e);
+                    ConsensusClientResponse::Err(e.to_string())
+                },
+            };
+            self.replication.hwm.store(log_index, Ordering::Release);
+
+            if let Some(pending) = self.consensus_tracker.remove(&log_index) {
+                replies.push((pending, res));
+            }
+        }
+        self.membership.commit(commit_index);
+        // Followers hear of the commit before the clients waiting on it do
+        self.send_commit_heartbeat().await;
+        for (pending, res) in replies {
+            if let ConsensusClientResponse::Result(res) = &res {
+                sessions.set_response(pending.session_req, res);
+            }
+            let _ = pending.callback.send(res);
+        }
+        // wake reads that were waiting for the new commit index
+        cache_manager.pings().await;
+    }
 
-        if consensus.votable(&res.from) {
-            println!("[INFO] Received acks for log index num: {}", res.log_idx);
-            consensus.increase_vote(res.from);
+    /// Fails the writes that waited for consensus past their deadline, so their clients aren't left hanging.
+    pub(crate) fn expire_consensus_requests(&mut self) {
+        let timed_out = self.consensus_tracker.expire();
+        if timed_out > 0 {
+            println!("[WARN] {} write(s) timed out before reaching consensus", timed_out);
         }
-        if consensus.cnt < consensus.get_required_votes() {
-            self.consensus_tracker.insert(res.log_idx, consensus);
+        self.replication.timed_out_writes += timed_out;
+    }
+
+    /// Leader this follower last heard from, or else the node it voted for in the current term.
+    pub(crate) fn known_leader(&self) -> Option<PeerIdentifier> {
+        self.leader
+            .clone()
+            .or_else(|| self.replication.voted_for())
+            .filter(|leader| *leader != self.replication.self_identifier())
+    }
+
+    pub(crate) fn forwards_writes(&self) -> bool {
+        self.forward_writes && !self.replication.is_leader_mode
+    }
+
+    pub(crate) fn forwards_reads(&self) -> bool {
+        self.forward_reads && !self.replication.is_leader_mode
+    }
+
+    /// Sends `kind` to the leader this node follows, and relays the leader's answer to `callback`.
+    pub(crate) async fn forward_to_leader(
+        &mut self,
+        kind: ForwardedKind,
+        callback: ForwardCallback,
+        timeout: Option<Duration>,
+    ) {
+        let Some(leader) = self.leader.clone() else {
+            callback.fail("ERR no leader to forward to");
             return;
+        };
+        let seq = self.forwarded.add(callback, timeout.unwrap_or(self.write_timeout));
+        let request = ForwardRequest { seq, from: self.replication.self_identifier(), kind };
+        let sent = match self.members.get_mut(&leader) {
+            Some(peer) => peer.send_to_peer(request).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            self.forwarded.fail(seq, "ERR no leader to forward to");
+        }
+    }
+
+    /// Callback for a request `to` forwarded. Whatever it is given gets sent back to `to`.
+    pub(crate) fn reply_to_forwarded<T: Into<ForwardedResponse> + Send + 'static>(
+        &self,
+        to: PeerIdentifier,
+        seq: u64,
+    ) -> tokio::sync::oneshot::Sender<T> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<T>();
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            let response = match rx.await {
+                Ok(res) => res.into(),
+                Err(_) => ForwardedResponse::Err("ERR leader dropped the request".into()),
+            };
+            let reply = ForwardReply { seq, response };
+            let _ = handler.send(ClusterCommand::SendForwardReply { to, reply }).await;
+        });
+        tx
+    }
+
+    pub(crate) async fn send_forward_reply(&mut self, to: &PeerIdentifier, reply: ForwardReply) {
+        if let Some(peer) = self.members.get_mut(to) {
+            let _ = peer.send_to_peer(reply).await;
         }
-        sessions.set_response(consensus.session_req.take());
-        let _ = consensus.callback.send(ConsensusClientResponse::LogIndex(Some(res.log_idx)));
     }
 
     // After send_ack: Leader updates its knowledge of follower's progress
@@ -435,10 +710,10 @@ impl ClusterActor {
         }
     }
 
-    pub(crate) async fn send_commit_heartbeat(&mut self, offset: u64) {
-        // TODO is there any case where I can use offset input?
-        self.replication.hwm.fetch_add(1, Ordering::Relaxed);
+    async fn send_commit_heartbeat(&mut self) {
+        self.persist_commit_index().await;
 
+        let offset = self.replication.hwm.load(Ordering::Acquire);
         let message: HeartBeatMessage =
             self.replication.default_heartbeat(0, offset, self.replication.term);
         println!("[INFO] log {} commited", message.hwm);
@@ -451,13 +726,26 @@ impl ClusterActor {
         mut heartbeat: HeartBeatMessage,
         cache_manager: &CacheManager,
     ) {
+        // Accepting the heartbeat confirms the sender still leads, whatever happens to its entries
+        if heartbeat.read_seq != 0 {
+            let ack = ReplicationResponse::new(
+                wal.last_log_index,
+                RejectionReason::None,
+                &self.replication,
+            )
+            .with_read_seq(heartbeat.read_seq);
+            if let Some(leader) = self.members.get_mut(&heartbeat.from) {
+                let _ = leader.send_to_peer(ack).await;
+            }
+        }
+
         // * logging case
         if self.try_replicate_logs(wal, &mut heartbeat).await.is_err() {
             return;
         };
 
         // * state machine case
-        self.replicate_state(heartbeat.hwm, wal, cache_manager).await;
+        self.replicate_state(&heartbeat.from, heartbeat.hwm, wal, cache_manager).await;
     }
 
     async fn try_replicate_logs(
@@ -469,51 +757,78 @@ impl ClusterActor {
             return Ok(());
         }
 
-        if let Err(e) =
+        if let Err(conflict) =
             self.ensure_prev_consistency(wal, rpc.prev_log_index, rpc.prev_log_term).await
         {
-            self.send_ack(&rpc.from, wal.last_log_index, e).await;
+            let rejection = ReplicationResponse::new(
+                wal.last_log_index,
+                RejectionReason::LogInconsistency,
+                &self.replication,
+            )
+            .with_conflict(conflict);
+            if let Some(leader) = self.members.get_mut(&rpc.from) {
+                let _ = leader.send_to_peer(rejection).await;
+            }
             return Err(anyhow::anyhow!("Fail fail to append"));
         }
 
-        let match_index =
-            wal.follower_write_entries(std::mem::take(&mut rpc.append_entries)).await?;
+        let entries = std::mem::take(&mut rpc.append_entries);
+        if let Some(log_index) = wal.follower_write_entries(&entries).await? {
+            self.membership.truncate_after(log_index);
+        }
+        self.membership.record(&entries);
 
+        // Only the entries up to the last one sent are known to match the leader's.
+        // The log may hold more past them, left over from an older leader.
+        let match_index = entries.last().map_or(rpc.prev_log_index, |op| op.log_index);
         self.send_ack(&rpc.from, match_index, RejectionReason::None).await;
         Ok(())
     }
 
+    /// Rejects entries whose previous entry the follower doesn't hold.
+    /// When it holds one from another term, the error says which term and where that term starts.
     async fn ensure_prev_consistency(
-        &self,
+        &mut self,
         wal: &mut ReplicatedLogs<impl TWriteAheadLog>,
         prev_log_index: u64,
         prev_log_term: u64,
-    ) -> Result<(), RejectionReason> {
+    ) -> Result<(), Option<LogConflict>> {
         // Case 1: Empty log
         if wal.is_empty() {
             if prev_log_index == 0 {
                 return Ok(()); // First entry, no previous log to check
             }
+            if prev_log_index == wal.last_log_index && prev_log_term == wal.last_log_term {
+                return Ok(()); // Log was reset by a snapshot ending right at the previous entry
+            }
             println!("[ERROR] Log is empty but leader expects an entry");
-            return Err(RejectionReason::LogInconsistency); // Log empty but leader expects an entry
+            return Err(None); // Log empty but leader expects an entry
         }
 
         // Case 2: Previous index is before the log’s start (compacted/truncated)
         if prev_log_index < wal.log_start_index() {
             println!("[ERROR] Previous log index is before the log’s start");
-            return Err(RejectionReason::LogInconsistency); // Leader references an old, unavailable entry
+            return Err(None); // Leader references an old, unavailable entry
+        }
+
+        // Case 3: Previous index is past the end of the log; the leader resends from where it ends
+        if prev_log_index > wal.last_log_index {
+            println!("[ERROR] Log ends at {} before the previous log index", wal.last_log_index);
+            return Err(None);
         }
 
-        // * Raft followers should truncate their log starting at prev_log_index + 1 and then append the new entries
-        // * Just returning an error is breaking consistency
+        // * The entry at prev_log_index conflicts with the leader's, so it goes along with everything after it.
+        // * The leader resends from an earlier entry, and the follower appends them in their place
         if let Some(prev_entry) = wal.read_at(prev_log_index).await {
             println!("[INFO] Previous log entry: {:?}", prev_entry);
             if prev_entry.term != prev_log_term {
                 // ! Term mismatch -> triggers log truncation
                 println!("[ERROR] Term mismatch: {} != {}", prev_entry.term, prev_log_term);
-                wal.truncate_after(prev_log_index).await;
+                let first_index = wal.first_index_of_term(prev_entry.term).await;
+                wal.truncate_after(prev_log_index - 1).await;
+                self.membership.truncate_after(prev_log_index - 1);
 
-                return Err(RejectionReason::LogInconsistency);
+                return Err(Some(LogConflict { term: prev_entry.term, first_index }));
             }
         }
 
@@ -524,12 +839,85 @@ impl ClusterActor {
         &mut self,
         logger: &ReplicatedLogs<impl TWriteAheadLog>,
     ) {
-        self.send_to_replicas(AppendEntriesRPC(self.replication.default_heartbeat(
-            0,
-            logger.last_log_index,
-            logger.last_log_term,
-        )))
-        .await;
+        // Every heartbeat asks followers to confirm, which renews the lease and shows they are still reachable
+        let read_seq = self.read_index.probe_seq();
+        if let Some(lease) = self.lease.as_mut() {
+            lease.record_sent(read_seq);
+        }
+        let heartbeat = HeartBeatMessage {
+            read_seq,
+            ..self.replication.default_heartbeat(0, logger.last_log_index, logger.last_log_term)
+        };
+        self.send_to_replicas(AppendEntriesRPC(heartbeat)).await;
+    }
+
+    /// Answers with the commit index a linearizable read has to wait for, once a quorum confirms this node still leads.
+    /// Under a lease, the leader answers right away and refuses the read once the lease has lapsed
+    /// or while it hands leadership over, as the target may win an election before the lease runs out.
+    /// Followers answer `None` and serve reads from their own state.
+    pub(crate) async fn read_index(
+        &mut self,
+        callback: ReadIndexCallback,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        if !self.replication.is_leader_mode {
+            let _ = callback.send(Ok(None));
+            return;
+        }
+
+        let read_index = self.replication.hwm.load(Ordering::Acquire);
+        let required_acks = self.membership.required_acks(&self.replication.self_identifier());
+        if let Some(lease) = &self.lease {
+            let res = if self.leadership_transfer.is_some() {
+                Err(anyhow::anyhow!(
+                    "ERR leadership transfer in progress, retry on the current leader"
+                ))
+            } else if lease.is_valid(self.membership.voters(), required_acks) {
+                Ok(Some(read_index))
+            } else {
+                Err(anyhow::anyhow!("ERR leader lease expired, retry on the current leader"))
+            };
+            let _ = callback.send(res);
+            return;
+        }
+
+        if self
+            .read_index
+            .start(
+                read_index,
+                required_acks,
+                callback,
+                Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX),
+            )
+            .is_some()
+        {
+            self.send_leader_heartbeat(logger).await;
+        }
+    }
+
+    pub(crate) fn confirm_read_index(&mut self, res: ReplicationResponse) {
+        if let Some(peer) = self.members.get_mut(&res.from) {
+            peer.last_seen = Instant::now();
+        }
+        // Any follower that acked holds off elections for a while, but the lease only counts voters when it is checked
+        self.renew_lease(&res.from, res.read_seq);
+        // Only voters of the committed configuration can confirm leadership
+        if self.replication.is_leader_mode && self.membership.is_voter(&res.from) {
+            self.read_index.ack(res.read_seq, &res.from);
+        }
+    }
+
+    fn revoke_lease(&mut self) {
+        if let Some(lease) = self.lease.as_mut() {
+            lease.revoke();
+        }
+    }
+
+    /// Only acknowledged heartbeat rounds renew the lease; gossip doesn't mean the sender still follows this node.
+    fn renew_lease(&mut self, from: &PeerIdentifier, read_seq: u64) {
+        if let Some(lease) = self.lease.as_mut().filter(|_| self.replication.is_leader_mode) {
+            lease.record(from, read_seq);
+        }
     }
 
     async fn send_to_replicas(&mut self, msg: impl Into<QueryIO> + Send + Clone) {
@@ -555,29 +943,142 @@ impl ClusterActor {
             .collect()
     }
 
-    pub(crate) async fn run_for_election(
+    /// Asks peers whether this node could win an election, without bumping its term yet.
+    pub(crate) async fn start_pre_vote(&mut self, logger: &ReplicatedLogs<impl TWriteAheadLog>) {
+        if let ElectionState::Leader = self.replication.election_state {
+            return;
+        }
+        // Only voters of the committed configuration may campaign
+        let self_id = self.replication.self_identifier();
+        if !self.membership.is_voter(&self_id) {
+            return;
+        }
+
+        self.pre_vote = Some(ElectionVoting {
+            cnt: 0,
+            replica_count: self.membership.peer_voter_count(&self_id),
+        });
+        let pre_vote = PreVote(RequestVote {
+            term: self.replication.term + 1,
+            ..RequestVote::new(&self.replication, logger.last_log_index, logger.last_log_term)
+        });
+
+        println!("[INFO] Requesting pre-votes for term {}", pre_vote.0.term);
+        self.voters_mut()
+            .map(|peer| peer.send_to_peer(pre_vote.clone()))
+            .collect::<FuturesUnordered<_>>()
+            .for_each(|_| async {})
+            .await;
+    }
+
+    pub(crate) async fn pre_vote_election(
+        &mut self,
+        request_vote: RequestVote,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        let grant_vote = self.grants_pre_vote(&request_vote, logger);
+        println!(
+            "[INFO] Pre-voting for {} with term {} and granted: {}",
+            request_vote.candidate_id, request_vote.term, grant_vote
+        );
+
+        let term = if grant_vote { request_vote.term } else { self.replication.term };
+        let Some(peer) = self.find_replica_mut(&request_vote.candidate_id) else {
+            return;
+        };
+        let _ = peer
+            .send_to_peer(PreVoteReply(RequestVoteReply { term, vote_granted: grant_vote }))
+            .await;
+    }
+
+    fn grants_pre_vote(
+        &self,
+        request_vote: &RequestVote,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+    ) -> bool {
+        // Nodes that still hear from a leader won't help anyone replace it
+        let leader_alive = self.replication.is_leader_mode
+            || self.leader_contacted_at.elapsed() < Duration::from_millis(ELECTION_TIMEOUT_MIN);
+
+        !leader_alive
+            && request_vote.term > self.replication.term
+            && request_vote.is_log_up_to_date(logger.last_log_index, logger.last_log_term)
+    }
+
+    pub(crate) async fn tally_pre_vote(
         &mut self,
+        reply: RequestVoteReply,
         logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
     ) {
-        let ElectionState::Follower { voted_for: None } = &self.replication.election_state else {
+        if !reply.vote_granted {
+            self.maybe_update_term(reply.term).await;
+            return;
+        }
+        // Granted pre-votes carry the term this node asked for
+        if reply.term != self.replication.term + 1 {
+            return;
+        }
+        let Some(voting) = self.pre_vote.take() else {
             return;
         };
+        self.pre_vote = voting.voting_if_unfinished();
+        if self.pre_vote.is_none() {
+            self.run_for_election(logger).await;
+        }
+    }
+
+    pub(crate) async fn run_for_election(
+        &mut self,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        if let ElectionState::Leader = self.replication.election_state {
+            return;
+        }
 
+        self.pre_vote = None;
         self.become_candidate();
+        if self
+            .persist_term_and_vote(self.replication.term, self.replication.voted_for())
+            .await
+            .is_err()
+        {
+            self.step_down().await;
+            return;
+        }
+
         let request_vote =
-            RequestVote::new(&self.replication, logger.last_log_index, logger.last_log_index);
+            RequestVote::new(&self.replication, logger.last_log_index, logger.last_log_term);
 
         println!("[INFO] Running for election term {}", self.replication.term);
-        self.replicas_mut()
-            .map(|(peer, _)| peer.send_to_peer(request_vote.clone()))
+        self.voters_mut()
+            .map(|peer| peer.send_to_peer(request_vote.clone()))
             .collect::<FuturesUnordered<_>>()
             .for_each(|_| async {})
             .await;
     }
 
-    pub(crate) async fn vote_election(&mut self, request_vote: RequestVote, current_log_idx: u64) {
-        let grant_vote = current_log_idx <= request_vote.last_log_index
-            && self.replication.may_become_follower(&request_vote.candidate_id, request_vote.term);
+    pub(crate) async fn vote_election(
+        &mut self,
+        request_vote: RequestVote,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        // A newer term cancels whatever this node voted for or ran for before
+        self.maybe_update_term(request_vote.term).await;
+
+        let mut grant_vote = request_vote
+            .is_log_up_to_date(logger.last_log_index, logger.last_log_term)
+            && self.replication.can_vote_for(&request_vote.candidate_id, request_vote.term);
+
+        // The vote must be durable before the candidate hears about it
+        if grant_vote {
+            grant_vote = self
+                .persist_term_and_vote(request_vote.term, Some(request_vote.candidate_id.clone()))
+                .await
+                .is_ok()
+                && self
+                    .replication
+                    .may_become_follower(&request_vote.candidate_id, request_vote.term);
+        }
 
         println!(
             "[INFO] Voting for {} with term {} and granted: {}",
@@ -591,64 +1092,405 @@ impl ClusterActor {
         let _ = peer.send_to_peer(RequestVoteReply { term, vote_granted: grant_vote }).await;
     }
 
-    pub(crate) async fn tally_vote(&mut self, logger: &ReplicatedLogs<impl TWriteAheadLog>) {
+    pub(crate) async fn tally_vote(
+        &mut self,
+        reply: RequestVoteReply,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        if reply.term > self.replication.term {
+            self.maybe_update_term(reply.term).await;
+            return;
+        }
+        // Replies to an earlier candidacy must not count toward this one
+        if !reply.vote_granted || reply.term != self.replication.term {
+            return;
+        }
         if !self.replication.election_state.may_become_leader() {
             return;
         }
         self.become_leader().await;
-
-        let msg =
-            self.replication.default_heartbeat(0, logger.last_log_index, logger.last_log_term);
-        self.replicas_mut()
-            .map(|(peer, _)| peer.send_to_peer(AppendEntriesRPC(msg.clone())))
-            .collect::<FuturesUnordered<_>>()
-            .for_each(|_| async {})
-            .await;
+        self.append_noop(logger).await;
     }
 
-    pub(crate) fn reset_election_timeout(&mut self, leader_id: &PeerIdentifier) {
-        if let Some(peer) = self.members.get_mut(leader_id) {
-            peer.last_seen = Instant::now();
-        }
-        self.heartbeat_scheduler.reset_election_timeout();
-        self.replication.election_state = ElectionState::Follower { voted_for: None };
+    /// A leader may only count replicas for entries of its own term.
+    /// Committing a no-op right away commits whatever earlier terms left behind, without waiting for a client write.
+    async fn append_noop(&mut self, logger: &mut ReplicatedLogs<impl TWriteAheadLog>) {
+        let (tx, _) = tokio::sync::oneshot::channel();
+        self.req_consensus(logger, WriteRequest::NoOp, tx, None, None).await;
     }
 
-    async fn replicate_state(
+    /// Takes a voter out of the configuration, leaving it a learner if it is still around.
+    /// Voters are only ever removed this way, so a voter that is merely unreachable keeps its vote.
+    pub(crate) async fn demote_voter(
         &mut self,
-        heartbeat_hwm: u64,
-        wal: &mut ReplicatedLogs<impl TWriteAheadLog>,
-        cache_manager: &CacheManager,
+        voter: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
     ) {
-        let old_hwm = self.replication.hwm.load(Ordering::Acquire);
-        if heartbeat_hwm > old_hwm {
-            println!("[INFO] Received commit offset {}", heartbeat_hwm);
-
-            for log_index in (old_hwm + 1)..=heartbeat_hwm {
-                let Some(log) = wal.read_at(log_index).await else {
-                    println!("[ERROR] log has never been replicated!");
-                    return;
-                };
-
-                if let Err(e) = cache_manager.apply_log(log.request).await {
-                    println!("[ERROR] Failed to apply log: {:?}", e);
-                    return; // Stop on first error
-                }
-            }
-            self.replication.hwm.store(heartbeat_hwm, Ordering::Release);
+        let rejection = if !self.replication.is_leader_mode {
+            Some("ERR not a leader")
+        } else if !self.membership.latest().contains(&voter) {
+            Some("ERR not a voter")
+        } else if voter == self.replication.self_identifier() {
+            Some("ERR transfer leadership before demoting the leader")
+        } else if self.membership.has_pending() || self.leadership_transfer.is_some() {
+            Some("ERR configuration change in progress")
+        } else {
+            None
+        };
+        if let Some(err) = rejection {
+            let _ = callback.send(Err(anyhow::anyhow!(err)));
+            return;
         }
-    }
 
-    pub(crate) fn maybe_update_term(&mut self, new_term: u64) {
-        if new_term > self.replication.term {
-            self.replication.term = new_term;
-            self.replication.election_state = ElectionState::Follower { voted_for: None };
-            self.replication.is_leader_mode = false;
-            self.replication.role = ReplicationRole::Follower;
-        }
+        let mut voters = self.membership.latest().clone();
+        voters.remove(&voter);
+        self.propose_configuration(voters, logger, Some(callback)).await;
     }
 
-    pub(crate) async fn check_term_outdated(
+    /// Adds a learner to the voting configuration once its log has caught up with this leader's.
+    pub(crate) async fn promote_learner(
+        &mut self,
+        learner: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        let match_index = self.replicas().find(|(id, _, _)| **id == learner).map(|(_, _, idx)| idx);
+        let rejection = if !self.replication.is_leader_mode {
+            Some("ERR not a leader")
+        } else if match_index.is_none() {
+            Some("ERR no such replica")
+        } else if self.membership.latest().contains(&learner) {
+            Some("ERR already a voter")
+        } else if self.membership.has_pending() || self.leadership_transfer.is_some() {
+            Some("ERR configuration change in progress")
+        } else if match_index < Some(logger.last_log_index) {
+            Some("ERR learner has not caught up")
+        } else {
+            None
+        };
+        if let Some(err) = rejection {
+            let _ = callback.send(Err(anyhow::anyhow!(err)));
+            return;
+        }
+
+        let mut voters = self.membership.latest().clone();
+        voters.insert(learner);
+        self.propose_configuration(voters, logger, Some(callback)).await;
+    }
+
+    /// Appends `voters` as the next configuration and commits it in the background.
+    async fn propose_configuration(
+        &mut self,
+        voters: BTreeSet<PeerIdentifier>,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+        callback: Option<tokio::sync::oneshot::Sender<anyhow::Result<()>>>,
+    ) {
+        println!("[INFO] Proposing configuration {:?}", voters);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let log = WriteRequest::Configuration { voters: voters.into_iter().collect() };
+        self.req_consensus(logger, log, tx, None, None).await;
+
+        let Some(callback) = callback else {
+            return;
+        };
+        tokio::spawn(async move {
+            let res = match rx.await {
+                Ok(ConsensusClientResponse::Result(_)) => Ok(()),
+                Ok(ConsensusClientResponse::Err(e)) => Err(anyhow::anyhow!(e)),
+                _ => Err(anyhow::anyhow!("ERR configuration change was not committed")),
+            };
+            let _ = callback.send(res);
+        });
+    }
+
+    /// Hands leadership over to `target` once its log has caught up with this leader's.
+    pub(crate) async fn transfer_leadership(
+        &mut self,
+        target: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        let rejection = if !self.replication.is_leader_mode {
+            Some("ERR not a leader")
+        } else if self.leadership_transfer.is_some() {
+            Some("ERR leadership transfer already in progress")
+        } else if self.find_replica_mut(&target).is_none() {
+            Some("ERR no such replica")
+        } else if !self.membership.is_voter(&target) {
+            Some("ERR target is not a voter")
+        } else {
+            None
+        };
+        if let Some(err) = rejection {
+            let _ = callback.send(Err(anyhow::anyhow!(err)));
+            return;
+        }
+
+        println!("[INFO] Transferring leadership to {}", target);
+        self.leadership_transfer = Some(LeadershipTransfer::new(
+            target,
+            callback,
+            Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX),
+        ));
+        self.continue_leadership_transfer(logger, cache_manager).await;
+    }
+
+    /// Sends the transfer target whatever it is missing, then tells it to start an election.
+    pub(crate) async fn continue_leadership_transfer(
+        &mut self,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        let Some(transfer) = self.leadership_transfer.as_ref() else {
+            return;
+        };
+        if transfer.timeout_now_sent {
+            return;
+        }
+        let target = transfer.target.clone();
+        let Some(match_index) =
+            self.replicas().find(|(id, _, _)| **id == target).map(|(_, _, idx)| idx)
+        else {
+            self.finish_leadership_transfer(Err(anyhow::anyhow!("ERR no such replica")));
+            return;
+        };
+
+        if match_index < logger.last_log_index {
+            self.replicate_or_snapshot(target, logger, cache_manager).await;
+            return;
+        }
+
+        // Sent at most once. If the target never takes over, the transfer simply expires
+        if let Some(transfer) = self.leadership_transfer.as_mut() {
+            transfer.timeout_now_sent = true;
+        }
+        // The target campaigns right away, without waiting out the election timeout the lease relies on
+        self.revoke_lease();
+        let timeout_now =
+            TimeoutNow { term: self.replication.term, from: self.replication.self_identifier() };
+        if let Some(peer) = self.find_replica_mut(&target) {
+            println!("[INFO] Sending TimeoutNow to {}", target);
+            let _ = peer.send_to_peer(timeout_now).await;
+        }
+    }
+
+    /// Sends `to` every entry after `match_index`, falling back to a snapshot when they were compacted away.
+    /// Whatever was in flight to it is sent again.
+    async fn send_entries_since(
+        &mut self,
+        to: PeerIdentifier,
+        match_index: u64,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        if let Some(peer) = self.find_replica_mut(&to) {
+            peer.inflight.reset(match_index);
+        }
+        self.replicate_or_snapshot(to, logger, cache_manager).await;
+    }
+
+    async fn replicate_or_snapshot(
+        &mut self,
+        to: PeerIdentifier,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        if !self.replicate_to(&to, logger).await {
+            self.prepare_snapshot_for(to, logger, cache_manager).await;
+        }
+    }
+
+    async fn replicate_to_followers(&mut self, logger: &ReplicatedLogs<impl TWriteAheadLog>) {
+        let replicas = self.replicas().map(|(id, _, _)| id.clone()).collect::<Vec<_>>();
+        for id in replicas {
+            self.replicate_to(&id, logger).await;
+        }
+    }
+
+    /// Streams `to` the entries after the ones it was already sent, in batches, until its inflight window fills up.
+    /// Returns false when the entry preceding them was compacted away, as only a snapshot can catch it up then.
+    async fn replicate_to(
+        &mut self,
+        to: &PeerIdentifier,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+    ) -> bool {
+        loop {
+            let Some(peer) = self.find_replica_mut(to) else {
+                return true;
+            };
+            let sent_up_to = peer.inflight.sent_up_to();
+            if peer.inflight.is_full() || sent_up_to >= logger.last_log_index {
+                return true;
+            }
+
+            let Some(prev_log_term) = logger.term_at(sent_up_to).await else {
+                return false;
+            };
+            let batch = take_batch(
+                logger
+                    .range(sent_up_to, (sent_up_to + MAX_BATCH_ENTRIES).min(logger.last_log_index)),
+            );
+            let Some(last_index) = batch.last().map(|op| op.log_index) else {
+                return true;
+            };
+            let msg = self
+                .replication
+                .default_heartbeat(0, sent_up_to, prev_log_term)
+                .set_append_entries(batch);
+
+            let Some(peer) = self.find_replica_mut(to) else {
+                return true;
+            };
+            // Batches are queued rather than written, so a slow follower doesn't hold up the actor.
+            // One that can't be queued goes out on a later attempt
+            if peer.try_send_to_peer(AppendEntriesRPC(msg)).is_err() {
+                return true;
+            }
+            peer.inflight.record(last_index);
+        }
+    }
+
+    /// Frees the inflight window up to what `from` has stored and sends it what comes next.
+    pub(crate) async fn continue_replication(
+        &mut self,
+        from: &PeerIdentifier,
+        match_index: u64,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        if !self.replication.is_leader_mode {
+            return;
+        }
+        let Some(peer) = self.find_replica_mut(from) else {
+            return;
+        };
+        peer.inflight.ack(match_index);
+        self.replicate_or_snapshot(from.clone(), logger, cache_manager).await;
+    }
+
+    /// Resends what a follower left unacknowledged for too long, as when a batch or its ack got lost.
+    /// Other followers are sent whatever their window has room for.
+    pub(crate) async fn resume_replication(
+        &mut self,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        if !self.replication.is_leader_mode {
+            return;
+        }
+        let ack_timeout = Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX);
+        let replicas = self
+            .replicas()
+            .map(|(id, peer, match_index)| {
+                (id.clone(), peer.inflight.is_stalled(ack_timeout).then_some(match_index))
+            })
+            .collect::<Vec<_>>();
+        for (id, stalled) in replicas {
+            match stalled {
+                Some(match_index) => {
+                    self.send_entries_since(id, match_index, logger, cache_manager).await
+                },
+                None => self.replicate_or_snapshot(id, logger, cache_manager).await,
+            }
+        }
+    }
+
+    /// Gives up on a transfer the target did not complete in time, so the leader accepts writes again.
+    pub(crate) fn expire_leadership_transfer(&mut self) {
+        if self.leadership_transfer.as_ref().is_some_and(LeadershipTransfer::is_expired) {
+            println!("[ERROR] Leadership transfer timed out");
+            self.finish_leadership_transfer(Err(anyhow::anyhow!(
+                "ERR leadership transfer timed out"
+            )));
+        }
+    }
+
+    fn finish_leadership_transfer(&mut self, res: anyhow::Result<()>) {
+        if let Some(transfer) = self.leadership_transfer.take() {
+            transfer.complete(res);
+        }
+    }
+
+    /// The leader asked this node to take over, so it campaigns without waiting for its election timeout.
+    pub(crate) async fn timeout_now(
+        &mut self,
+        timeout_now: TimeoutNow,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+    ) {
+        if timeout_now.term < self.replication.term {
+            return;
+        }
+        println!("[INFO] Received TimeoutNow from {}", timeout_now.from);
+        self.run_for_election(logger).await;
+    }
+
+    pub(crate) fn reset_election_timeout(&mut self, leader_id: &PeerIdentifier) {
+        if let Some(peer) = self.members.get_mut(leader_id) {
+            peer.last_seen = Instant::now();
+        }
+        self.heartbeat_scheduler.reset_election_timeout();
+        self.leader_contacted_at = Instant::now();
+        self.leader = Some(leader_id.clone());
+        self.pre_vote = None;
+        // Keep the vote cast in this term so it can't be given to another candidate
+        if !matches!(self.replication.election_state, ElectionState::Follower { .. }) {
+            self.replication.election_state =
+                ElectionState::Follower { voted_for: self.replication.voted_for() };
+        }
+    }
+
+    async fn replicate_state(
+        &mut self,
+        leader: &PeerIdentifier,
+        heartbeat_hwm: u64,
+        wal: &mut ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        let old_hwm = self.replication.hwm.load(Ordering::Acquire);
+        if heartbeat_hwm > old_hwm {
+            println!("[INFO] Received commit offset {}", heartbeat_hwm);
+
+            for log_index in (old_hwm + 1)..=heartbeat_hwm {
+                let Some(log) = wal.read_at(log_index).await else {
+                    println!("[ERROR] log has never been replicated!");
+                    // Report where the log ends so the leader resends what is missing
+                    self.send_ack(leader, wal.last_log_index, RejectionReason::LogInconsistency)
+                        .await;
+                    return;
+                };
+
+                if let Err(e) = cache_manager.apply_log(log.request, log_index).await {
+                    println!("[ERROR] Failed to apply log: {:?}", e);
+                    return; // Stop on first error
+                }
+            }
+            self.replication.hwm.store(heartbeat_hwm, Ordering::Release);
+            self.membership.commit(heartbeat_hwm);
+            self.persist_commit_index().await;
+            cache_manager.pings().await;
+        }
+    }
+
+    pub(crate) async fn maybe_update_term(&mut self, new_term: u64) {
+        if new_term > self.replication.term {
+            if self.persist_term_and_vote(new_term, None).await.is_err() {
+                return;
+            }
+            self.replication.term = new_term;
+            self.pre_vote = None;
+            self.finish_leadership_transfer(Ok(()));
+            self.read_index.fail_all();
+            self.revoke_lease();
+            self.replication.election_state = ElectionState::Follower { voted_for: None };
+            self.replication.is_leader_mode = false;
+            self.replication.role = ReplicationRole::Follower;
+            self.heartbeat_scheduler.turn_follower_mode().await;
+        }
+    }
+
+    pub(crate) async fn check_term_outdated(
         &mut self,
         heartbeat: &HeartBeatMessage,
         wal: &ReplicatedLogs<impl TWriteAheadLog>,
@@ -668,83 +1510,325 @@ impl ClusterActor {
     /// Used when:
     /// 1) on follower's consensus rejection when term is not matched
     /// 2) step down operation is given from user
+    /// 3) the leader lost contact with a quorum
     pub(crate) async fn step_down(&mut self) {
-        self.replication.vote_for(None);
+        self.read_index.fail_all();
+        self.revoke_lease();
+        for (_, voting) in self.consensus_tracker.drain() {
+            let _ = voting.callback.send(ConsensusClientResponse::Err(
+                "ERR leader stepped down before the write was committed".into(),
+            ));
+        }
+        // Keep the vote cast in this term so it can't be given to another candidate
+        let voted_for = self.replication.voted_for();
+        self.replication.vote_for(voted_for);
+        self.replication.role = ReplicationRole::Follower;
         self.heartbeat_scheduler.turn_follower_mode().await;
     }
 
+    /// CheckQuorum: a leader that hasn't heard from a quorum of voters within the node timeout steps down,
+    /// instead of accepting writes that can never commit.
+    pub(crate) async fn check_quorum(&mut self) {
+        if !self.replication.is_leader_mode {
+            return;
+        }
+
+        let required_acks = self.membership.required_acks(&self.replication.self_identifier());
+        let timeout = Duration::from_millis(self.node_timeout as u64);
+        let reachable = self
+            .members
+            .iter()
+            .filter(|(id, peer)| self.membership.is_voter(id) && peer.last_seen.elapsed() < timeout)
+            .count();
+        if reachable >= required_acks {
+            return;
+        }
+
+        println!(
+            "[INFO] Heard from {} of {} required voters within the node timeout, stepping down",
+            reachable, required_acks
+        );
+        self.step_down().await;
+    }
+
     async fn become_leader(&mut self) {
         eprintln!("\x1b[32m[INFO] Election succeeded\x1b[0m");
         self.replication.become_leader();
+        self.leader = None;
+        // Whatever was sent under an earlier leadership can't be relied on
+        for peer in self.members.values_mut() {
+            peer.inflight.reset(peer.kind.match_index());
+        }
         self.heartbeat_scheduler.turn_leader_mode().await;
     }
     fn become_candidate(&mut self) {
-        self.replication.become_candidate(self.replicas().count() as u8);
+        self.leader = None;
+        let replica_count = self.membership.peer_voter_count(&self.replication.self_identifier());
+        self.replication.become_candidate(replica_count);
     }
 
-    pub(crate) async fn handle_repl_rejection(&mut self, repl_res: ReplicationResponse) {
+    pub(crate) async fn handle_repl_rejection(
+        &mut self,
+        repl_res: ReplicationResponse,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
         match repl_res.rej_reason {
             RejectionReason::ReceiverHasHigherTerm => self.step_down().await,
             RejectionReason::LogInconsistency => {
-                self.decrease_match_index(&repl_res.from);
+                // Every batch that was in flight behind the conflict is rejected the same way, and one resend covers them all
+                let conflict_term = repl_res.conflict.as_ref().map(|conflict| conflict.term);
+                if self
+                    .find_replica_mut(&repl_res.from)
+                    .is_none_or(|peer| peer.inflight.rewound_for(conflict_term, repl_res.log_idx))
+                {
+                    return;
+                }
+                // The entries the follower needs next have been compacted away
+                if repl_res.log_idx + 1 < logger.log_start_index() {
+                    self.prepare_snapshot_for(repl_res.from, logger, cache_manager).await;
+                    return;
+                }
+                let upper_bound = match repl_res.conflict {
+                    // Resend from the end of the conflicting term in our log, or skip the term entirely
+                    Some(conflict) => match logger.last_index_of_term(conflict.term).await {
+                        Some(last_index) => last_index,
+                        None => conflict.first_index.saturating_sub(1),
+                    },
+                    None => repl_res.log_idx,
+                };
+                self.decrease_match_index(&repl_res.from, upper_bound.min(repl_res.log_idx));
+                // Retry from the lowered index so a lagging follower catches up without waiting for new writes
+                let Some(match_index) =
+                    self.replicas().find(|(id, _, _)| **id == repl_res.from).map(|(_, _, idx)| idx)
+                else {
+                    return;
+                };
+                self.send_entries_since(repl_res.from.clone(), match_index, logger, cache_manager)
+                    .await;
+                if let Some(peer) = self.find_replica_mut(&repl_res.from) {
+                    peer.inflight.mark_rewound(conflict_term, repl_res.log_idx);
+                }
             },
             RejectionReason::None => (),
         }
     }
 
-    fn decrease_match_index(&mut self, from: &PeerIdentifier) {
-        if let Some(peer) = self.members.get_mut(from) {
-            peer.kind.decrease_match_index();
+    /// Takes an in-memory snapshot in the background. It is sent through `SendInstallSnapshot` once ready.
+    async fn prepare_snapshot_for(
+        &mut self,
+        to: PeerIdentifier,
+        logger: &ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+    ) {
+        if !self.snapshot_transfers.insert(to.clone()) {
+            return;
         }
-    }
 
-    pub(crate) async fn replicaof(&mut self, peer_addr: PeerIdentifier) {
-        self.replication.vote_for(Some(peer_addr));
-        self.set_replication_info(ReplicationId::Undecided, 0);
-        self.heartbeat_scheduler.turn_follower_mode().await;
+        let last_included_index = self.replication.hwm.load(Ordering::Acquire);
+        let Some(last_included_term) = logger.term_at(last_included_index).await else {
+            println!("[ERROR] No term known for snapshot index {}", last_included_index);
+            self.snapshot_transfers.remove(&to);
+            return;
+        };
+        println!("[INFO] Preparing snapshot up to {} for {}", last_included_index, to);
+
+        let save = cache_manager
+            .route_save(
+                SaveTarget::InMemory(Vec::new()),
+                self.replication.replid.clone(),
+                last_included_index,
+            )
+            .await;
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            let snapshot = async { Ok(save?.await??.into_inner()) }.await;
+            let _ = handler
+                .send(ClusterCommand::SendInstallSnapshot {
+                    to,
+                    last_included_index,
+                    last_included_term,
+                    snapshot,
+                })
+                .await;
+        });
     }
 
-    pub(crate) async fn join_peer_network_if_absent(&mut self, cluster_nodes: Vec<ClusterNode>) {
-        let peers = cluster_nodes
-            .into_iter()
-            .filter(|n| n.bind_addr != self.replication.self_identifier())
-            .filter(|p| !self.members.contains_key(&p.bind_addr))
-            .map(|node| node.bind_addr)
-            .collect::<Vec<_>>();
-        if peers.is_empty() {
+    pub(crate) async fn send_install_snapshot(
+        &mut self,
+        to: PeerIdentifier,
+        last_included_index: u64,
+        last_included_term: u64,
+        snapshot: anyhow::Result<Vec<u8>>,
+    ) {
+        self.snapshot_transfers.remove(&to);
+        let snapshot = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                println!("[ERROR] Failed to create snapshot for {}: {:?}", to, e);
+                return;
+            },
+        };
+        if !self.replication.is_leader_mode {
             return;
         }
 
-        for peer in peers {
-            if self.replication.in_ban_list(&peer) {
-                continue;
-            }
-
-            if self.discover_cluster(peer).await.is_ok() {
-                let _ = self.snapshot_topology().await;
-                break;
+        let chunks = InstallSnapshot::chunks(
+            &self.replication,
+            last_included_index,
+            last_included_term,
+            self.membership.voters().iter().cloned().collect(),
+            snapshot,
+        );
+        let Some(peer) = self.members.get_mut(&to) else {
+            return;
+        };
+        println!("[INFO] Sending snapshot up to {} to {}", last_included_index, to);
+        for chunk in chunks {
+            if let Err(e) = peer.send_to_peer(chunk).await {
+                println!("[ERROR] Failed to send snapshot to {}: {:?}", to, e);
+                return;
             }
         }
     }
-}
-
-#[cfg(test)]
-#[allow(unused_variables)]
-mod test {
-    use super::*;
-    use crate::adapters::op_logs::memory_based::MemoryOpLogs;
-    use crate::domains::caches::actor::CacheCommandSender;
-    use crate::domains::caches::cache_objects::CacheEntry;
-    use crate::domains::caches::command::CacheCommand;
-    use crate::domains::cluster_actors::commands::ClusterCommand;
-    use crate::domains::cluster_actors::listener::PeerListener;
-    use crate::domains::cluster_actors::replication::ReplicationRole;
-    use crate::domains::operation_logs::WriteOperation;
-    use crate::domains::operation_logs::WriteRequest;
-
-    use std::ops::Range;
-    use std::time::Duration;
-    use tokio::fs::OpenOptions;
+
+    /// Buffers snapshot chunks from the leader and, once the last one arrives,
+    /// replaces the cache and the log with the snapshot.
+    pub(crate) async fn install_snapshot(
+        &mut self,
+        chunk: InstallSnapshot,
+        logger: &mut ReplicatedLogs<impl TWriteAheadLog>,
+        cache_manager: &CacheManager,
+        config_manager: &ConfigManager,
+    ) {
+        let InstallSnapshot {
+            term,
+            from,
+            last_included_index,
+            last_included_term,
+            offset,
+            data,
+            done,
+            voters,
+        } = chunk;
+        if term < self.replication.term {
+            self.send_ack(&from, logger.last_log_index, RejectionReason::ReceiverHasHigherTerm)
+                .await;
+            return;
+        }
+        self.reset_election_timeout(&from);
+        self.maybe_update_term(term).await;
+
+        if offset == 0 {
+            self.incoming_snapshot.clear();
+        }
+        if offset != self.incoming_snapshot.len() as u64 {
+            println!("[ERROR] Unexpected snapshot chunk offset {}", offset);
+            self.incoming_snapshot.clear();
+            return;
+        }
+        self.incoming_snapshot.extend(data);
+        if !done {
+            return;
+        }
+
+        let data = std::mem::take(&mut self.incoming_snapshot);
+        let snapshot = match SnapshotLoader::load_from_bytes(&data) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                println!("[ERROR] Failed to load snapshot: {:?}", e);
+                return;
+            },
+        };
+
+        // The snapshot has to be on disk before the entries it covers are dropped from the log
+        let saved = async { replace_file(config_manager.get_filepath().await?, &data).await }.await;
+        if let Err(e) = saved {
+            println!("[ERROR] Failed to save snapshot: {:?}", e);
+            return;
+        }
+        if self.persist_snapshot_point(last_included_index, last_included_term).await.is_err() {
+            return;
+        }
+
+        cache_manager.drop_cache().await;
+        if let Err(e) = cache_manager.apply_snapshot(snapshot).await {
+            println!("[ERROR] Failed to apply snapshot: {:?}", e);
+            return;
+        }
+        if let Err(e) = logger.install_snapshot(last_included_index, last_included_term).await {
+            println!("[ERROR] Failed to reset logs for snapshot: {:?}", e);
+            return;
+        }
+        self.membership = Membership::new(voters, last_included_index);
+        self.replication.hwm.store(last_included_index, Ordering::Release);
+        self.persist_commit_index().await;
+
+        println!("[INFO] Installed snapshot up to {}", last_included_index);
+        self.send_ack(&from, last_included_index, RejectionReason::None).await;
+    }
+
+    /// Steps the follower's match index back, never past the last index it reported.
+    fn decrease_match_index(&mut self, from: &PeerIdentifier, last_log_index: u64) {
+        if let Some(peer) = self.members.get_mut(from) {
+            peer.kind.decrease_match_index(last_log_index);
+        }
+    }
+
+    pub(crate) async fn replicaof(&mut self, peer_addr: PeerIdentifier) {
+        self.leader = None;
+        self.replication.vote_for(Some(peer_addr));
+        // The configuration of the cluster this node is joining arrives through its log
+        self.membership = Membership::default();
+        self.set_replication_info(ReplicationId::Undecided, 0);
+        self.heartbeat_scheduler.turn_follower_mode().await;
+    }
+
+    pub(crate) async fn join_peer_network_if_absent(&mut self, cluster_nodes: Vec<ClusterNode>) {
+        let peers = cluster_nodes
+            .into_iter()
+            .filter(|n| n.bind_addr != self.replication.self_identifier())
+            .filter(|p| !self.members.contains_key(&p.bind_addr))
+            .map(|node| node.bind_addr)
+            .collect::<Vec<_>>();
+        if peers.is_empty() {
+            return;
+        }
+
+        for peer in peers {
+            if self.replication.in_ban_list(&peer) {
+                continue;
+            }
+
+            if self.discover_cluster(peer).await.is_ok() {
+                let _ = self.snapshot_topology().await;
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_variables)]
+mod test {
+    use super::*;
+    use crate::adapters::op_logs::memory_based::MemoryOpLogs;
+    use crate::domains::caches::actor::CacheCommandSender;
+    use crate::domains::caches::cache_objects::CacheEntry;
+    use crate::domains::caches::cache_objects::CacheValue;
+    use crate::domains::caches::command::CacheCommand;
+    use crate::domains::cluster_actors::commands::ClusterCommand;
+    use crate::domains::cluster_actors::listener::PeerListener;
+    use crate::domains::cluster_actors::replication::ReplicationRole;
+    use crate::domains::config_actors::actor::ConfigActor;
+    use crate::domains::operation_logs::WriteOperation;
+    use crate::domains::operation_logs::WriteRequest;
+
+    use std::ops::Range;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicU64;
+    use std::time::Duration;
+    use tokio::fs::OpenOptions;
 
     use tokio::net::TcpListener;
     use tokio::net::TcpStream;
@@ -758,7 +1842,7 @@ mod test {
         value: &str,
     ) -> WriteOperation {
         WriteOperation {
-            log_index: index_num.into(),
+            log_index: index_num,
             request: WriteRequest::Set { key: key.into(), value: value.into() },
             term,
         }
@@ -776,9 +1860,10 @@ mod test {
             append_entries: op_logs,
             ban_list: vec![],
             from: PeerIdentifier::new("localhost", 8080),
-            replid: ReplicationId::Key("localhost".to_string().into()),
+            replid: ReplicationId::Key("localhost".to_string()),
             hop_count: 0,
             cluster_nodes: vec![],
+            read_seq: 0,
         }
     }
 
@@ -798,7 +1883,25 @@ mod test {
             .await
             .unwrap();
 
-        ClusterActor::new(100, replication, 100, topology_writer)
+        ClusterActor::new(
+            100,
+            replication,
+            100,
+            topology_writer,
+            HardStateStore::default(),
+            false,
+            Duration::from_secs(5),
+            false,
+            false,
+        )
+    }
+
+    fn config_manager_create_helper(dir: impl Into<String>) -> ConfigManager {
+        ConfigManager::new(
+            ConfigActor::new(dir.into(), "dump.rdb".into()),
+            "localhost".into(),
+            8080,
+        )
     }
 
     async fn cluster_member_create_helper(
@@ -823,7 +1926,7 @@ mod test {
                     x,
                     PeerState::Replica {
                         match_index: follower_hwm,
-                        replid: ReplicationId::Key("localhost".to_string().into()),
+                        replid: ReplicationId::Key("localhost".to_string()),
                     },
                     kill_switch,
                 ),
@@ -831,6 +1934,31 @@ mod test {
         }
     }
 
+    /// Connects `actor` to `leader`, and hands over what the actor replies to it.
+    async fn leader_peer_create_helper(
+        actor: &mut ClusterActor,
+        leader: PeerIdentifier,
+    ) -> tokio::sync::mpsc::Receiver<ClusterCommand> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let to_leader = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (at_leader, _) = listener.accept().await.unwrap();
+
+        let (replies, rx) = channel(100);
+        let kill_switch = PeerListener::spawn(at_leader.into_split().0, replies, leader.clone());
+        let state = PeerState::Replica {
+            match_index: 0,
+            replid: ReplicationId::Key("localhost".to_string()),
+        };
+        let peer = Peer::new(leader.to_string(), to_leader.into_split().1, state, kill_switch);
+        actor.members.insert(leader, peer);
+        rx
+    }
+
+    fn voters_create_helper(actor: &mut ClusterActor, voters: &[PeerIdentifier]) {
+        let self_id = actor.replication.self_identifier();
+        actor.membership = Membership::new(voters.iter().cloned().chain(iter::once(self_id)), 0);
+    }
+
     #[tokio::test]
     async fn test_hop_count_when_one() {
         // GIVEN
@@ -858,417 +1986,1588 @@ mod test {
         // GIVEN
         let fanout = 2;
 
-        // WHEN
-        let hop_count = ClusterActor::hop_count(fanout, 3);
+        // WHEN
+        let hop_count = ClusterActor::hop_count(fanout, 3);
+        // THEN
+        assert_eq!(hop_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_hop_count_when_thirty() {
+        // GIVEN
+        let fanout = 2;
+
+        // WHEN
+        let hop_count = ClusterActor::hop_count(fanout, 30);
+        // THEN
+        assert_eq!(hop_count, 4);
+    }
+
+    #[tokio::test]
+    async fn leader_commits_right_away_when_followers_not_exist() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        // WHEN
+        cluster_actor
+            .req_consensus(
+                &mut logger,
+                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                tx,
+                None,
+                None,
+            )
+            .await;
+        cluster_actor
+            .advance_commit_index(&logger, &cache_manager, &mut ClientSessions::default())
+            .await;
+
+        // THEN
+        assert_eq!(cluster_actor.consensus_tracker.len(), 0);
+        assert_eq!(logger.last_log_index, 1);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 1);
+        let ConsensusClientResponse::Result(res) = rx.await.unwrap() else {
+            panic!("Expected the write to be committed");
+        };
+        assert_eq!(res, QueryIO::SimpleString("s:bar|idx:1".into()));
+        assert_eq!(cache_manager.route_get("foo").await.unwrap().unwrap().value().unwrap(), "bar");
+    }
+
+    #[tokio::test]
+    async fn req_consensus_inserts_consensus_voting() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        // - add 5 followers
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+
+        cluster_member_create_helper(&mut cluster_actor, 0..5, cluster_sender, cache_manager, 0)
+            .await;
+        let followers = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &followers);
+
+        let (tx, _) = tokio::sync::oneshot::channel();
+        let client_id = Uuid::now_v7();
+        let session_request = SessionRequest::new(1, client_id);
+        // WHEN
+        cluster_actor
+            .req_consensus(
+                &mut logger,
+                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                tx,
+                Some(session_request.clone()),
+                None,
+            )
+            .await;
+
+        // THEN
+        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
+        assert_eq!(logger.last_log_index, 1);
+
+        assert_eq!(
+            cluster_actor.consensus_tracker.get(&1).unwrap().session_req.as_ref().unwrap().clone(), //* session_request_is_saved_on_tracker
+            session_request
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leader_req_consensus_early_return_when_already_processed_session_req_given() {
+        // GIVEN
+        let cluster_actor = cluster_actor_create_helper().await;
+
+        let cache_manager = CacheManager { inboxes: vec![] };
+
+        let mut sessions = ClientSessions::default();
+        let client_id = Uuid::now_v7();
+        let client_req = SessionRequest::new(1, client_id);
+
+        // WHEN - session request is already processed
+        sessions
+            .set_response(Some(client_req.clone()), &QueryIO::SimpleString("s:bar|idx:1".into()));
+        let handler = cluster_actor.self_handler.clone();
+        let config_manager = config_manager_create_helper(".");
+        tokio::spawn(cluster_actor.handle(
+            MemoryOpLogs::default(),
+            cache_manager,
+            config_manager,
+            sessions,
+        ));
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        handler
+            .send(ClusterCommand::LeaderReqConsensus {
+                log: WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                callback: tx,
+                session_req: Some(client_req),
+                timeout: None,
+            })
+            .await
+            .unwrap();
+
+        // THEN - the earlier response is handed out again
+        let ConsensusClientResponse::Result(res) = rx.await.unwrap() else {
+            panic!("Expected the earlier response");
+        };
+        assert_eq!(res, QueryIO::SimpleString("s:bar|idx:1".into()));
+    }
+
+    #[tokio::test]
+    async fn test_write_committed_once_majority_of_voters_matches() {
+        // GIVEN
+        let mut sessions = ClientSessions::default();
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+
+        // - 4 followers and the leader make 5 voters, with one more replica as a learner
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..5,
+            cluster_sender,
+            CacheManager { inboxes: vec![] },
+            0,
+        )
+        .await;
+        let voters = (0..4).map(|port| PeerIdentifier::new("localhost", port)).collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &voters);
+        let (client_request_sender, client_wait) = tokio::sync::oneshot::channel();
+
+        let client_id = Uuid::now_v7();
+        let client_request = SessionRequest::new(3, client_id);
+        cluster_actor
+            .req_consensus(
+                &mut logger,
+                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                client_request_sender,
+                Some(client_request.clone()),
+                None,
+            )
+            .await;
+
+        // WHEN
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 0), 1);
+        // - the learner doesn't count
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 4), 1);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+
+        // up to this point, tracker hold the consensus
+        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 0);
+
+        // ! Majority matched - 3 out of 5 voters including the leader
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 1), 1);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+
+        // THEN
+        assert_eq!(cluster_actor.consensus_tracker.len(), 0);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 1);
+
+        client_wait.await.unwrap();
+        assert!(sessions.processed_response(&Some(client_request)).is_some()); // * session_request_is_marked_as_processed
+    }
+
+    #[tokio::test]
+    async fn test_entries_committed_together_are_applied_in_order() {
+        // GIVEN
+        let mut sessions = ClientSessions::default();
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..2,
+            cluster_sender,
+            CacheManager { inboxes: vec![] },
+            0,
+        )
+        .await;
+        let voters = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &voters);
+
+        let mut receivers = vec![];
+        for value in ["a", "b", "c"] {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let write = WriteRequest::Set { key: "foo".into(), value: value.into() };
+            cluster_actor.req_consensus(&mut logger, write, tx, None, None).await;
+            receivers.push(rx);
+        }
+
+        // WHEN - a late ack for the first entry arrives after the one covering all three
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 0), 3);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 1), 1);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 3);
+        for (idx, rx) in receivers.into_iter().enumerate() {
+            assert!(matches!(rx.await.unwrap(), ConsensusClientResponse::Result(_)), "{idx}");
+        }
+        assert_eq!(cache_manager.route_get("foo").await.unwrap().unwrap().value().unwrap(), "c");
+    }
+
+    #[tokio::test]
+    async fn test_entries_of_earlier_terms_are_not_committed_by_counting() {
+        // GIVEN
+        let mut sessions = ClientSessions::default();
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        logger
+            .follower_write_entries(&[write_operation_create_helper(1, 1, "foo", "bar")])
+            .await
+            .unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.term = 2;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..2,
+            cluster_sender,
+            CacheManager { inboxes: vec![] },
+            0,
+        )
+        .await;
+        let voters = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &voters);
+
+        // WHEN
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 0), 1);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 0);
+
+        // WHEN - an entry of the current term gets replicated on top of it
+        let (tx, _) = tokio::sync::oneshot::channel();
+        cluster_actor.req_consensus(&mut logger, WriteRequest::NoOp, tx, None, None).await;
+        cluster_actor.update_on_hertbeat_message(&PeerIdentifier::new("localhost", 0), 2);
+        cluster_actor.advance_commit_index(&logger, &cache_manager, &mut sessions).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 2);
+        assert_eq!(cache_manager.route_get("foo").await.unwrap().unwrap().value().unwrap(), "bar");
+    }
+
+    #[tokio::test]
+    async fn test_write_fails_once_consensus_deadline_passes() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..2, cluster_sender, cache_manager, 0)
+            .await;
+        let voters = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &voters);
+
+        // - the first write overrides the configured timeout
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        let write = WriteRequest::Set { key: "foo".into(), value: "bar".into() };
+        cluster_actor
+            .req_consensus(&mut logger, write.clone(), tx1, None, Some(Duration::ZERO))
+            .await;
+        cluster_actor.req_consensus(&mut logger, write, tx2, None, None).await;
+
+        // WHEN
+        cluster_actor.expire_consensus_requests();
+
+        // THEN
+        assert!(matches!(rx1.await.unwrap(), ConsensusClientResponse::Err(_)));
+        assert!(rx2.try_recv().is_err());
+        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
+        assert_eq!(cluster_actor.replication.timed_out_writes, 1);
+    }
+
+    #[tokio::test]
+    async fn logger_writes_entry_after_last_log_index() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        let test_logs = vec![
+            write_operation_create_helper(1, 0, "foo", "bar"),
+            write_operation_create_helper(2, 0, "foo2", "bar"),
+            write_operation_create_helper(3, 0, "foo3", "bar"),
+        ];
+        logger.follower_write_entries(&test_logs).await.unwrap();
+
+        // WHEN
+        let entry = logger
+            .leader_write_entry(&WriteRequest::Set { key: "foo4".into(), value: "bar".into() }, 1)
+            .await
+            .unwrap();
+
+        // THEN
+        assert_eq!(entry.log_index, 4);
+        assert_eq!((logger.last_log_index, logger.last_log_term), (4, 1));
+    }
+
+    #[tokio::test]
+    async fn test_followers_are_sent_only_the_entries_they_miss() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..5,
+            cluster_sender.clone(),
+            cache_manager,
+            3,
+        )
+        .await;
+        // *add lagged followers with its match index being 1
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 5..7, cluster_sender, cache_manager, 1)
+            .await;
+
+        let test_logs = vec![
+            write_operation_create_helper(1, 0, "foo", "bar"),
+            write_operation_create_helper(2, 0, "foo2", "bar"),
+            write_operation_create_helper(3, 0, "foo3", "bar"),
+        ];
+        logger.follower_write_entries(&test_logs).await.unwrap();
+
+        // WHEN
+        let (tx, _) = tokio::sync::oneshot::channel();
+        cluster_actor
+            .req_consensus(
+                &mut logger,
+                WriteRequest::Set { key: "foo4".into(), value: "bar".into() },
+                tx,
+                None,
+                None,
+            )
+            .await;
+
+        // THEN - every follower got one batch ending at the new entry
+        assert!(cluster_actor.members.values().all(|peer| peer.inflight.sent_up_to() == 4));
+    }
+
+    #[tokio::test]
+    async fn test_writes_wait_for_room_in_inflight_window_and_go_out_together() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            0,
+        )
+        .await;
+        let follower = PeerIdentifier::new("localhost", 0);
+
+        let mut write = async |actor: &mut ClusterActor, key: &str| {
+            let (tx, _) = tokio::sync::oneshot::channel();
+            let log = WriteRequest::Set { key: key.into(), value: "bar".into() };
+            actor.req_consensus(&mut logger, log, tx, None, None).await;
+        };
+        let mut sent = 0;
+        while !cluster_actor.members[&follower].inflight.is_full() {
+            sent += 1;
+            write(&mut cluster_actor, "foo").await;
+        }
+
+        // WHEN
+        write(&mut cluster_actor, "held1").await;
+        write(&mut cluster_actor, "held2").await;
+
+        // THEN
+        assert_eq!(cluster_actor.members[&follower].inflight.sent_up_to(), sent);
+
+        // WHEN - the follower acknowledges the first batch
+        cluster_actor.continue_replication(&follower, 1, &logger, &cache_manager).await;
+
+        // THEN - both held back writes go out in a single batch
+        let inflight = &cluster_actor.members[&follower].inflight;
+        assert_eq!(inflight.sent_up_to(), sent + 2);
+        assert!(inflight.is_full());
+    }
+
+    #[tokio::test]
+    async fn follower_cluster_actor_replicate_log() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        // WHEN - term
+        let heartbeat = heartbeat_create_helper(
+            0,
+            0,
+            vec![
+                write_operation_create_helper(1, 0, "foo", "bar"),
+                write_operation_create_helper(2, 0, "foo2", "bar"),
+            ],
+        );
+        let cache_manager = CacheManager {
+            inboxes: (0..10).map(|_| CacheCommandSender(channel(10).0)).collect::<Vec<_>>(),
+        };
+        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 0);
+        assert_eq!(logger.last_log_index, 2);
+        let logs = logger.range(0, 2);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].log_index, 1);
+        assert_eq!(logs[1].log_index, 2);
+        assert_eq!(logs[0].request, WriteRequest::Set { key: "foo".into(), value: "bar".into() });
+        assert_eq!(logs[1].request, WriteRequest::Set { key: "foo2".into(), value: "bar".into() });
+    }
+
+    #[tokio::test]
+    async fn follower_cluster_actor_replicate_state() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let (cache_handler, mut receiver) = tokio::sync::mpsc::channel(100);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        let heartbeat = heartbeat_create_helper(
+            0,
+            0,
+            vec![
+                write_operation_create_helper(1, 0, "foo", "bar"),
+                write_operation_create_helper(2, 0, "foo2", "bar"),
+            ],
+        );
+
+        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(cache_handler)] };
+        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+
+        // WHEN - commit until 2
+        let task = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, value } } => {
+                        assert_eq!(value, "bar");
+                        if key == "foo2" {
+                            break;
+                        }
+                    },
+                    _ => continue,
+                }
+            }
+        });
+        let heartbeat = heartbeat_create_helper(0, 2, vec![]);
+        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 2);
+        assert_eq!(logger.last_log_index, 2);
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_apply_multiple_committed_entries() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        // Add multiple entries
+        let entries = vec![
+            write_operation_create_helper(1, 0, "key1", "value1"),
+            write_operation_create_helper(2, 0, "key2", "value2"),
+            write_operation_create_helper(3, 0, "key3", "value3"),
+        ];
+
+        let heartbeat = heartbeat_create_helper(1, 0, entries);
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+
+        // First append entries but don't commit
+        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+
+        // Create a task to monitor applied entries
+        let monitor_task = tokio::spawn(async move {
+            let mut applied_keys = Vec::new();
+
+            while let Some(message) = rx.recv().await {
+                if let CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, .. } } = message
+                {
+                    applied_keys.push(key);
+                    if applied_keys.len() == 3 {
+                        break;
+                    }
+                }
+            }
+
+            applied_keys
+        });
+
+        // WHEN - commit all entries
+        let commit_heartbeat = heartbeat_create_helper(1, 3, vec![]);
+
+        cluster_actor.replicate(&mut logger, commit_heartbeat, &cache_manager).await;
+
+        // THEN
+        // Verify that all entries were committed and applied in order
+        let applied_keys = tokio::time::timeout(Duration::from_secs(1), monitor_task)
+            .await
+            .expect("Timeout waiting for entries to be applied")
+            .expect("Task failed");
+
+        assert_eq!(applied_keys, vec!["key1", "key2", "key3"]);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_committed_logs_applies_entries_up_to_persisted_commit_index() {
+        // GIVEN
+        let wal = MemoryOpLogs {
+            writer: vec![
+                write_operation_create_helper(1, 0, "key1", "value1"),
+                write_operation_create_helper(2, 0, "key2", "value2"),
+                write_operation_create_helper(3, 1, "key3", "value3"),
+            ],
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor
+            .hard_state
+            .save(HardState { commit_index: 2, ..Default::default() })
+            .await
+            .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+
+        // WHEN
+        cluster_actor.replay_committed_logs(&logger, &cache_manager).await;
+
+        // THEN - the uncommitted third entry is not applied
+        let mut applied_keys = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            if let CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, .. } } = message {
+                applied_keys.push(key);
+            }
+        }
+        assert_eq!(applied_keys, vec!["key1", "key2"]);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 2);
+        assert_eq!(logger.last_log_index, 3);
+        assert_eq!(logger.last_log_term, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_committed_logs_skips_entries_covered_by_snapshot() {
+        // GIVEN
+        let wal = MemoryOpLogs {
+            writer: vec![
+                write_operation_create_helper(1, 0, "key1", "value1"),
+                write_operation_create_helper(2, 0, "key2", "value2"),
+            ],
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor
+            .hard_state
+            .save(HardState { commit_index: 2, ..Default::default() })
+            .await
+            .unwrap();
+
+        // snapshot was taken at index 1
+        cluster_actor.replication.hwm.store(1, Ordering::Release);
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+
+        // WHEN
+        cluster_actor.replay_committed_logs(&logger, &cache_manager).await;
+
+        // THEN
+        let mut applied_keys = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            if let CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, .. } } = message {
+                applied_keys.push(key);
+            }
+        }
+        assert_eq!(applied_keys, vec!["key2"]);
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_vote_election_persists_term_and_vote_before_granting() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        let candidate_id = PeerIdentifier::new("localhost", 8081);
+        let request_vote = RequestVote {
+            term: 1,
+            candidate_id: candidate_id.clone(),
+            last_log_index: 0,
+            last_log_term: 0,
+        };
+
+        // WHEN
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        cluster_actor.vote_election(request_vote, &logger).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.term, 1);
+        assert_eq!(
+            cluster_actor.hard_state.state(),
+            &HardState { term: 1, voted_for: Some(candidate_id), ..Default::default() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vote_election_rejects_candidate_with_older_last_log_term() {
+        // GIVEN - the candidate's log is longer but ends in an older term
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 2, 3);
+        let request_vote = RequestVote {
+            term: 5,
+            candidate_id: PeerIdentifier::new("localhost", 8081),
+            last_log_index: 10,
+            last_log_term: 2,
+        };
+
+        // WHEN
+        cluster_actor.vote_election(request_vote, &logger).await;
+
+        // THEN - the newer term is adopted but the vote is withheld
+        assert_eq!(cluster_actor.replication.term, 5);
+        assert_eq!(cluster_actor.replication.voted_for(), None);
+        assert_eq!(cluster_actor.hard_state.state().voted_for, None);
+    }
+
+    #[tokio::test]
+    async fn test_vote_election_grants_shorter_log_with_newer_last_log_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 5, 1);
+        let candidate_id = PeerIdentifier::new("localhost", 8081);
+        let request_vote = RequestVote {
+            term: 3,
+            candidate_id: candidate_id.clone(),
+            last_log_index: 3,
+            last_log_term: 2,
+        };
+
+        // WHEN
+        cluster_actor.vote_election(request_vote, &logger).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.voted_for(), Some(candidate_id));
+    }
+
+    #[tokio::test]
+    async fn test_vote_election_rejects_shorter_log_in_same_last_log_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 5, 2);
+        let request_vote = RequestVote {
+            term: 3,
+            candidate_id: PeerIdentifier::new("localhost", 8081),
+            last_log_index: 4,
+            last_log_term: 2,
+        };
+
+        // WHEN
+        cluster_actor.vote_election(request_vote, &logger).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.voted_for(), None);
+    }
+
+    #[tokio::test]
+    async fn test_candidate_votes_for_candidate_of_newer_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.term = 1;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        cluster_actor.become_candidate();
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let candidate_id = PeerIdentifier::new("localhost", 8081);
+        let request_vote = RequestVote {
+            term: 3,
+            candidate_id: candidate_id.clone(),
+            last_log_index: 0,
+            last_log_term: 0,
+        };
+
+        // WHEN
+        cluster_actor.vote_election(request_vote, &logger).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.term, 3);
+        assert_eq!(cluster_actor.replication.voted_for(), Some(candidate_id));
+    }
+
+    #[tokio::test]
+    async fn test_tally_vote_ignores_replies_from_previous_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        cluster_actor.replication.term = 2;
+        cluster_actor.become_candidate();
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        cluster_actor
+            .tally_vote(RequestVoteReply { term: 2, vote_granted: true }, &mut logger)
+            .await;
+
+        // THEN
+        assert!(matches!(
+            cluster_actor.replication.election_state,
+            ElectionState::Candidate { .. }
+        ));
+    }
+
+    async fn follower_create_helper() -> ClusterActor {
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.is_leader_mode = false;
+        cluster_actor.replication.role = ReplicationRole::Follower;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        cluster_actor
+    }
+
+    #[tokio::test]
+    async fn test_start_pre_vote_keeps_term_and_vote() {
+        // GIVEN
+        let mut cluster_actor = follower_create_helper().await;
+        cluster_actor.replication.term = 3;
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        cluster_actor.start_pre_vote(&logger).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.term, 3);
+        assert_eq!(cluster_actor.replication.voted_for(), None);
+        assert!(cluster_actor.pre_vote.is_some());
+        assert_eq!(cluster_actor.hard_state.state(), &HardState::default());
+    }
+
+    #[tokio::test]
+    async fn test_pre_vote_denied_while_leader_is_alive() {
+        // GIVEN
+        let mut cluster_actor = follower_create_helper().await;
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let request_vote = RequestVote {
+            term: 1,
+            candidate_id: PeerIdentifier::new("localhost", 8081),
+            last_log_index: 0,
+            last_log_term: 0,
+        };
+        cluster_actor.reset_election_timeout(&PeerIdentifier::new("localhost", 8082));
+
+        // WHEN
+        let granted_with_live_leader = cluster_actor.grants_pre_vote(&request_vote, &logger);
+        cluster_actor.leader_contacted_at =
+            Instant::now() - Duration::from_millis(ELECTION_TIMEOUT_MIN);
+        let granted_after_timeout = cluster_actor.grants_pre_vote(&request_vote, &logger);
+
+        // THEN
+        assert!(!granted_with_live_leader);
+        assert!(granted_after_timeout);
+        assert_eq!(cluster_actor.replication.term, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pre_vote_denied_for_stale_log() {
+        // GIVEN
+        let mut cluster_actor = follower_create_helper().await;
+        cluster_actor.leader_contacted_at =
+            Instant::now() - Duration::from_millis(ELECTION_TIMEOUT_MIN);
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 3, 1);
+        let request_vote = RequestVote {
+            term: 2,
+            candidate_id: PeerIdentifier::new("localhost", 8081),
+            last_log_index: 2,
+            last_log_term: 1,
+        };
+
+        // WHEN
+        let granted = cluster_actor.grants_pre_vote(&request_vote, &logger);
+
+        // THEN
+        assert!(!granted);
+    }
+
+    #[tokio::test]
+    async fn test_pre_vote_majority_starts_election() {
+        // GIVEN
+        let mut cluster_actor = follower_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..4, cluster_sender, cache_manager, 0)
+            .await;
+        let followers = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &followers);
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        cluster_actor.start_pre_vote(&logger).await;
+        let granted = RequestVoteReply { term: 1, vote_granted: true };
+
+        // WHEN
+        cluster_actor.tally_pre_vote(granted.clone(), &mut logger).await;
+        let term_after_first_pre_vote = cluster_actor.replication.term;
+        cluster_actor.tally_pre_vote(granted, &mut logger).await;
+
+        // THEN
+        assert_eq!(term_after_first_pre_vote, 0);
+        assert_eq!(cluster_actor.replication.term, 1);
+        assert!(matches!(
+            cluster_actor.replication.election_state,
+            ElectionState::Candidate { .. }
+        ));
+        assert!(cluster_actor.pre_vote.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_node_outside_configuration_does_not_campaign() {
+        // GIVEN
+        let mut cluster_actor = follower_create_helper().await;
+        cluster_actor.membership = Membership::new([PeerIdentifier::new("localhost", 0)], 0);
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        cluster_actor.start_pre_vote(&logger).await;
+
+        // THEN
+        assert!(cluster_actor.pre_vote.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_joined_replica_stays_learner() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..2, cluster_sender, cache_manager, 0)
+            .await;
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        cluster_actor.send_cluster_heartbeat(0, &logger).await;
+
+        // THEN
+        assert_eq!(logger.last_log_index, 0);
+        assert_eq!(cluster_actor.membership.latest().len(), 1);
+        assert!(!cluster_actor.membership.is_voter(&PeerIdentifier::new("localhost", 0)));
+    }
+
+    #[tokio::test]
+    async fn test_promote_learner_once_committed() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..2, cluster_sender, cache_manager, 0)
+            .await;
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let learner = PeerIdentifier::new("localhost", 0);
+
+        // WHEN
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.promote_learner(learner.clone(), tx, &mut logger).await;
+        // - only one change may be in flight
+        let (tx2, rx2) = tokio::sync::oneshot::channel();
+        cluster_actor.promote_learner(PeerIdentifier::new("localhost", 1), tx2, &mut logger).await;
+
+        // THEN
+        assert_eq!(logger.last_log_index, 1);
+        assert_eq!(cluster_actor.membership.voters().len(), 1);
+        assert_eq!(cluster_actor.membership.latest().len(), 2);
+        assert_eq!(
+            rx2.await.unwrap().unwrap_err().to_string(),
+            "ERR configuration change in progress"
+        );
+
+        // WHEN - a lone voter commits the change by itself
+        cluster_actor
+            .advance_commit_index(
+                &logger,
+                &CacheManager { inboxes: vec![] },
+                &mut ClientSessions::default(),
+            )
+            .await;
+
+        // THEN
+        assert!(rx.await.unwrap().is_ok());
+        assert!(cluster_actor.membership.is_voter(&learner));
+        assert_eq!(cluster_actor.hard_state.state().voters.len(), 2);
+        assert_eq!(
+            cluster_actor.membership.required_acks(&cluster_actor.replication.self_identifier()),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promote_learner_rejected_until_caught_up() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..1, cluster_sender, cache_manager, 0)
+            .await;
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        logger
+            .follower_write_entries(&[write_operation_create_helper(1, 0, "foo", "bar")])
+            .await
+            .unwrap();
+
+        // WHEN
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.promote_learner(PeerIdentifier::new("localhost", 0), tx, &mut logger).await;
+
+        // THEN
+        assert_eq!(rx.await.unwrap().unwrap_err().to_string(), "ERR learner has not caught up");
+        assert_eq!(logger.last_log_index, 1);
+        assert!(!cluster_actor.membership.has_pending());
+    }
+
+    #[tokio::test]
+    async fn test_demote_voter_removes_it_from_configuration() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let leaving = PeerIdentifier::new("localhost", 0);
+        voters_create_helper(&mut cluster_actor, std::slice::from_ref(&leaving));
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN - the voter is not among the connected replicas
+        cluster_actor.send_cluster_heartbeat(0, &logger).await;
+
+        // THEN - it keeps its vote until it is demoted
+        assert!(cluster_actor.membership.latest().contains(&leaving));
+        assert_eq!(logger.last_log_index, 0);
+
+        // WHEN
+        let (tx, _rx) = tokio::sync::oneshot::channel();
+        cluster_actor.demote_voter(leaving.clone(), tx, &mut logger).await;
+
+        // THEN
+        assert!(!cluster_actor.membership.latest().contains(&leaving));
+        // - the leaving voter still counts until the change is committed
+        assert!(cluster_actor.membership.is_voter(&leaving));
+        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_demote_voter_rejects_leader_and_non_voters() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        voters_create_helper(&mut cluster_actor, &[PeerIdentifier::new("localhost", 0)]);
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let self_id = cluster_actor.replication.self_identifier();
+
+        // WHEN
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.demote_voter(self_id, tx, &mut logger).await;
+        let (tx2, rx2) = tokio::sync::oneshot::channel();
+        cluster_actor.demote_voter(PeerIdentifier::new("localhost", 1), tx2, &mut logger).await;
+
         // THEN
-        assert_eq!(hop_count, 1);
+        assert_eq!(
+            rx.await.unwrap().unwrap_err().to_string(),
+            "ERR transfer leadership before demoting the leader"
+        );
+        assert_eq!(rx2.await.unwrap().unwrap_err().to_string(), "ERR not a voter");
+        assert_eq!(logger.last_log_index, 0);
     }
 
     #[tokio::test]
-    async fn test_hop_count_when_thirty() {
+    async fn test_follower_drops_configuration_of_truncated_entries() {
         // GIVEN
-        let fanout = 2;
+        let mut cluster_actor = follower_create_helper().await;
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        let config = WriteOperation {
+            log_index: 2,
+            request: WriteRequest::Configuration {
+                voters: vec![
+                    PeerIdentifier::new("localhost", 8080),
+                    PeerIdentifier::new("localhost", 0),
+                ],
+            },
+            term: 1,
+        };
+        let entries = vec![write_operation_create_helper(1, 1, "foo", "bar"), config];
+        cluster_actor
+            .replicate(&mut logger, heartbeat_create_helper(1, 0, entries), &cache_manager)
+            .await;
+        assert!(cluster_actor.membership.has_pending());
+
+        // WHEN - a leader of a newer term disagrees on the entries
+        let mut heartbeat =
+            heartbeat_create_helper(2, 0, vec![write_operation_create_helper(2, 2, "foo", "baz")]);
+        heartbeat.prev_log_term = 2;
+        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
 
-        // WHEN
-        let hop_count = ClusterActor::hop_count(fanout, 30);
         // THEN
-        assert_eq!(hop_count, 4);
+        assert!(!cluster_actor.membership.has_pending());
     }
 
     #[tokio::test]
-    async fn leader_consensus_tracker_not_changed_when_followers_not_exist() {
+    async fn test_transfer_leadership_blocks_writes_until_leader_steps_down() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
         let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..2,
+            cluster_sender,
+            cache_manager.clone(),
+            0,
+        )
+        .await;
+        voters_create_helper(&mut cluster_actor, &[PeerIdentifier::new("localhost", 0)]);
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
         let (tx, rx) = tokio::sync::oneshot::channel();
 
         // WHEN
+        cluster_actor
+            .transfer_leadership(PeerIdentifier::new("localhost", 0), tx, &logger, &cache_manager)
+            .await;
+        let (write_tx, write_rx) = tokio::sync::oneshot::channel();
         cluster_actor
             .req_consensus(
                 &mut logger,
                 WriteRequest::Set { key: "foo".into(), value: "bar".into() },
-                tx,
+                write_tx,
+                None,
                 None,
             )
             .await;
 
         // THEN
-        assert_eq!(cluster_actor.consensus_tracker.len(), 0);
-        assert_eq!(logger.last_log_index, 1);
+        assert!(cluster_actor.leadership_transfer.as_ref().unwrap().timeout_now_sent);
+        assert!(matches!(write_rx.await.unwrap(), ConsensusClientResponse::Err(_)));
+        assert_eq!(logger.last_log_index, 0);
+
+        // WHEN - the target wins the election in the next term
+        cluster_actor.maybe_update_term(1).await;
+
+        // THEN
+        assert!(rx.await.unwrap().is_ok());
+        assert!(cluster_actor.leadership_transfer.is_none());
     }
 
     #[tokio::test]
-    async fn req_consensus_inserts_consensus_voting() {
+    async fn test_transfer_leadership_waits_for_target_to_catch_up() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-
+        let wal = MemoryOpLogs {
+            writer: (1..=2).map(|i| write_operation_create_helper(i, 0, "key", "value")).collect(),
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
         let mut cluster_actor = cluster_actor_create_helper().await;
-
-        // - add 5 followers
         let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
         let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            0,
+        )
+        .await;
+        let target = PeerIdentifier::new("localhost", 0);
+        voters_create_helper(&mut cluster_actor, std::slice::from_ref(&target));
+        let (tx, _rx) = tokio::sync::oneshot::channel();
 
-        cluster_member_create_helper(&mut cluster_actor, 0..5, cluster_sender, cache_manager, 0)
-            .await;
-
-        let (tx, _) = tokio::sync::oneshot::channel();
-        let client_id = Uuid::now_v7();
-        let session_request = SessionRequest::new(1, client_id);
         // WHEN
-        cluster_actor
-            .req_consensus(
-                &mut logger,
-                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
-                tx,
-                Some(session_request.clone()),
-            )
-            .await;
+        cluster_actor.transfer_leadership(target.clone(), tx, &logger, &cache_manager).await;
+        let sent_before_catch_up =
+            cluster_actor.leadership_transfer.as_ref().unwrap().timeout_now_sent;
+        cluster_actor.update_on_hertbeat_message(&target, 2);
+        cluster_actor.continue_leadership_transfer(&logger, &cache_manager).await;
 
         // THEN
-        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
-        assert_eq!(logger.last_log_index, 1);
-
-        assert_eq!(
-            cluster_actor.consensus_tracker.get(&1).unwrap().session_req.as_ref().unwrap().clone(), //* session_request_is_saved_on_tracker
-            session_request
-        );
+        assert!(!sent_before_catch_up);
+        assert!(cluster_actor.leadership_transfer.as_ref().unwrap().timeout_now_sent);
     }
 
     #[tokio::test]
-    async fn test_leader_req_consensus_early_return_when_already_processed_session_req_given() {
+    async fn test_transfer_leadership_rejected_for_unknown_replica() {
         // GIVEN
-        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-        let cluster_actor = cluster_actor_create_helper().await;
-
+        let mut cluster_actor = cluster_actor_create_helper().await;
         let cache_manager = CacheManager { inboxes: vec![] };
-
-        let mut sessions = ClientSessions::default();
-        let client_id = Uuid::now_v7();
-        let client_req = SessionRequest::new(1, client_id);
-
-        // WHEN - session request is already processed
-        sessions.set_response(Some(client_req.clone()));
-        let handler = cluster_actor.self_handler.clone();
-        tokio::spawn(cluster_actor.handle(MemoryOpLogs::default(), cache_manager, sessions));
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
         let (tx, rx) = tokio::sync::oneshot::channel();
-        handler
-            .send(ClusterCommand::LeaderReqConsensus {
-                log: WriteRequest::Set { key: "foo".into(), value: "bar".into() },
-                callback: tx,
-                session_req: Some(client_req),
-            })
-            .await
-            .unwrap();
+
+        // WHEN
+        cluster_actor
+            .transfer_leadership(PeerIdentifier::new("localhost", 0), tx, &logger, &cache_manager)
+            .await;
 
         // THEN
-        rx.await.unwrap();
+        assert!(rx.await.unwrap().is_err());
+        assert!(cluster_actor.leadership_transfer.is_none());
     }
 
     #[tokio::test]
-    async fn test_consensus_voting_deleted_when_consensus_reached() {
+    async fn test_transfer_leadership_rejected_for_learner() {
         // GIVEN
-        let mut sessions = ClientSessions::default();
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
         let mut cluster_actor = cluster_actor_create_helper().await;
-
         let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
-
-        // - add followers to create quorum
         let cache_manager = CacheManager { inboxes: vec![] };
-        cluster_member_create_helper(&mut cluster_actor, 0..4, cluster_sender, cache_manager, 0)
-            .await;
-        let (client_request_sender, client_wait) = tokio::sync::oneshot::channel();
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            0,
+        )
+        .await;
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let (tx, rx) = tokio::sync::oneshot::channel();
 
-        let client_id = Uuid::now_v7();
-        let client_request = SessionRequest::new(3, client_id);
+        // WHEN
         cluster_actor
-            .req_consensus(
-                &mut logger,
-                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
-                client_request_sender,
-                Some(client_request.clone()),
-            )
+            .transfer_leadership(PeerIdentifier::new("localhost", 0), tx, &logger, &cache_manager)
             .await;
 
-        // WHEN
-        let follower_res = ReplicationResponse {
-            log_idx: 1,
-            term: 0,
-            rej_reason: RejectionReason::None,
-            from: PeerIdentifier("".into()),
-        };
-        cluster_actor
-            .track_replication_progress(follower_res.clone().set_from("repl1"), &mut sessions);
-        cluster_actor
-            .track_replication_progress(follower_res.clone().set_from("repl2"), &mut sessions);
+        // THEN
+        assert_eq!(rx.await.unwrap().unwrap_err().to_string(), "ERR target is not a voter");
+        assert!(cluster_actor.leadership_transfer.is_none());
+    }
 
-        // up to this point, tracker hold the consensus
-        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
-        assert_eq!(cluster_actor.consensus_tracker.get(&1).unwrap().voters.len(), 2);
+    #[tokio::test]
+    async fn test_expired_leadership_transfer_is_abandoned() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.leadership_transfer =
+            Some(LeadershipTransfer::new(PeerIdentifier::new("localhost", 0), tx, Duration::ZERO));
 
-        // ! Majority votes made
-        cluster_actor.track_replication_progress(follower_res.set_from("repl3"), &mut sessions);
+        // WHEN
+        cluster_actor.expire_leadership_transfer();
 
         // THEN
-        assert_eq!(cluster_actor.consensus_tracker.len(), 0);
-        assert_eq!(logger.last_log_index, 1);
-
-        client_wait.await.unwrap();
-        assert!(sessions.is_processed(&Some(client_request))); // * session_request_is_marked_as_processed
+        assert!(rx.await.unwrap().is_err());
+        assert!(cluster_actor.leadership_transfer.is_none());
+        assert!(cluster_actor.replication.is_leader_mode);
     }
 
     #[tokio::test]
-    async fn test_same_voter_can_vote_only_once() {
+    async fn test_timeout_now_starts_election_immediately() {
         // GIVEN
-        let mut sessions = ClientSessions::default();
+        let mut cluster_actor = follower_create_helper().await;
+        cluster_actor.replication.term = 2;
+        cluster_actor.reset_election_timeout(&PeerIdentifier::new("localhost", 8082));
         let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-        let mut cluster_actor = cluster_actor_create_helper().await;
+        let timeout_now = TimeoutNow { term: 2, from: PeerIdentifier::new("localhost", 8082) };
 
-        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        // WHEN
+        cluster_actor.timeout_now(timeout_now, &mut logger).await;
 
-        // - add followers to create quorum
-        let cache_manager = CacheManager { inboxes: vec![] };
-        cluster_member_create_helper(&mut cluster_actor, 0..4, cluster_sender, cache_manager, 0)
-            .await;
-        let (client_request_sender, client_wait) = tokio::sync::oneshot::channel();
+        // THEN
+        assert_eq!(cluster_actor.replication.term, 3);
+        assert!(matches!(
+            cluster_actor.replication.election_state,
+            ElectionState::Candidate { .. }
+        ));
+    }
 
-        cluster_actor
-            .req_consensus(
-                &mut logger,
-                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
-                client_request_sender,
-                None,
-            )
-            .await;
+    #[tokio::test]
+    async fn test_maybe_update_term_persists_new_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
 
         // WHEN
-        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
-        let follower_res = ReplicationResponse {
-            log_idx: 1,
-            term: 0,
-            rej_reason: RejectionReason::None,
-            from: PeerIdentifier("repl1".into()), //TODO Must be changed if "update_match_index" becomes idempotent operation on peer id
-        };
-        cluster_actor.track_replication_progress(follower_res.clone(), &mut sessions);
-        cluster_actor.track_replication_progress(follower_res.clone(), &mut sessions);
-        cluster_actor.track_replication_progress(follower_res.clone(), &mut sessions);
+        cluster_actor.maybe_update_term(5).await;
 
-        // THEN - no change in consensus tracker even though the same voter voted multiple times
-        assert_eq!(cluster_actor.consensus_tracker.len(), 1);
-        assert_eq!(logger.last_log_index, 1);
+        // THEN
+        assert_eq!(cluster_actor.replication.term, 5);
+        assert_eq!(
+            cluster_actor.hard_state.state(),
+            &HardState { term: 5, voted_for: None, ..Default::default() }
+        );
+        assert!(!cluster_actor.replication.is_leader_mode);
     }
 
     #[tokio::test]
-    async fn logger_create_entries_from_lowest() {
+    async fn test_leader_compacts_logs_past_lagging_replica_and_snapshots_it() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-
-        let test_logs = vec![
-            write_operation_create_helper(1, 0, "foo", "bar"),
-            write_operation_create_helper(2, 0, "foo2", "bar"),
-            write_operation_create_helper(3, 0, "foo3", "bar"),
-        ];
-        logger.follower_write_entries(test_logs.clone()).await.unwrap();
+        let wal = MemoryOpLogs {
+            writer: (1..=5).map(|i| write_operation_create_helper(i, i, "key", "value")).collect(),
+        };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.hwm.store(5, Ordering::Release);
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            2,
+        )
+        .await;
 
         // WHEN
-        const LOWEST_FOLLOWER_COMMIT_INDEX: u64 = 2;
-        let logs = logger
-            .leader_write_entries(
-                &WriteRequest::Set { key: "foo4".into(), value: "bar".into() },
-                Some(LOWEST_FOLLOWER_COMMIT_INDEX),
-                0,
-            )
-            .await
-            .unwrap();
+        cluster_actor.compact_logs(&mut logger, &cache_manager, 4).await;
 
         // THEN
-        assert_eq!(logs.len(), 2);
-        assert_eq!(logs[0].log_index, 3);
-        assert_eq!(logs[1].log_index, 4);
-        assert_eq!(logger.last_log_index, 4);
+        assert_eq!(logger.log_start_index(), 5);
+        assert_eq!(logger.last_log_index, 5);
+        assert_eq!(logger.term_at(4).await, Some(4));
+        let HardState { last_included_index, last_included_term, .. } =
+            cluster_actor.hard_state.state().clone();
+        assert_eq!((last_included_index, last_included_term), (4, 4));
+        assert!(cluster_actor.snapshot_transfers.contains(&PeerIdentifier::new("localhost", 0)));
     }
 
     #[tokio::test]
-    async fn generate_follower_entries() {
+    async fn test_leader_prepares_snapshot_for_follower_behind_compacted_log() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-
+        let wal = MemoryOpLogs {
+            writer: (4..=5).map(|i| write_operation_create_helper(i, 1, "key", "value")).collect(),
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
         let mut cluster_actor = cluster_actor_create_helper().await;
-
+        cluster_actor.replication.hwm.store(5, Ordering::Release);
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
         let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
-        let cache_manager = CacheManager { inboxes: vec![] };
         cluster_member_create_helper(
             &mut cluster_actor,
-            0..5,
-            cluster_sender.clone(),
-            cache_manager,
-            3,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            1,
         )
         .await;
+        let follower = PeerIdentifier::new("localhost", 0);
+        let rejection = ReplicationResponse {
+            log_idx: 1,
+            term: 1,
+            rej_reason: RejectionReason::LogInconsistency,
+            from: follower.clone(),
+            read_seq: 0,
+            conflict: None,
+        };
 
-        let test_logs = vec![
-            write_operation_create_helper(1, 0, "foo", "bar"),
-            write_operation_create_helper(2, 0, "foo2", "bar"),
-            write_operation_create_helper(3, 0, "foo3", "bar"),
-        ];
-
-        cluster_actor.replication.hwm.store(3, Ordering::Release);
-
-        logger.follower_write_entries(test_logs).await.unwrap();
+        // WHEN
+        cluster_actor.handle_repl_rejection(rejection, &logger, &cache_manager).await;
 
-        //WHEN
-        // *add lagged followers with its commit index being 1
-        let cache_manager = CacheManager { inboxes: vec![] };
-        cluster_member_create_helper(&mut cluster_actor, 5..7, cluster_sender, cache_manager, 1)
-            .await;
+        // THEN
+        assert!(cluster_actor.snapshot_transfers.contains(&follower));
+        // The heartbeat scheduler shares the receiver, so skip whatever it sends in the meantime
+        let receiving = async {
+            while let Some(cmd) = cluster_actor.receiver.recv().await {
+                if let ClusterCommand::SendInstallSnapshot {
+                    to,
+                    last_included_index,
+                    last_included_term,
+                    snapshot,
+                } = cmd
+                {
+                    return (to, last_included_index, last_included_term, snapshot);
+                }
+            }
+            panic!("Expected SendInstallSnapshot");
+        };
+        let (to, last_included_index, last_included_term, snapshot) =
+            tokio::time::timeout(Duration::from_secs(1), receiving).await.unwrap();
+        assert_eq!(to, follower);
+        assert_eq!((last_included_index, last_included_term), (5, 1));
+        assert!(SnapshotLoader::load_from_bytes(&snapshot.unwrap()).is_ok());
+    }
 
-        // * add new log - this must create entries that are greater than 3
-        let lowest_hwm = cluster_actor.take_low_watermark();
-        let append_entries = logger
-            .leader_write_entries(
-                &WriteRequest::Set { key: "foo4".into(), value: "bar".into() },
-                lowest_hwm,
-                0,
-            )
+    #[tokio::test]
+    async fn test_follower_installs_snapshot_from_chunks() {
+        // GIVEN
+        let leader_cache = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+        leader_cache
+            .route_set(CacheEntry::KeyValue { key: "foo".into(), value: "bar".into() })
+            .await
+            .unwrap();
+        let snapshot = leader_cache
+            .route_save(SaveTarget::InMemory(Vec::new()), ReplicationId::Key("master".into()), 7)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .unwrap()
+            .into_inner();
+
+        let wal = MemoryOpLogs {
+            writer: (1..=2)
+                .map(|i| write_operation_create_helper(i, 0, "stale", "value"))
+                .collect(),
+        };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        cache_manager
+            .route_set(CacheEntry::KeyValue { key: "stale".into(), value: "value".into() })
             .await
             .unwrap();
 
+        let voters =
+            vec![PeerIdentifier::new("localhost", 8080), PeerIdentifier::new("localhost", 8081)];
+        let mut chunk =
+            InstallSnapshot::chunks(&cluster_actor.replication, 7, 2, voters.clone(), snapshot)
+                .pop()
+                .unwrap();
+        let rest = chunk.data.split_off(chunk.data.len() / 2);
+        let first = InstallSnapshot { done: false, ..chunk.clone() };
+        let second = InstallSnapshot { offset: first.data.len() as u64, data: rest, ..chunk };
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_manager = config_manager_create_helper(dir.path().to_str().unwrap());
+
+        // WHEN
+        cluster_actor.install_snapshot(first, &mut logger, &cache_manager, &config_manager).await;
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Acquire), 0);
+        cluster_actor.install_snapshot(second, &mut logger, &cache_manager, &config_manager).await;
+
+        // THEN
+        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Acquire), 7);
+        assert_eq!((logger.last_log_index, logger.last_log_term), (7, 2));
+        assert_eq!(logger.term_at(7).await, Some(2));
+        let saved =
+            SnapshotLoader::load_from_filepath(config_manager.get_filepath().await.unwrap())
+                .await
+                .unwrap();
+        assert_eq!(saved.extract_replication_info().1, 7);
+        let HardState { last_included_index, last_included_term, .. } =
+            cluster_actor.hard_state.state().clone();
+        assert_eq!((last_included_index, last_included_term), (7, 2));
+        assert!(logger.is_empty());
+        assert!(cluster_actor.incoming_snapshot.is_empty());
+        assert_eq!(cluster_actor.membership.voters().iter().cloned().collect::<Vec<_>>(), voters);
+        assert_eq!(cluster_actor.hard_state.state().voters, voters);
+        assert_eq!(
+            cache_manager.route_get("foo").await.unwrap(),
+            Some(CacheValue::Value("bar".into()))
+        );
+        assert_eq!(cache_manager.route_get("stale").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_follower_accepts_entries_right_after_installed_snapshot() {
+        // GIVEN
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 7, 2);
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        // WHEN
+        let result = cluster_actor.ensure_prev_consistency(&mut logger, 7, 2).await;
+
         // THEN
-        assert_eq!(append_entries.len(), 3);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_follower_reports_where_conflicting_term_starts() {
+        // GIVEN
+        let wal = MemoryOpLogs {
+            writer: [1, 1, 2, 2, 2]
+                .into_iter()
+                .zip(1..)
+                .map(|(term, i)| write_operation_create_helper(i, term, "key", "value"))
+                .collect(),
+        };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+
+        // WHEN
+        let result = cluster_actor.ensure_prev_consistency(&mut logger, 5, 3).await;
+
+        // THEN
+        assert_eq!(result, Err(Some(LogConflict { term: 2, first_index: 3 })));
+    }
+
+    #[tokio::test]
+    async fn test_divergent_follower_ends_with_the_leaders_log() {
+        // GIVEN: the follower holds entries of terms [1, 1, 2, 2] and the leader of terms [1, 1, 3, 3, 3]
+        let log_of_terms = |terms: &[u64]| -> Vec<_> {
+            terms
+                .iter()
+                .zip(1..)
+                .map(|(term, i)| write_operation_create_helper(i, *term, "key", &term.to_string()))
+                .collect()
+        };
+        let leader_log = log_of_terms(&[1, 1, 3, 3, 3]);
+        let wal = MemoryOpLogs { writer: log_of_terms(&[1, 1, 2, 2]) };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let leader = PeerIdentifier::new("localhost", 8080);
+        let mut replies = leader_peer_create_helper(&mut cluster_actor, leader).await;
+
+        // WHEN: the leader sends its last entry, then resends from where the conflicting term starts
+        let mut heartbeat = heartbeat_create_helper(3, 0, leader_log[4..].to_vec());
+        heartbeat.prev_log_term = 3;
+        assert!(cluster_actor.try_replicate_logs(&mut logger, &mut heartbeat).await.is_err());
+        let Some(ClusterCommand::ReplicationResponse(rejection)) = replies.recv().await else {
+            panic!("Expected a rejection");
+        };
+        let first_index = rejection.conflict.unwrap().first_index as usize;
 
-        let entries =
-            cluster_actor.generate_follower_entries(append_entries, 3, 0).collect::<Vec<_>>();
+        let mut heartbeat = heartbeat_create_helper(3, 0, leader_log[first_index - 1..].to_vec());
+        heartbeat.prev_log_term = leader_log[first_index - 2].term;
+        cluster_actor.try_replicate_logs(&mut logger, &mut heartbeat).await.unwrap();
 
-        // * for old followers must have 1 entry
-        assert_eq!(entries.iter().filter(|(_, hb)| hb.append_entries.len() == 1).count(), 5);
-        // * for lagged followers must have 3 entries
-        assert_eq!(entries.iter().filter(|(_, hb)| hb.append_entries.len() == 3).count(), 2)
+        // THEN
+        assert_eq!(first_index, 3);
+        assert_eq!(logger.target.writer, leader_log);
+        assert_eq!((logger.last_log_index, logger.last_log_term), (5, 3));
+        let Some(ClusterCommand::ReplicationResponse(ack)) = replies.recv().await else {
+            panic!("Expected an ack");
+        };
+        assert_eq!((ack.log_idx, ack.rej_reason), (5, RejectionReason::None));
     }
 
     #[tokio::test]
-    async fn follower_cluster_actor_replicate_log() {
-        // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+    async fn test_follower_acks_only_up_to_the_entries_it_was_sent() {
+        // GIVEN: the follower holds entries past the ones a late batch resends
+        let wal = MemoryOpLogs {
+            writer: (1..=5).map(|i| write_operation_create_helper(i, 1, "key", "value")).collect(),
+        };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
         let mut cluster_actor = cluster_actor_create_helper().await;
-        // WHEN - term
-        let heartbeat = heartbeat_create_helper(
-            0,
+        let leader = PeerIdentifier::new("localhost", 8080);
+        let mut replies = leader_peer_create_helper(&mut cluster_actor, leader).await;
+
+        // WHEN
+        let mut heartbeat = heartbeat_create_helper(
+            1,
             0,
-            vec![
-                write_operation_create_helper(1, 0, "foo", "bar"),
-                write_operation_create_helper(2, 0, "foo2", "bar"),
-            ],
+            vec![write_operation_create_helper(2, 1, "key", "value")],
         );
-        let cache_manager = CacheManager {
-            inboxes: (0..10).map(|_| CacheCommandSender(channel(10).0)).collect::<Vec<_>>(),
-        };
-        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+        heartbeat.prev_log_term = 1;
+        cluster_actor.try_replicate_logs(&mut logger, &mut heartbeat).await.unwrap();
 
-        // THEN
-        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 0);
-        assert_eq!(logger.last_log_index, 2);
-        let logs = logger.range(0, 2);
-        assert_eq!(logs.len(), 2);
-        assert_eq!(logs[0].log_index, 1);
-        assert_eq!(logs[1].log_index, 2);
-        assert_eq!(logs[0].request, WriteRequest::Set { key: "foo".into(), value: "bar".into() });
-        assert_eq!(logs[1].request, WriteRequest::Set { key: "foo2".into(), value: "bar".into() });
+        // THEN: the entries after the batch weren't checked against the leader's, so they don't count
+        let Some(ClusterCommand::ReplicationResponse(ack)) = replies.recv().await else {
+            panic!("Expected an ack");
+        };
+        assert_eq!(ack.log_idx, 2);
+        assert_eq!(logger.last_log_index, 5);
     }
 
     #[tokio::test]
-    async fn follower_cluster_actor_replicate_state() {
+    async fn test_follower_rejects_entries_past_the_end_of_its_log() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
-        let (cache_handler, mut receiver) = tokio::sync::mpsc::channel(100);
+        let wal = MemoryOpLogs {
+            writer: (1..=2).map(|i| write_operation_create_helper(i, 1, "key", "value")).collect(),
+        };
+        let mut logger = ReplicatedLogs::restore(wal).await.unwrap();
         let mut cluster_actor = cluster_actor_create_helper().await;
 
-        let heartbeat = heartbeat_create_helper(
-            0,
-            0,
-            vec![
-                write_operation_create_helper(1, 0, "foo", "bar"),
-                write_operation_create_helper(2, 0, "foo2", "bar"),
-            ],
-        );
-
-        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(cache_handler)] };
-        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
-
-        // WHEN - commit until 2
-        let task = tokio::spawn(async move {
-            while let Some(message) = receiver.recv().await {
-                match message {
-                    CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, value } } => {
-                        assert_eq!(value, "bar");
-                        if key == "foo2" {
-                            break;
-                        }
-                    },
-                    _ => continue,
-                }
-            }
-        });
-        let heartbeat = heartbeat_create_helper(0, 2, vec![]);
-        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
+        // WHEN
+        let result = cluster_actor.ensure_prev_consistency(&mut logger, 5, 1).await;
 
         // THEN
-        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 2);
+        assert_eq!(result, Err(None));
         assert_eq!(logger.last_log_index, 2);
-        task.await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_apply_multiple_committed_entries() {
+    async fn test_leader_backtracks_a_whole_term_on_conflict() {
         // GIVEN
-        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let wal = MemoryOpLogs {
+            writer: [1, 1, 1, 3, 3, 3]
+                .into_iter()
+                .zip(1..)
+                .map(|(term, i)| write_operation_create_helper(i, term, "key", "value"))
+                .collect(),
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
         let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..2,
+            cluster_sender,
+            cache_manager.clone(),
+            6,
+        )
+        .await;
+        let rejection = |from: &str, conflict| ReplicationResponse {
+            log_idx: 6,
+            term: 3,
+            rej_reason: RejectionReason::LogInconsistency,
+            from: PeerIdentifier(from.into()),
+            read_seq: 0,
+            conflict: Some(conflict),
+        };
+        let match_index = |actor: &ClusterActor, id: &str| {
+            actor.replicas().find(|(peer, _, _)| peer.0 == id).map(|(_, _, idx)| idx).unwrap()
+        };
 
-        // Add multiple entries
-        let entries = vec![
-            write_operation_create_helper(1, 0, "key1", "value1"),
-            write_operation_create_helper(2, 0, "key2", "value2"),
-            write_operation_create_helper(3, 0, "key3", "value3"),
-        ];
-
-        let heartbeat = heartbeat_create_helper(1, 0, entries);
-
-        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-        let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
-
-        // First append entries but don't commit
-        cluster_actor.replicate(&mut logger, heartbeat, &cache_manager).await;
-
-        // Create a task to monitor applied entries
-        let monitor_task = tokio::spawn(async move {
-            let mut applied_keys = Vec::new();
-
-            while let Some(message) = rx.recv().await {
-                if let CacheCommand::Set { cache_entry: CacheEntry::KeyValue { key, .. } } = message
-                {
-                    applied_keys.push(key);
-                    if applied_keys.len() == 3 {
-                        break;
-                    }
-                }
-            }
+        // WHEN - the leader has no entry of term 2, then holds term 1 entries up to 3
+        cluster_actor
+            .handle_repl_rejection(
+                rejection("localhost:0", LogConflict { term: 2, first_index: 3 }),
+                &logger,
+                &cache_manager,
+            )
+            .await;
+        cluster_actor
+            .handle_repl_rejection(
+                rejection("localhost:1", LogConflict { term: 1, first_index: 1 }),
+                &logger,
+                &cache_manager,
+            )
+            .await;
 
-            applied_keys
-        });
+        // THEN
+        assert_eq!(match_index(&cluster_actor, "localhost:0"), 2);
+        assert_eq!(match_index(&cluster_actor, "localhost:1"), 3);
+    }
 
-        // WHEN - commit all entries
-        let commit_heartbeat = heartbeat_create_helper(1, 3, vec![]);
+    #[tokio::test]
+    async fn test_leader_rewinds_once_for_rejections_of_batches_in_flight() {
+        // GIVEN
+        let wal = MemoryOpLogs {
+            writer: [1, 1, 1, 3, 3, 3]
+                .into_iter()
+                .zip(1..)
+                .map(|(term, i)| write_operation_create_helper(i, term, "key", "value"))
+                .collect(),
+        };
+        let logger = ReplicatedLogs::restore(wal).await.unwrap();
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let cache_manager = CacheManager::run_cache_actors(cluster_actor.replication.hwm.clone());
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            6,
+        )
+        .await;
+        let follower = PeerIdentifier::new("localhost", 0);
+        let rejection = ReplicationResponse {
+            log_idx: 6,
+            term: 3,
+            rej_reason: RejectionReason::LogInconsistency,
+            from: follower.clone(),
+            read_seq: 0,
+            conflict: Some(LogConflict { term: 2, first_index: 3 }),
+        };
+        cluster_actor.handle_repl_rejection(rejection.clone(), &logger, &cache_manager).await;
+        let match_index = cluster_actor.members[&follower].kind.match_index();
 
-        cluster_actor.replicate(&mut logger, commit_heartbeat, &cache_manager).await;
+        // WHEN - a batch sent before the rewind is rejected for the same conflict
+        cluster_actor.handle_repl_rejection(rejection, &logger, &cache_manager).await;
 
         // THEN
-        // Verify that all entries were committed and applied in order
-        let applied_keys = tokio::time::timeout(Duration::from_secs(1), monitor_task)
-            .await
-            .expect("Timeout waiting for entries to be applied")
-            .expect("Task failed");
-
-        assert_eq!(applied_keys, vec!["key1", "key2", "key3"]);
-        assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 3);
+        assert_eq!(match_index, 2);
+        assert_eq!(cluster_actor.members[&follower].kind.match_index(), match_index);
+        assert_eq!(cluster_actor.members[&follower].inflight.sent_up_to(), 6);
     }
 
     #[tokio::test]
@@ -1390,11 +3689,10 @@ mod test {
 
     #[tokio::test]
     async fn follower_truncates_log_on_term_mismatch() {
-        // GIVEN: A follower with an existing log entry at index 1, term 1
+        // GIVEN: A follower holding entries 1 to 3 of term 1
         let mut inmemory = MemoryOpLogs::default();
-        //prefill
-
         inmemory.writer.extend(vec![
+            write_operation_create_helper(1, 1, "key0", "val0"),
             write_operation_create_helper(2, 1, "key1", "val1"),
             write_operation_create_helper(3, 1, "key2", "val2"),
         ]);
@@ -1402,23 +3700,20 @@ mod test {
         let mut logger = ReplicatedLogs::new(inmemory, 3, 1);
         let mut cluster_actor = cluster_actor_create_helper().await;
 
-        assert_eq!(logger.target.writer.len(), 2);
-
-        // Simulate an initial log entry at index 1, term 1
-        // WHEN: Leader sends an AppendEntries with prev_log_index=1, prev_log_term=2 (mismatch)
+        // WHEN: Leader sends an AppendEntries with prev_log_index=2, prev_log_term=2 (mismatch)
         let mut heartbeat = heartbeat_create_helper(
             2,
             0,
-            vec![write_operation_create_helper(2, 0, "key2", "val2")],
+            vec![write_operation_create_helper(3, 2, "key2", "val2")],
         );
-        heartbeat.prev_log_term = 0;
-        heartbeat.prev_log_index = 2;
+        heartbeat.prev_log_term = 2;
 
         let result = cluster_actor.try_replicate_logs(&mut logger, &mut heartbeat).await;
 
-        // THEN: Expect truncation and rejection
-        assert_eq!(logger.target.writer.len(), 1);
+        // THEN: The conflicting entry goes along with everything after it, and the entries are rejected
         assert!(result.is_err(), "Should reject due to term mismatch");
+        assert_eq!(logger.target.writer.len(), 1);
+        assert_eq!((logger.last_log_index, logger.last_log_term), (1, 1));
     }
 
     #[tokio::test]
@@ -1670,4 +3965,212 @@ mod test {
         assert!(handle.await.is_ok());
         assert!(rx.await.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_read_index_waits_for_voter_confirmation() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..3, cluster_sender, cache_manager, 0)
+            .await;
+        voters_create_helper(
+            &mut cluster_actor,
+            &[PeerIdentifier::new("localhost", 0), PeerIdentifier::new("localhost", 1)],
+        );
+        cluster_actor.replication.hwm.store(4, Ordering::Release);
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        cluster_actor.read_index(tx, &logger).await;
+        let seq = cluster_actor.read_index.pending_seq().unwrap();
+        let ack = ReplicationResponse {
+            log_idx: 0,
+            term: 0,
+            rej_reason: RejectionReason::None,
+            from: PeerIdentifier("".into()),
+            read_seq: seq,
+            conflict: None,
+        };
+
+        // THEN - learners can't confirm leadership
+        cluster_actor.confirm_read_index(ack.clone().set_from("localhost:2"));
+        assert!(rx.try_recv().is_err());
+
+        cluster_actor.confirm_read_index(ack.set_from("localhost:0"));
+        assert_eq!(rx.await.unwrap().unwrap(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_read_index_on_follower_is_none() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.is_leader_mode = false;
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.read_index(tx, &logger).await;
+
+        // THEN
+        assert_eq!(rx.await.unwrap().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_follower_refuses_write_with_leader_it_follows() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.replication.is_leader_mode = false;
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let write = WriteRequest::Set { key: "foo".into(), value: "bar".into() };
+
+        // WHEN - no leader heard from yet
+        let res = cluster_actor.try_create_append_entries(&mut logger, &write).await;
+
+        // THEN
+        assert!(matches!(res, Err(ConsensusClientResponse::Err(_))));
+
+        let leader = PeerIdentifier::new("127.0.0.1", 6380);
+        cluster_actor.leader = Some(leader.clone());
+        let res = cluster_actor.try_create_append_entries(&mut logger, &write).await;
+        assert!(matches!(res, Err(ConsensusClientResponse::Moved(moved)) if moved == leader));
+    }
+
+    #[tokio::test]
+    async fn test_read_index_under_lease_needs_fresh_quorum_acks() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.lease = Some(LeaderLease::default());
+        voters_create_helper(&mut cluster_actor, &[PeerIdentifier::new("localhost", 0)]);
+        cluster_actor.replication.hwm.store(2, Ordering::Release);
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+
+        // WHEN - no voter acknowledged this leader yet
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.read_index(tx, &logger).await;
+
+        // THEN
+        assert!(rx.await.unwrap().is_err());
+
+        cluster_actor.send_leader_heartbeat(&logger).await;
+        let ack = ReplicationResponse {
+            log_idx: 0,
+            term: 0,
+            rej_reason: RejectionReason::None,
+            from: PeerIdentifier::new("localhost", 0),
+            read_seq: 1,
+            conflict: None,
+        };
+        cluster_actor.confirm_read_index(ack);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.read_index(tx, &logger).await;
+        assert_eq!(rx.await.unwrap().unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_lease_reads_refused_once_leadership_transfer_starts() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        cluster_actor.lease = Some(LeaderLease::default());
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(
+            &mut cluster_actor,
+            0..1,
+            cluster_sender,
+            cache_manager.clone(),
+            0,
+        )
+        .await;
+        let target = PeerIdentifier::new("localhost", 0);
+        voters_create_helper(&mut cluster_actor, std::slice::from_ref(&target));
+        let logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        cluster_actor.lease.as_mut().unwrap().record_sent(1);
+        cluster_actor.renew_lease(&target, 1);
+
+        // WHEN
+        let (tx, _rx) = tokio::sync::oneshot::channel();
+        cluster_actor.transfer_leadership(target.clone(), tx, &logger, &cache_manager).await;
+
+        // THEN - the target is told to campaign, so the lease is gone
+        assert!(cluster_actor.leadership_transfer.as_ref().unwrap().timeout_now_sent);
+        assert!(
+            !cluster_actor.lease.as_ref().unwrap().is_valid(cluster_actor.membership.voters(), 1)
+        );
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor.read_index(tx, &logger).await;
+        assert_eq!(
+            rx.await.unwrap().unwrap_err().to_string(),
+            "ERR leadership transfer in progress, retry on the current leader"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_leader_appends_noop_in_its_term() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        voters_create_helper(&mut cluster_actor, &[PeerIdentifier::new("localhost", 0)]);
+        cluster_actor.replication.is_leader_mode = false;
+        cluster_actor.replication.election_state = ElectionState::Follower { voted_for: None };
+        cluster_actor.replication.term = 1;
+        cluster_actor.become_candidate();
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        logger
+            .follower_write_entries(&[write_operation_create_helper(1, 1, "foo", "bar")])
+            .await
+            .unwrap();
+
+        // WHEN
+        cluster_actor
+            .tally_vote(RequestVoteReply { term: 2, vote_granted: true }, &mut logger)
+            .await;
+
+        // THEN
+        assert!(cluster_actor.replication.is_leader_mode);
+        assert_eq!(logger.last_log_index, 2);
+        let noop = logger.read_at(2).await.unwrap();
+        assert_eq!(noop.request, WriteRequest::NoOp);
+        assert_eq!(noop.term, 2);
+        // - committed once the other voter acknowledges it
+        assert!(cluster_actor.consensus_tracker.get(&2).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_check_quorum_steps_down_when_voters_are_silent() {
+        // GIVEN
+        let mut cluster_actor = cluster_actor_create_helper().await;
+        let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+        let cache_manager = CacheManager { inboxes: vec![] };
+        cluster_member_create_helper(&mut cluster_actor, 0..2, cluster_sender, cache_manager, 0)
+            .await;
+        let voters = cluster_actor.members.keys().cloned().collect::<Vec<_>>();
+        voters_create_helper(&mut cluster_actor, &voters);
+        let mut logger = ReplicatedLogs::new(MemoryOpLogs::default(), 0, 0);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cluster_actor
+            .req_consensus(
+                &mut logger,
+                WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                tx,
+                None,
+                None,
+            )
+            .await;
+
+        // - one voter is still heard from
+        cluster_actor.check_quorum().await;
+        assert!(cluster_actor.replication.is_leader_mode);
+
+        // WHEN
+        for peer in cluster_actor.members.values_mut() {
+            peer.last_seen = Instant::now() - Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX);
+        }
+        cluster_actor.check_quorum().await;
+
+        // THEN
+        assert!(!cluster_actor.replication.is_leader_mode);
+        assert_eq!(cluster_actor.replication.role, ReplicationRole::Follower);
+        assert!(matches!(rx.await.unwrap(), ConsensusClientResponse::Err(_)));
+    }
 }
@@ -1,9 +1,11 @@
+use std::time::Duration;
 use tokio::net::TcpStream;
 
 use crate::{
     ReplicationState,
     domains::{
         cluster_actors::{
+            consensus::ReadIndexCallback,
             replication::{HeartBeatMessage, ReplicationId, ReplicationRole},
             session::SessionRequest,
         },
@@ -13,7 +15,10 @@ use crate::{
     prelude::PeerIdentifier,
 };
 
-use super::{ConsensusClientResponse, ReplicationResponse, RequestVote, RequestVoteReply};
+use super::{
+    ConsensusClientResponse, ForwardReply, ForwardRequest, InstallSnapshot, ReplicationResponse,
+    RequestVote, RequestVoteReply, TimeoutNow,
+};
 
 #[derive(Debug)]
 pub(crate) enum ClusterCommand {
@@ -32,7 +37,18 @@ pub(crate) enum ClusterCommand {
         replid: ReplicationId,
         hwm: u64,
     },
+    ReplayCommittedLogs(tokio::sync::oneshot::Sender<()>),
+    CompactLogs {
+        snapshot_index: u64,
+    },
     InstallLeaderState(Vec<WriteOperation>),
+    SendInstallSnapshot {
+        to: PeerIdentifier,
+        last_included_index: u64,
+        last_included_term: u64,
+        snapshot: anyhow::Result<Vec<u8>>,
+    },
+    InstallSnapshot(InstallSnapshot),
     SendClusterHeatBeat,
     ForgetPeer(PeerIdentifier, tokio::sync::oneshot::Sender<Option<()>>),
     ReplicaOf(PeerIdentifier, tokio::sync::oneshot::Sender<()>),
@@ -40,11 +56,10 @@ pub(crate) enum ClusterCommand {
         log: WriteRequest,
         callback: tokio::sync::oneshot::Sender<ConsensusClientResponse>,
         session_req: Option<SessionRequest>,
+        // overrides the configured write timeout for this request
+        timeout: Option<Duration>,
     },
     ReplicationResponse(ReplicationResponse),
-    SendCommitHeartBeat {
-        log_idx: u64,
-    },
     AppendEntriesRPC(HeartBeatMessage),
 
     SendAppendEntriesRPC,
@@ -52,6 +67,30 @@ pub(crate) enum ClusterCommand {
     StartLeaderElection,
     VoteElection(RequestVote),
     ApplyElectionVote(RequestVoteReply),
+    PreVoteElection(RequestVote),
+    ApplyPreVote(RequestVoteReply),
+    TransferLeadership {
+        target: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+    },
+    TimeoutNow(TimeoutNow),
+    ReadIndex(ReadIndexCallback),
+    // a request a follower forwarded to this node, as the leader
+    ForwardRequest(ForwardRequest),
+    // the leader's answer to a request this node forwarded
+    ForwardReply(ForwardReply),
+    SendForwardReply {
+        to: PeerIdentifier,
+        reply: ForwardReply,
+    },
+    PromoteLearner {
+        learner: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+    },
+    DemoteVoter {
+        voter: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+    },
     ClusterHeartBeat(HeartBeatMessage),
     GetRole(tokio::sync::oneshot::Sender<ReplicationRole>),
     SubscribeToTopologyChange(
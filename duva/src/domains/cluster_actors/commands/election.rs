@@ -18,6 +18,12 @@ impl RequestVote {
             last_log_term,
         }
     }
+
+    /// Election restriction (§5.4.1): the candidate's log must be at least as up-to-date as the voter's.
+    /// Logs are compared by the term of their last entry first, then by their length.
+    pub(crate) fn is_log_up_to_date(&self, last_log_index: u64, last_log_term: u64) -> bool {
+        (self.last_log_term, self.last_log_index) >= (last_log_term, last_log_index)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
@@ -25,3 +31,19 @@ pub struct RequestVoteReply {
     pub(crate) term: u64,
     pub(crate) vote_granted: bool,
 }
+
+/// Asks whether peers would vote for the sender in `term`, the term it would campaign in.
+/// Neither side changes its term or vote, so a node that can't win never disrupts the cluster.
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct PreVote(pub(crate) RequestVote);
+
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct PreVoteReply(pub(crate) RequestVoteReply);
+
+/// Sent by a leader handing over leadership once the target's log has caught up.
+/// The target starts an election right away, skipping the election timeout and the pre-vote.
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct TimeoutNow {
+    pub(crate) term: u64,
+    pub(crate) from: PeerIdentifier,
+}
@@ -0,0 +1,61 @@
+use super::ConsensusClientResponse;
+use crate::domains::{
+    cluster_actors::session::SessionRequest, operation_logs::WriteRequest,
+    peers::identifier::PeerIdentifier,
+};
+
+/// Sent by a follower to the leader on behalf of one of its clients.
+/// The leader answers with a `ForwardReply` carrying the same `seq`.
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct ForwardRequest {
+    pub(crate) seq: u64,
+    pub(crate) from: PeerIdentifier,
+    pub(crate) kind: ForwardedKind,
+}
+
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub enum ForwardedKind {
+    Write { log: WriteRequest, session_req: Option<SessionRequest>, timeout_mills: Option<u64> },
+    // commit index a linearizable read on the follower has to wait for
+    ReadIndex,
+}
+
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct ForwardReply {
+    pub(crate) seq: u64,
+    pub(crate) response: ForwardedResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub enum ForwardedResponse {
+    // serialized response of the committed write
+    Written(Vec<u8>),
+    ReadIndex(u64),
+    Err(String),
+    // the request reached a node that is no longer the leader
+    Moved(PeerIdentifier),
+}
+
+impl From<ConsensusClientResponse> for ForwardedResponse {
+    fn from(value: ConsensusClientResponse) -> Self {
+        match value {
+            ConsensusClientResponse::Result(res) => {
+                ForwardedResponse::Written(res.serialize().into())
+            },
+            ConsensusClientResponse::Err(err) => ForwardedResponse::Err(err),
+            ConsensusClientResponse::Moved(leader) => ForwardedResponse::Moved(leader),
+        }
+    }
+}
+
+impl From<anyhow::Result<Option<u64>>> for ForwardedResponse {
+    fn from(value: anyhow::Result<Option<u64>>) -> Self {
+        match value {
+            Ok(Some(read_index)) => ForwardedResponse::ReadIndex(read_index),
+            Ok(None) => {
+                ForwardedResponse::Err("ERR forwarded to a node that is not the leader".into())
+            },
+            Err(err) => ForwardedResponse::Err(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::domains::{
+    cluster_actors::replication::ReplicationState, peers::identifier::PeerIdentifier,
+};
+
+const SNAPSHOT_CHUNK_SIZE: usize = 16 * 1024;
+
+/// A piece of the leader's snapshot, sent to a follower whose next entry has already been compacted away.
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct InstallSnapshot {
+    pub(crate) term: u64,
+    pub(crate) from: PeerIdentifier,
+    pub(crate) last_included_index: u64,
+    pub(crate) last_included_term: u64,
+    pub(crate) offset: u64,
+    pub(crate) data: Vec<u8>,
+    pub(crate) done: bool,
+    // committed voting configuration, which the compacted log no longer carries
+    pub(crate) voters: Vec<PeerIdentifier>,
+}
+
+impl InstallSnapshot {
+    /// Splits `snapshot` into chunks. The last chunk has `done` set, even when the snapshot is empty.
+    pub(crate) fn chunks(
+        repl: &ReplicationState,
+        last_included_index: u64,
+        last_included_term: u64,
+        voters: Vec<PeerIdentifier>,
+        snapshot: Vec<u8>,
+    ) -> Vec<Self> {
+        let mut chunks: Vec<_> = snapshot
+            .chunks(SNAPSHOT_CHUNK_SIZE)
+            .enumerate()
+            .map(|(i, data)| Self {
+                term: repl.term,
+                from: repl.self_identifier(),
+                last_included_index,
+                last_included_term,
+                offset: (i * SNAPSHOT_CHUNK_SIZE) as u64,
+                data: data.to_vec(),
+                done: false,
+                voters: voters.clone(),
+            })
+            .collect();
+
+        match chunks.last_mut() {
+            Some(last) => last.done = true,
+            None => chunks.push(Self {
+                term: repl.term,
+                from: repl.self_identifier(),
+                last_included_index,
+                last_included_term,
+                offset: 0,
+                data: vec![],
+                done: true,
+                voters,
+            }),
+        }
+        chunks
+    }
+}
@@ -1,10 +1,14 @@
 mod cluster_actor_command;
 mod election;
+mod forward;
+mod install_snapshot;
 mod peer_listener_command;
 pub(crate) mod types;
 mod write_con;
 pub(crate) use cluster_actor_command::ClusterCommand;
 pub(crate) use election::*;
+pub(crate) use forward::*;
+pub(crate) use install_snapshot::*;
 pub(crate) use peer_listener_command::PeerListenerCommand;
 pub(crate) use types::*;
 pub(crate) use write_con::*;
@@ -1,6 +1,9 @@
 use crate::domains::{
     cluster_actors::{
-        commands::{ReplicationResponse, RequestVote, RequestVoteReply},
+        commands::{
+            ForwardReply, ForwardRequest, InstallSnapshot, PreVote, PreVoteReply,
+            ReplicationResponse, RequestVote, RequestVoteReply, TimeoutNow,
+        },
         replication::HeartBeatMessage,
     },
     operation_logs::WriteOperation,
@@ -15,6 +18,12 @@ pub(crate) enum PeerListenerCommand {
     Acks(ReplicationResponse),
     RequestVote(RequestVote),
     RequestVoteReply(RequestVoteReply),
+    InstallSnapshot(InstallSnapshot),
+    PreVote(PreVote),
+    PreVoteReply(PreVoteReply),
+    TimeoutNow(TimeoutNow),
+    ForwardRequest(ForwardRequest),
+    ForwardReply(ForwardReply),
 }
 
 impl TryFrom<QueryIO> for PeerListenerCommand {
@@ -40,6 +49,12 @@ impl TryFrom<QueryIO> for PeerListenerCommand {
             QueryIO::ConsensusFollowerResponse(acks) => Ok(PeerListenerCommand::Acks(acks)),
             QueryIO::RequestVote(vote) => Ok(PeerListenerCommand::RequestVote(vote)),
             QueryIO::RequestVoteReply(reply) => Ok(PeerListenerCommand::RequestVoteReply(reply)),
+            QueryIO::InstallSnapshot(chunk) => Ok(PeerListenerCommand::InstallSnapshot(chunk)),
+            QueryIO::PreVote(pre_vote) => Ok(PeerListenerCommand::PreVote(pre_vote)),
+            QueryIO::PreVoteReply(reply) => Ok(PeerListenerCommand::PreVoteReply(reply)),
+            QueryIO::TimeoutNow(timeout_now) => Ok(PeerListenerCommand::TimeoutNow(timeout_now)),
+            QueryIO::ForwardRequest(request) => Ok(PeerListenerCommand::ForwardRequest(request)),
+            QueryIO::ForwardReply(reply) => Ok(PeerListenerCommand::ForwardReply(reply)),
             _ => Err(anyhow::anyhow!("Invalid data")),
         }
     }
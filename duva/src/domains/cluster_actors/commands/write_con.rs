@@ -1,11 +1,15 @@
 use crate::domains::{
     cluster_actors::replication::ReplicationState, peers::identifier::PeerIdentifier,
+    query_parsers::QueryIO,
 };
 
 #[derive(Debug)]
 pub(crate) enum ConsensusClientResponse {
-    LogIndex(Option<u64>),
+    // the entry got committed and applied, with what applying it returned
+    Result(QueryIO),
     Err(String),
+    // refused by a follower, which points the client at the leader it knows of
+    Moved(PeerIdentifier),
 }
 
 #[derive(Debug, Clone, PartialEq, bincode::Decode, bincode::Encode)]
@@ -14,6 +18,18 @@ pub struct ReplicationResponse {
     pub(crate) term: u64,
     pub(crate) rej_reason: RejectionReason,
     pub(crate) from: PeerIdentifier,
+    // read index round this acknowledges, 0 for replication acks
+    pub(crate) read_seq: u64,
+    // set when the entry before the sent ones exists but belongs to another term
+    pub(crate) conflict: Option<LogConflict>,
+}
+
+/// Lets the leader skip a follower's whole conflicting term instead of backing off one entry at a time.
+#[derive(Debug, Clone, PartialEq, bincode::Decode, bincode::Encode)]
+pub struct LogConflict {
+    pub(crate) term: u64,
+    // first index the follower holds for `term`
+    pub(crate) first_index: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, bincode::Decode, bincode::Encode)]
@@ -29,7 +45,22 @@ impl ReplicationResponse {
         rej_reason: RejectionReason,
         repl_state: &ReplicationState,
     ) -> Self {
-        Self { log_idx, term: repl_state.term, rej_reason, from: repl_state.self_identifier() }
+        Self {
+            log_idx,
+            term: repl_state.term,
+            rej_reason,
+            from: repl_state.self_identifier(),
+            read_seq: 0,
+            conflict: None,
+        }
+    }
+
+    pub(crate) fn with_read_seq(self, read_seq: u64) -> Self {
+        Self { read_seq, ..self }
+    }
+
+    pub(crate) fn with_conflict(self, conflict: Option<LogConflict>) -> Self {
+        Self { conflict, ..self }
     }
 
     pub(crate) fn is_granted(&self) -> bool {
@@ -51,8 +51,9 @@ pub(crate) struct ElectionVoting {
 }
 
 impl ElectionVoting {
+    // `replica_count` other voters plus the candidate itself; a candidate never wins on its own vote alone
     fn get_required_votes(&self) -> u8 {
-        (self.replica_count + 1).div_ceil(2)
+        self.replica_count.div_ceil(2).max(1)
     }
     pub(crate) fn voting_if_unfinished(mut self) -> Option<Self> {
         self.cnt += 1;
@@ -74,11 +75,11 @@ fn test_get_required_votes() {
     assert_eq!(ev.get_required_votes(), 1);
 
     let ev = ElectionVoting { cnt: 0, replica_count: 2 };
-    assert_eq!(ev.get_required_votes(), 2);
+    assert_eq!(ev.get_required_votes(), 1);
 
     let ev = ElectionVoting { cnt: 0, replica_count: 3 };
     assert_eq!(ev.get_required_votes(), 2);
 
     let ev = ElectionVoting { cnt: 0, replica_count: 4 };
-    assert_eq!(ev.get_required_votes(), 3);
+    assert_eq!(ev.get_required_votes(), 2);
 }
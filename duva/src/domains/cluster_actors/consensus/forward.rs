@@ -0,0 +1,143 @@
+use super::log::ConsensusCallback;
+use super::read_index::ReadIndexCallback;
+use crate::domains::cluster_actors::commands::{
+    ConsensusClientResponse, ForwardReply, ForwardedResponse,
+};
+use crate::domains::query_parsers::deserialize;
+use bytes::BytesMut;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Requests this follower forwarded to the leader, waiting for its reply.
+#[derive(Debug, Default)]
+pub(crate) struct ForwardTracker {
+    last_seq: u64,
+    pending: HashMap<u64, PendingForward>,
+}
+
+#[derive(Debug)]
+struct PendingForward {
+    callback: ForwardCallback,
+    deadline: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) enum ForwardCallback {
+    Write(ConsensusCallback),
+    ReadIndex(ReadIndexCallback),
+}
+
+impl ForwardCallback {
+    pub(crate) fn fail(self, err: impl Into<String>) {
+        let err = err.into();
+        match self {
+            ForwardCallback::Write(callback) => {
+                let _ = callback.send(ConsensusClientResponse::Err(err));
+            },
+            ForwardCallback::ReadIndex(callback) => {
+                let _ = callback.send(Err(anyhow::anyhow!(err)));
+            },
+        }
+    }
+
+    fn resolve(self, response: ForwardedResponse) {
+        match (self, response) {
+            (ForwardCallback::Write(callback), ForwardedResponse::Written(res)) => {
+                let res = match deserialize(BytesMut::from(&res[..])) {
+                    Ok((res, _)) => ConsensusClientResponse::Result(res),
+                    Err(_) => {
+                        ConsensusClientResponse::Err("ERR malformed reply from leader".into())
+                    },
+                };
+                let _ = callback.send(res);
+            },
+            (ForwardCallback::Write(callback), ForwardedResponse::Moved(leader)) => {
+                let _ = callback.send(ConsensusClientResponse::Moved(leader));
+            },
+            (ForwardCallback::ReadIndex(callback), ForwardedResponse::ReadIndex(read_index)) => {
+                let _ = callback.send(Ok(Some(read_index)));
+            },
+            (callback, ForwardedResponse::Err(err)) => callback.fail(err),
+            (callback, _) => callback.fail("ERR unexpected reply from leader"),
+        }
+    }
+}
+
+impl ForwardTracker {
+    /// Returns the sequence number the request has to be sent with.
+    pub(crate) fn add(&mut self, callback: ForwardCallback, timeout: Duration) -> u64 {
+        self.last_seq += 1;
+        self.pending
+            .insert(self.last_seq, PendingForward { callback, deadline: Instant::now() + timeout });
+        self.last_seq
+    }
+
+    pub(crate) fn resolve(&mut self, reply: ForwardReply) {
+        if let Some(pending) = self.pending.remove(&reply.seq) {
+            pending.callback.resolve(reply.response);
+        }
+    }
+
+    pub(crate) fn fail(&mut self, seq: u64, err: &str) {
+        if let Some(pending) = self.pending.remove(&seq) {
+            pending.callback.fail(err);
+        }
+    }
+
+    /// Fails the requests the leader didn't answer in time, as when it crashed meanwhile.
+    /// A forwarded write may still get committed after it was reported as failed.
+    pub(crate) fn expire(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u64> =
+            self.pending.iter().filter(|(_, p)| now >= p.deadline).map(|(seq, _)| *seq).collect();
+        for seq in expired {
+            self.fail(seq, "ERR forwarded request timed out");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domains::query_parsers::QueryIO;
+
+    #[tokio::test]
+    async fn test_reply_resolves_the_request_it_answers() {
+        // GIVEN
+        let mut tracker = ForwardTracker::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        let seq = tracker.add(ForwardCallback::Write(tx1), Duration::from_secs(60));
+        tracker.add(ForwardCallback::ReadIndex(tx2), Duration::from_secs(60));
+
+        // WHEN
+        let written = QueryIO::SimpleString("s:bar|idx:3".into()).serialize().to_vec();
+        tracker.resolve(ForwardReply { seq, response: ForwardedResponse::Written(written) });
+
+        // THEN
+        let ConsensusClientResponse::Result(res) = rx1.await.unwrap() else {
+            panic!("Expected the forwarded write to succeed");
+        };
+        assert_eq!(res, QueryIO::SimpleString("s:bar|idx:3".into()));
+        assert!(rx2.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expire_fails_only_overdue_requests() {
+        // GIVEN
+        let mut tracker = ForwardTracker::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        tracker.add(ForwardCallback::ReadIndex(tx1), Duration::ZERO);
+        tracker.add(ForwardCallback::ReadIndex(tx2), Duration::from_secs(60));
+
+        // WHEN
+        tracker.expire();
+
+        // THEN
+        assert!(rx1.await.unwrap().is_err());
+        assert!(rx2.try_recv().is_err());
+        assert_eq!(tracker.pending.len(), 1);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::domains::cluster_actors::heartbeats::scheduler::ELECTION_TIMEOUT_MIN;
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Upper bound on how much faster a follower's clock may run than the leader's, over one election timeout.
+const MAX_CLOCK_DRIFT: Duration = Duration::from_millis(ELECTION_TIMEOUT_MIN / 10);
+
+/// Time during which the leader may serve reads locally after a quorum acknowledged it.
+/// Followers don't start an election before an election timeout has passed, so no other leader can exist meanwhile.
+/// The lease runs from when the acknowledged heartbeat was sent, as the follower may have reset its timer any time after.
+#[derive(Debug)]
+pub(crate) struct LeaderLease {
+    duration: Duration,
+    // when each heartbeat round that may renew the lease was sent, by its read sequence number
+    sent: BTreeMap<u64, Instant>,
+    acks: HashMap<PeerIdentifier, Instant>,
+}
+
+impl Default for LeaderLease {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(ELECTION_TIMEOUT_MIN) - MAX_CLOCK_DRIFT)
+    }
+}
+
+impl LeaderLease {
+    pub(crate) fn new(duration: Duration) -> Self {
+        Self { duration, sent: BTreeMap::new(), acks: HashMap::new() }
+    }
+
+    /// Remembers when the heartbeat of round `seq` was sent.
+    pub(crate) fn record_sent(&mut self, seq: u64) {
+        // acknowledgements of rounds sent longer than a lease ago could not renew it anymore
+        self.sent.retain(|_, sent_at| sent_at.elapsed() < self.duration);
+        self.sent.insert(seq, Instant::now());
+    }
+
+    /// Records that `from` acknowledged the heartbeat of round `seq`, renewing the lease from when it was sent.
+    pub(crate) fn record(&mut self, from: &PeerIdentifier, seq: u64) {
+        let Some(&sent_at) = self.sent.get(&seq) else {
+            return;
+        };
+        let acked_at = self.acks.entry(from.clone()).or_insert(sent_at);
+        *acked_at = (*acked_at).max(sent_at);
+    }
+
+    /// Whether at least `required_acks` of `voters` acknowledged the leader within the lease duration.
+    pub(crate) fn is_valid(&self, voters: &BTreeSet<PeerIdentifier>, required_acks: usize) -> bool {
+        let fresh = voters
+            .iter()
+            .filter_map(|voter| self.acks.get(voter))
+            .filter(|acked_at| acked_at.elapsed() < self.duration)
+            .count();
+        fresh >= required_acks
+    }
+
+    /// Forgets every acknowledgement, once the leadership they were given to is over.
+    /// Rounds already sent can't renew the lease anymore either.
+    pub(crate) fn revoke(&mut self) {
+        self.sent.clear();
+        self.acks.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn voters() -> BTreeSet<PeerIdentifier> {
+        (6380..6383).map(|port| PeerIdentifier::new("127.0.0.1", port)).collect()
+    }
+
+    #[test]
+    fn test_lease_held_while_quorum_acks_are_fresh() {
+        // GIVEN
+        let mut lease = LeaderLease::new(Duration::from_secs(10));
+        assert!(!lease.is_valid(&voters(), 1));
+        lease.record_sent(1);
+
+        // WHEN
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 1);
+        lease.record(&PeerIdentifier::new("127.0.0.1", 9999), 1);
+
+        // THEN - acks from outside the configuration don't count
+        assert!(lease.is_valid(&voters(), 1));
+        assert!(!lease.is_valid(&voters(), 2));
+    }
+
+    #[tokio::test]
+    async fn test_lease_lapses_after_duration() {
+        let mut lease = LeaderLease::new(Duration::from_millis(10));
+        lease.record_sent(1);
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 1);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(!lease.is_valid(&voters(), 1));
+    }
+
+    #[tokio::test]
+    async fn test_lease_runs_from_when_acknowledged_round_was_sent() {
+        // GIVEN
+        let mut lease = LeaderLease::new(Duration::from_millis(50));
+        lease.record_sent(1);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // WHEN - the ack of a round sent a lease ago arrives just now
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 1);
+
+        // THEN
+        assert!(!lease.is_valid(&voters(), 1));
+
+        // WHEN - a round nobody was sent doesn't renew it either
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 2);
+
+        // THEN
+        assert!(!lease.is_valid(&voters(), 1));
+    }
+
+    #[test]
+    fn test_revoked_lease_is_invalid() {
+        let mut lease = LeaderLease::new(Duration::from_secs(10));
+        lease.record_sent(1);
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 1);
+
+        lease.revoke();
+
+        assert!(!lease.is_valid(&voters(), 1));
+        assert!(lease.is_valid(&voters(), 0));
+        // - acks of rounds sent before the revocation no longer count
+        lease.record(&PeerIdentifier::new("127.0.0.1", 6381), 1);
+        assert!(!lease.is_valid(&voters(), 1));
+    }
+}
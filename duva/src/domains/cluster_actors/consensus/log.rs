@@ -1,57 +1,75 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::{
-    domains::{
-        cluster_actors::{commands::ConsensusClientResponse, session::SessionRequest},
-        peers::identifier::PeerIdentifier,
-    },
+    domains::cluster_actors::{commands::ConsensusClientResponse, session::SessionRequest},
     make_smart_pointer,
 };
 use tokio::sync::oneshot::Sender;
-pub(crate) type ReplicationVote = Sender<ConsensusClientResponse>;
+use tokio::time::Instant;
+pub(crate) type ConsensusCallback = Sender<ConsensusClientResponse>;
 
+/// Writes waiting for their log entry to be committed, by log index.
 #[derive(Default, Debug)]
-pub struct LogConsensusTracker(pub(crate) HashMap<u64, LogConsensusVoting>);
+pub struct LogConsensusTracker(pub(crate) HashMap<u64, PendingConsensus>);
 impl LogConsensusTracker {
     pub(crate) fn add(
         &mut self,
         key: u64,
         callback: Sender<ConsensusClientResponse>,
-        replica_count: usize,
         session_req: Option<SessionRequest>,
+        timeout: Duration,
     ) {
-        self.insert(key, LogConsensusVoting::new(callback, replica_count, session_req));
+        let deadline = Instant::now() + timeout;
+        self.insert(key, PendingConsensus { callback, session_req, deadline });
+    }
+
+    /// Fails the writes that didn't reach consensus before their deadline and stops tracking them.
+    /// Returns how many writes timed out.
+    pub(crate) fn expire(&mut self) -> u64 {
+        let now = Instant::now();
+        let expired: Vec<u64> =
+            self.iter().filter(|(_, v)| now >= v.deadline).map(|(idx, _)| *idx).collect();
+        for idx in expired.iter() {
+            if let Some(pending) = self.remove(idx) {
+                let _ = pending.callback.send(ConsensusClientResponse::Err(
+                    "ERR write timed out; outcome unknown".into(),
+                ));
+            }
+        }
+        expired.len() as u64
     }
 }
-make_smart_pointer!(LogConsensusTracker, HashMap<u64, LogConsensusVoting>);
+make_smart_pointer!(LogConsensusTracker, HashMap<u64, PendingConsensus>);
 
 #[derive(Debug)]
-pub struct LogConsensusVoting {
-    pub(crate) voters: Vec<PeerIdentifier>,
-    pub(crate) callback: ReplicationVote,
-    pub(crate) cnt: u8,
+pub struct PendingConsensus {
+    pub(crate) callback: ConsensusCallback,
     pub(crate) session_req: Option<SessionRequest>,
+    pub(crate) deadline: Instant,
 }
-impl LogConsensusVoting {
-    fn new(
-        callback: ReplicationVote,
-        replica_count: usize,
-        session_req: Option<SessionRequest>,
-    ) -> Self {
-        Self { callback, cnt: 0, voters: Vec::with_capacity(replica_count), session_req }
-    }
 
-    pub(crate) fn increase_vote(&mut self, voter: PeerIdentifier) {
-        self.cnt += 1;
-        self.voters.push(voter);
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    pub(crate) fn get_required_votes(&self) -> u8 {
-        let replica_count = self.voters.capacity() as u8;
-        (replica_count + 1).div_ceil(2)
-    }
+    #[tokio::test]
+    async fn test_expire_fails_only_overdue_writes() {
+        // GIVEN
+        let mut tracker = LogConsensusTracker::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        tracker.add(1, tx1, None, Duration::ZERO);
+        tracker.add(2, tx2, None, Duration::from_secs(60));
+
+        // WHEN
+        let timed_out = tracker.expire();
 
-    pub(crate) fn votable(&self, voter: &PeerIdentifier) -> bool {
-        !self.voters.iter().any(|v| v == voter)
+        // THEN
+        assert_eq!(timed_out, 1);
+        assert!(matches!(rx1.await.unwrap(), ConsensusClientResponse::Err(_)));
+        assert!(rx2.try_recv().is_err());
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.get(&2).is_some());
     }
 }
@@ -0,0 +1,189 @@
+use crate::domains::operation_logs::{WriteOperation, WriteRequest};
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Voting configuration of the cluster.
+/// It changes one server at a time through `WriteRequest::Configuration` entries,
+/// and quorum is always computed from the last committed one.
+/// Replicas outside of it are learners: they receive entries but neither vote nor count towards quorum.
+#[derive(Debug, Default)]
+pub(crate) struct Membership {
+    voters: BTreeSet<PeerIdentifier>,
+    commit_index: u64,
+    // configurations appended to the log but not committed yet, by log index
+    pending: BTreeMap<u64, BTreeSet<PeerIdentifier>>,
+}
+
+impl Membership {
+    pub(crate) fn new(voters: impl IntoIterator<Item = PeerIdentifier>, commit_index: u64) -> Self {
+        Self { voters: voters.into_iter().collect(), commit_index, pending: BTreeMap::new() }
+    }
+
+    pub(crate) fn voters(&self) -> &BTreeSet<PeerIdentifier> {
+        &self.voters
+    }
+
+    pub(crate) fn is_voter(&self, peer_id: &PeerIdentifier) -> bool {
+        self.voters.contains(peer_id)
+    }
+
+    /// The most recent configuration in the log, committed or not.
+    pub(crate) fn latest(&self) -> &BTreeSet<PeerIdentifier> {
+        self.pending.values().next_back().unwrap_or(&self.voters)
+    }
+
+    pub(crate) fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub(crate) fn record(&mut self, entries: &[WriteOperation]) {
+        for op in entries.iter().filter(|op| op.log_index > self.commit_index) {
+            if let WriteRequest::Configuration { voters } = &op.request {
+                self.pending.insert(op.log_index, voters.iter().cloned().collect());
+            }
+        }
+    }
+
+    pub(crate) fn truncate_after(&mut self, log_index: u64) {
+        self.pending.split_off(&(log_index + 1));
+    }
+
+    /// Makes the latest configuration at or below `commit_index` effective.
+    pub(crate) fn commit(&mut self, commit_index: u64) {
+        if commit_index <= self.commit_index {
+            return;
+        }
+        self.commit_index = commit_index;
+
+        let uncommitted = self.pending.split_off(&(commit_index + 1));
+        let committed = std::mem::replace(&mut self.pending, uncommitted);
+        if let Some((log_index, voters)) = committed.into_iter().next_back() {
+            println!("[INFO] Configuration at log index {} committed: {:?}", log_index, voters);
+            self.voters = voters;
+        }
+    }
+
+    /// Acknowledgements the leader needs from other voters to commit an entry.
+    /// Without a committed configuration, the leader is the only voter.
+    pub(crate) fn required_acks(&self, leader: &PeerIdentifier) -> usize {
+        if self.voters.is_empty() {
+            return 0;
+        }
+        let majority = self.voters.len() / 2 + 1;
+        majority - usize::from(self.is_voter(leader))
+    }
+
+    /// Highest log index a majority of voters has replicated, given how far each of them got.
+    /// Without a committed configuration there is no majority to ask.
+    pub(crate) fn quorum_index(&self, match_index: impl Fn(&PeerIdentifier) -> u64) -> Option<u64> {
+        if self.voters.is_empty() {
+            return None;
+        }
+        let mut indexes: Vec<u64> = self.voters.iter().map(match_index).collect();
+        indexes.sort_unstable_by(|a, b| b.cmp(a));
+        Some(indexes[self.voters.len() / 2])
+    }
+
+    /// Number of voters other than `node`.
+    pub(crate) fn peer_voter_count(&self, node: &PeerIdentifier) -> u8 {
+        self.voters.iter().filter(|voter| *voter != node).count() as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config_entry(log_index: u64, voters: &[&str]) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Configuration {
+                voters: voters.iter().map(|v| PeerIdentifier(v.to_string())).collect(),
+            },
+            log_index,
+            term: 1,
+        }
+    }
+
+    #[test]
+    fn test_configuration_takes_effect_only_once_committed() {
+        // GIVEN
+        let mut membership = Membership::new([PeerIdentifier("a".into())], 0);
+
+        // WHEN
+        membership.record(&[config_entry(1, &["a", "b"]), config_entry(2, &["a", "b", "c"])]);
+
+        // THEN
+        assert_eq!(membership.voters().len(), 1);
+        assert_eq!(membership.latest().len(), 3);
+
+        membership.commit(1);
+        assert_eq!(membership.voters().len(), 2);
+        assert!(membership.has_pending());
+
+        membership.commit(2);
+        assert_eq!(membership.voters().len(), 3);
+        assert!(!membership.has_pending());
+    }
+
+    #[test]
+    fn test_truncated_configuration_is_dropped() {
+        // GIVEN
+        let mut membership = Membership::new([PeerIdentifier("a".into())], 0);
+        membership.record(&[config_entry(3, &["a", "b"])]);
+
+        // WHEN
+        membership.truncate_after(2);
+        membership.commit(3);
+
+        // THEN
+        assert_eq!(membership.voters().len(), 1);
+    }
+
+    #[test]
+    fn test_entries_at_or_below_commit_index_are_not_recorded_again() {
+        // GIVEN
+        let mut membership = Membership::new([PeerIdentifier("a".into())], 5);
+
+        // WHEN
+        membership.record(&[config_entry(4, &["a", "b"])]);
+
+        // THEN
+        assert!(!membership.has_pending());
+    }
+
+    #[test]
+    fn test_required_acks() {
+        let leader = PeerIdentifier("a".into());
+        let membership = |voters: &[&str]| {
+            Membership::new(voters.iter().map(|v| PeerIdentifier(v.to_string())), 0)
+        };
+
+        assert_eq!(membership(&[]).required_acks(&leader), 0);
+        assert_eq!(membership(&["a"]).required_acks(&leader), 0);
+        assert_eq!(membership(&["a", "b"]).required_acks(&leader), 1);
+        assert_eq!(membership(&["a", "b", "c"]).required_acks(&leader), 1);
+        assert_eq!(membership(&["a", "b", "c", "d"]).required_acks(&leader), 2);
+        assert_eq!(membership(&["a", "b", "c", "d", "e"]).required_acks(&leader), 2);
+        // a leader removed from the configuration doesn't count itself
+        assert_eq!(membership(&["b", "c", "d"]).required_acks(&leader), 2);
+    }
+
+    #[test]
+    fn test_quorum_index_is_what_a_majority_replicated() {
+        let membership = |voters: &[&str]| {
+            Membership::new(voters.iter().map(|v| PeerIdentifier(v.to_string())), 0)
+        };
+        let match_index = |voter: &PeerIdentifier| match voter.0.as_str() {
+            "a" => 7,
+            "b" => 5,
+            "c" => 3,
+            _ => 0,
+        };
+
+        assert_eq!(membership(&[]).quorum_index(match_index), None);
+        assert_eq!(membership(&["a"]).quorum_index(match_index), Some(7));
+        assert_eq!(membership(&["a", "b"]).quorum_index(match_index), Some(5));
+        assert_eq!(membership(&["a", "b", "c"]).quorum_index(match_index), Some(5));
+        assert_eq!(membership(&["a", "b", "c", "d"]).quorum_index(match_index), Some(3));
+    }
+}
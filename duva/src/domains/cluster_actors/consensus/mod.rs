@@ -1,4 +1,16 @@
 mod log;
 pub(crate) use log::LogConsensusTracker;
 mod election;
-pub(crate) use election::ElectionState;
+pub(crate) use election::{ElectionState, ElectionVoting};
+mod forward;
+pub(crate) use forward::{ForwardCallback, ForwardTracker};
+mod lease;
+pub(crate) use lease::LeaderLease;
+mod membership;
+pub(crate) use membership::Membership;
+mod pipeline;
+pub(crate) use pipeline::{InflightWindow, MAX_BATCH_ENTRIES, take_batch};
+mod read_index;
+pub(crate) use read_index::{ReadIndexCallback, ReadIndexTracker};
+mod transfer;
+pub(crate) use transfer::LeadershipTransfer;
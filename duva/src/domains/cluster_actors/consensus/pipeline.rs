@@ -0,0 +1,180 @@
+use crate::domains::operation_logs::WriteOperation;
+use crate::domains::query_parsers::query_io::SERDE_CONFIG;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Limits on a single AppendEntries, so a lagging follower catches up in several round trips
+pub(crate) const MAX_BATCH_ENTRIES: u64 = 128;
+pub(crate) const MAX_BATCH_BYTES: usize = 1024 * 1024;
+// AppendEntries a follower may have unacknowledged before the leader stops sending to it
+pub(crate) const MAX_INFLIGHT_BATCHES: usize = 4;
+
+/// AppendEntries sent to a follower that it hasn't acknowledged yet.
+/// While the window is full, new entries wait in the log and go out together once acks free it up.
+#[derive(Debug, Default)]
+pub(crate) struct InflightWindow {
+    // highest index sent so far. The next batch starts right after it
+    sent_up_to: u64,
+    // last index of every unacknowledged batch, with when it was sent
+    batches: VecDeque<(u64, Instant)>,
+    // conflicting term and last index of the follower's log the window was last rewound for
+    rewound_for: Option<(Option<u64>, u64)>,
+}
+
+impl InflightWindow {
+    pub(crate) fn new(match_index: u64) -> Self {
+        Self { sent_up_to: match_index, batches: VecDeque::new(), rewound_for: None }
+    }
+
+    pub(crate) fn sent_up_to(&self) -> u64 {
+        self.sent_up_to
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.batches.len() >= MAX_INFLIGHT_BATCHES
+    }
+
+    pub(crate) fn record(&mut self, last_index: u64) {
+        self.sent_up_to = last_index;
+        self.batches.push_back((last_index, Instant::now()));
+    }
+
+    /// Frees the batches the follower has stored everything of.
+    pub(crate) fn ack(&mut self, match_index: u64) {
+        while self.batches.front().is_some_and(|(last_index, _)| *last_index <= match_index) {
+            self.batches.pop_front();
+        }
+        self.sent_up_to = self.sent_up_to.max(match_index);
+        self.rewound_for = None;
+    }
+
+    /// Forgets what was sent, so replication resumes right after `match_index`.
+    pub(crate) fn reset(&mut self, match_index: u64) {
+        self.batches.clear();
+        self.sent_up_to = match_index;
+        self.rewound_for = None;
+    }
+
+    /// Whether the window was already rewound for the same rejection, as when it comes from a batch sent before.
+    pub(crate) fn rewound_for(&self, conflict_term: Option<u64>, follower_last_index: u64) -> bool {
+        self.rewound_for == Some((conflict_term, follower_last_index))
+    }
+
+    /// Remembers the rejection the window was just rewound for, until the follower acknowledges entries again.
+    pub(crate) fn mark_rewound(&mut self, conflict_term: Option<u64>, follower_last_index: u64) {
+        self.rewound_for = Some((conflict_term, follower_last_index));
+    }
+
+    /// Whether the oldest batch went unacknowledged for longer than `timeout`, as when it got lost.
+    pub(crate) fn is_stalled(&self, timeout: Duration) -> bool {
+        self.batches.front().is_some_and(|(_, sent_at)| sent_at.elapsed() >= timeout)
+    }
+}
+
+/// Keeps the leading entries that fit in one AppendEntries. The first one is always kept.
+pub(crate) fn take_batch(entries: Vec<WriteOperation>) -> Vec<WriteOperation> {
+    let mut bytes = 0;
+    let mut batch = Vec::with_capacity(entries.len());
+    for op in entries.into_iter().take(MAX_BATCH_ENTRIES as usize) {
+        bytes += bincode::encode_to_vec(&op, SERDE_CONFIG).map_or(0, |encoded| encoded.len());
+        if bytes > MAX_BATCH_BYTES && !batch.is_empty() {
+            break;
+        }
+        batch.push(op);
+    }
+    batch
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domains::operation_logs::WriteRequest;
+
+    fn op(log_index: u64, value: String) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Set { key: "key".into(), value },
+            log_index,
+            term: 1,
+        }
+    }
+
+    #[test]
+    fn test_window_frees_batches_as_they_are_acknowledged() {
+        // GIVEN
+        let mut window = InflightWindow::new(0);
+        for last_index in 1..=MAX_INFLIGHT_BATCHES as u64 {
+            window.record(last_index * 10);
+        }
+        assert!(window.is_full());
+
+        // WHEN
+        window.ack(25);
+
+        // THEN
+        assert!(!window.is_full());
+        assert_eq!(window.batches.len(), MAX_INFLIGHT_BATCHES - 2);
+        assert_eq!(window.sent_up_to(), MAX_INFLIGHT_BATCHES as u64 * 10);
+    }
+
+    #[test]
+    fn test_window_reset_resends_after_match_index() {
+        // GIVEN
+        let mut window = InflightWindow::new(0);
+        window.record(10);
+        window.record(20);
+
+        // WHEN
+        window.reset(5);
+
+        // THEN
+        assert_eq!(window.sent_up_to(), 5);
+        assert!(!window.is_stalled(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_window_is_rewound_once_per_conflict() {
+        // GIVEN
+        let mut window = InflightWindow::new(0);
+        window.record(10);
+        window.record(20);
+        window.reset(5);
+        window.mark_rewound(Some(2), 8);
+
+        // WHEN - the batch sent after the rewind is acknowledged
+        let rejected_again = window.rewound_for(Some(2), 8);
+        window.ack(8);
+
+        // THEN
+        assert!(rejected_again);
+        assert!(!window.rewound_for(Some(3), 8));
+        assert!(!window.rewound_for(Some(2), 8));
+    }
+
+    #[test]
+    fn test_take_batch_caps_entry_count() {
+        // GIVEN
+        let entries = (1..=MAX_BATCH_ENTRIES + 10).map(|i| op(i, "v".into())).collect();
+
+        // WHEN
+        let batch = take_batch(entries);
+
+        // THEN
+        assert_eq!(batch.len(), MAX_BATCH_ENTRIES as usize);
+        assert_eq!(batch.last().unwrap().log_index, MAX_BATCH_ENTRIES);
+    }
+
+    #[test]
+    fn test_take_batch_caps_byte_size_but_keeps_first_entry() {
+        // GIVEN
+        let large = "v".repeat(MAX_BATCH_BYTES);
+        let entries = vec![op(1, large.clone()), op(2, large)];
+
+        // WHEN
+        let batch = take_batch(entries);
+
+        // THEN
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].log_index, 1);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use tokio::sync::oneshot::Sender;
+use tokio::time::Instant;
+
+pub(crate) type ReadIndexCallback = Sender<anyhow::Result<Option<u64>>>;
+
+/// Reads waiting for a quorum to confirm that this node still leads.
+/// Each round is checked by one heartbeat carrying its sequence number, which followers echo back.
+#[derive(Debug, Default)]
+pub(crate) struct ReadIndexTracker {
+    last_seq: u64,
+    rounds: BTreeMap<u64, ReadRound>,
+}
+
+#[derive(Debug)]
+struct ReadRound {
+    read_index: u64,
+    required_acks: usize,
+    acks: HashSet<PeerIdentifier>,
+    deadline: Instant,
+    callback: ReadIndexCallback,
+}
+
+impl ReadIndexTracker {
+    /// Starts a round for a read at `read_index`, answering right away when no other voter has to confirm.
+    /// Returns the sequence number the confirming heartbeat has to carry.
+    pub(crate) fn start(
+        &mut self,
+        read_index: u64,
+        required_acks: usize,
+        callback: ReadIndexCallback,
+        timeout: Duration,
+    ) -> Option<u64> {
+        if required_acks == 0 {
+            let _ = callback.send(Ok(Some(read_index)));
+            return None;
+        }
+
+        self.last_seq += 1;
+        self.rounds.insert(
+            self.last_seq,
+            ReadRound {
+                read_index,
+                required_acks,
+                acks: HashSet::new(),
+                deadline: Instant::now() + timeout,
+                callback,
+            },
+        );
+        Some(self.last_seq)
+    }
+
+    /// Sequence number of the latest round still waiting for confirmation.
+    #[cfg(test)]
+    pub(crate) fn pending_seq(&self) -> Option<u64> {
+        self.rounds.keys().next_back().copied()
+    }
+
+    /// Sequence number for a heartbeat sent without a read waiting on it.
+    /// Its acknowledgements confirm every round started before it.
+    pub(crate) fn probe_seq(&mut self) -> u64 {
+        self.last_seq += 1;
+        self.last_seq
+    }
+
+    /// Records that `from` acknowledged the heartbeat of round `seq`.
+    /// That also confirms every earlier round, as their heartbeats were sent before.
+    pub(crate) fn ack(&mut self, seq: u64, from: &PeerIdentifier) {
+        let confirmed: Vec<u64> = self
+            .rounds
+            .range_mut(..=seq)
+            .filter_map(|(seq, round)| {
+                round.acks.insert(from.clone());
+                (round.acks.len() >= round.required_acks).then_some(*seq)
+            })
+            .collect();
+
+        for seq in confirmed {
+            if let Some(round) = self.rounds.remove(&seq) {
+                let _ = round.callback.send(Ok(Some(round.read_index)));
+            }
+        }
+    }
+
+    /// Fails the rounds that were not confirmed in time.
+    pub(crate) fn expire(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u64> =
+            self.rounds.iter().filter(|(_, r)| now >= r.deadline).map(|(seq, _)| *seq).collect();
+        for seq in expired {
+            if let Some(round) = self.rounds.remove(&seq) {
+                let _ = round.callback.send(Err(anyhow::anyhow!("ERR read index timed out")));
+            }
+        }
+    }
+
+    /// Fails every pending round, once this node stopped leading.
+    pub(crate) fn fail_all(&mut self) {
+        for (_, round) in std::mem::take(&mut self.rounds) {
+            let _ = round.callback.send(Err(anyhow::anyhow!("ERR not a leader")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[tokio::test]
+    async fn test_read_answered_once_quorum_confirms() {
+        // GIVEN
+        let mut tracker = ReadIndexTracker::default();
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        let seq = tracker.start(3, 2, tx, TIMEOUT).unwrap();
+
+        // WHEN
+        tracker.ack(seq, &PeerIdentifier::new("127.0.0.1", 6001));
+        tracker.ack(seq, &PeerIdentifier::new("127.0.0.1", 6001));
+
+        // THEN - the same voter can't confirm twice
+        assert!(rx.try_recv().is_err());
+
+        tracker.ack(seq, &PeerIdentifier::new("127.0.0.1", 6002));
+        assert_eq!(rx.await.unwrap().unwrap(), Some(3));
+        assert_eq!(tracker.pending_seq(), None);
+    }
+
+    #[tokio::test]
+    async fn test_ack_of_later_round_confirms_earlier_ones() {
+        // GIVEN
+        let mut tracker = ReadIndexTracker::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, rx2) = tokio::sync::oneshot::channel();
+        tracker.start(1, 1, tx1, TIMEOUT);
+        let seq = tracker.start(2, 1, tx2, TIMEOUT).unwrap();
+
+        // WHEN
+        tracker.ack(seq, &PeerIdentifier::new("127.0.0.1", 6001));
+
+        // THEN
+        assert_eq!(rx1.await.unwrap().unwrap(), Some(1));
+        assert_eq!(rx2.await.unwrap().unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_read_answered_right_away_without_other_voters() {
+        let mut tracker = ReadIndexTracker::default();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        assert_eq!(tracker.start(5, 0, tx, TIMEOUT), None);
+        assert_eq!(rx.await.unwrap().unwrap(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_pending_reads_fail_when_deposed() {
+        let mut tracker = ReadIndexTracker::default();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tracker.start(5, 1, tx, TIMEOUT);
+
+        tracker.fail_all();
+
+        assert_eq!(rx.await.unwrap().unwrap_err().to_string(), "ERR not a leader");
+    }
+}
@@ -0,0 +1,30 @@
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Leadership hand-off in progress. The leader refuses writes until it completes or expires.
+#[derive(Debug)]
+pub(crate) struct LeadershipTransfer {
+    pub(crate) target: PeerIdentifier,
+    pub(crate) timeout_now_sent: bool,
+    deadline: Instant,
+    callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+}
+
+impl LeadershipTransfer {
+    pub(crate) fn new(
+        target: PeerIdentifier,
+        callback: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
+        timeout: Duration,
+    ) -> Self {
+        Self { target, timeout_now_sent: false, deadline: Instant::now() + timeout, callback }
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub(crate) fn complete(self, res: anyhow::Result<()>) {
+        let _ = self.callback.send(res);
+    }
+}
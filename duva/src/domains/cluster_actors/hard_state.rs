@@ -0,0 +1,119 @@
+use crate::domains::peers::identifier::PeerIdentifier;
+use crate::domains::query_parsers::query_io::SERDE_CONFIG;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+/// Raft state that has to survive a restart.
+/// Losing `term` or `voted_for` would let a restarted node vote twice in the same term.
+#[derive(Debug, Default, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) struct HardState {
+    pub(crate) term: u64,
+    pub(crate) voted_for: Option<PeerIdentifier>,
+    pub(crate) commit_index: u64,
+    pub(crate) voters: Vec<PeerIdentifier>,
+    // Where the snapshot on disk ends, so the log can resume from it after its entries are dropped
+    pub(crate) last_included_index: u64,
+    pub(crate) last_included_term: u64,
+}
+
+/// Durable storage for [`HardState`], kept in a small file next to the WAL.
+/// Without a path, the state lives in memory only.
+#[derive(Debug, Default)]
+pub struct HardStateStore {
+    path: Option<PathBuf>,
+    state: HardState,
+}
+
+impl HardStateStore {
+    /// Loads the hard state stored at `path`, starting from the default state if the file does not exist yet.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state = match tokio::fs::read(&path).await {
+            Ok(bytes) => bincode::decode_from_slice(&bytes, SERDE_CONFIG)?.0,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HardState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path: Some(path), state })
+    }
+
+    pub(crate) fn state(&self) -> &HardState {
+        &self.state
+    }
+
+    /// Durably records `state` before returning.
+    pub(crate) async fn save(&mut self, state: HardState) -> Result<()> {
+        if state == self.state {
+            return Ok(());
+        }
+
+        if let Some(path) = &self.path {
+            replace_file(path, &bincode::encode_to_vec(&state, SERDE_CONFIG)?).await?;
+        }
+
+        self.state = state;
+        Ok(())
+    }
+}
+
+/// Durably replaces the contents of `path` with `bytes`.
+/// The file is replaced through a rename so a crash never leaves a partially written file behind,
+/// and the directory is synced afterwards so the rename itself survives a crash.
+pub(crate) async fn replace_file(path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+
+    let mut file = File::create(&tmp_path).await?;
+    file.write_all(bytes).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    sync_parent_dir(path).await
+}
+
+async fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir).await?.sync_all().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_open_defaults_when_file_missing() -> Result<()> {
+        let dir = TempDir::new()?;
+        let store = HardStateStore::open(dir.path().join("dump.rdb.state")).await?;
+        assert_eq!(store.state(), &HardState::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_saved_state_survives_reopen() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("dump.rdb.state");
+
+        let mut store = HardStateStore::open(&path).await?;
+        let state = HardState {
+            term: 3,
+            voted_for: Some(PeerIdentifier::new("127.0.0.1", 6380)),
+            commit_index: 7,
+            voters: vec![PeerIdentifier::new("127.0.0.1", 6380)],
+            last_included_index: 5,
+            last_included_term: 2,
+        };
+        store.save(state.clone()).await?;
+        drop(store);
+
+        let store = HardStateStore::open(&path).await?;
+        assert_eq!(store.state(), &state);
+        assert!(!dir.path().join("dump.rdb.state.tmp").exists());
+        Ok(())
+    }
+}
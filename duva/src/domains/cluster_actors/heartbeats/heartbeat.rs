@@ -17,6 +17,7 @@ pub(crate) struct HeartBeatMessage {
     pub(crate) cluster_nodes: Vec<ClusterNode>,
     pub(crate) prev_log_index: u64, //index of log entry immediately preceding new ones
     pub(crate) prev_log_term: u64,  //term of prev_log_index entry
+    pub(crate) read_seq: u64,       //read index round to confirm, 0 if none
 }
 impl HeartBeatMessage {
     pub(crate) fn set_append_entries(mut self, entries: Vec<WriteOperation>) -> Self {
@@ -3,8 +3,9 @@ use std::{ops::Range, time::Duration};
 use tokio::{select, sync::mpsc::Sender, time::interval};
 const LEADER_HEARTBEAT_INTERVAL: u64 = 300;
 pub const LEADER_HEARTBEAT_INTERVAL_MAX: u64 = LEADER_HEARTBEAT_INTERVAL * 5;
+pub(crate) const ELECTION_TIMEOUT_MIN: u64 = LEADER_HEARTBEAT_INTERVAL * 3;
 const LEADER_HEARTBEAT_INTERVAL_RANGE: Range<u64> =
-    LEADER_HEARTBEAT_INTERVAL * 3..LEADER_HEARTBEAT_INTERVAL_MAX;
+    ELECTION_TIMEOUT_MIN..LEADER_HEARTBEAT_INTERVAL_MAX;
 
 #[derive(Debug)]
 pub(crate) struct HeartBeatScheduler {
@@ -163,9 +164,9 @@ mod tests {
         let (scheduler, _) = setup_scheduler(true).await;
 
         match scheduler.controller {
-            Some(SchedulerMode::Leader(_)) => assert!(true),
-            Some(SchedulerMode::Follower(_)) => assert!(false, "Expected Leader mode"),
-            None => assert!(false, "Expected Leader mode"),
+            Some(SchedulerMode::Leader(_)) => {},
+            Some(SchedulerMode::Follower(_)) => panic!("Expected Leader mode"),
+            None => panic!("Expected Leader mode"),
         }
     }
 
@@ -174,8 +175,8 @@ mod tests {
         let (scheduler, _) = setup_scheduler(false).await;
 
         match scheduler.controller {
-            Some(SchedulerMode::Follower(_)) => assert!(true),
-            Some(SchedulerMode::Leader(_)) => assert!(false, "Expected Follower mode"),
+            Some(SchedulerMode::Follower(_)) => {},
+            Some(SchedulerMode::Leader(_)) => panic!("Expected Follower mode"),
             None => todo!(),
         }
     }
@@ -95,6 +95,36 @@ impl PeerListener {
                             .send(ClusterCommand::ApplyElectionVote(reply))
                             .await;
                     },
+                    PeerListenerCommand::PreVote(pre_vote) => {
+                        let _ = self
+                            .cluster_handler
+                            .send(ClusterCommand::PreVoteElection(pre_vote.0))
+                            .await;
+                    },
+                    PeerListenerCommand::PreVoteReply(reply) => {
+                        let _ =
+                            self.cluster_handler.send(ClusterCommand::ApplyPreVote(reply.0)).await;
+                    },
+                    PeerListenerCommand::InstallSnapshot(chunk) => {
+                        let _ =
+                            self.cluster_handler.send(ClusterCommand::InstallSnapshot(chunk)).await;
+                    },
+                    PeerListenerCommand::TimeoutNow(timeout_now) => {
+                        let _ = self
+                            .cluster_handler
+                            .send(ClusterCommand::TimeoutNow(timeout_now))
+                            .await;
+                    },
+                    PeerListenerCommand::ForwardRequest(request) => {
+                        let _ = self
+                            .cluster_handler
+                            .send(ClusterCommand::ForwardRequest(request))
+                            .await;
+                    },
+                    PeerListenerCommand::ForwardReply(reply) => {
+                        let _ =
+                            self.cluster_handler.send(ClusterCommand::ForwardReply(reply)).await;
+                    },
                 }
             }
         }
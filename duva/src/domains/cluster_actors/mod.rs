@@ -9,6 +9,7 @@ use tokio::time::Instant;
 pub mod actor;
 pub mod commands;
 pub mod consensus;
+pub mod hard_state;
 pub mod heartbeats;
 mod listener;
 pub(crate) mod peer_connections;
@@ -158,12 +158,10 @@ impl InboundStream {
 
         if let PeerState::Replica { .. } =
             connected_info.decide_peer_kind(&self.self_repl_info.replid)
+            && let ReplicationId::Undecided = connected_info.replid
         {
-            if let ReplicationId::Undecided = connected_info.replid {
-                let logs =
-                    SyncLogs(logger.range(0, self.self_repl_info.hwm.load(Ordering::Acquire)));
-                self.w.write_io(logs).await?;
-            }
+            let logs = SyncLogs(logger.range(0, self.self_repl_info.hwm.load(Ordering::Acquire)));
+            self.w.write_io(logs).await?;
         };
 
         Ok(())
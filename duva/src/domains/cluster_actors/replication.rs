@@ -1,4 +1,5 @@
 use super::consensus::ElectionState;
+use super::hard_state::HardState;
 pub(crate) use super::heartbeats::heartbeat::BannedPeer;
 pub(crate) use super::heartbeats::heartbeat::HeartBeatMessage;
 use crate::domains::peers::cluster_peer::ClusterNode;
@@ -24,6 +25,8 @@ pub(crate) struct ReplicationState {
 
     pub(crate) election_state: ElectionState,
     pub(crate) is_leader_mode: bool,
+    // writes that failed because consensus wasn't reached in time
+    pub(crate) timed_out_writes: u64,
 }
 
 impl ReplicationState {
@@ -43,6 +46,24 @@ impl ReplicationState {
             self_host: self_host.to_string(),
             self_port,
             ban_list: Default::default(),
+            timed_out_writes: 0,
+        }
+    }
+
+    /// Restores the term and vote recorded before the last shutdown.
+    pub(crate) fn with_hard_state(mut self, hard_state: &HardState) -> Self {
+        self.term = hard_state.term;
+        if let ElectionState::Follower { voted_for } = &mut self.election_state {
+            *voted_for = hard_state.voted_for.clone();
+        }
+        self
+    }
+
+    /// The vote to persist alongside the current term. Candidates and leaders have voted for themselves.
+    pub(crate) fn voted_for(&self) -> Option<PeerIdentifier> {
+        match &self.election_state {
+            ElectionState::Follower { voted_for } => voted_for.clone(),
+            ElectionState::Candidate { .. } | ElectionState::Leader => Some(self.self_identifier()),
         }
     }
 
@@ -61,6 +82,7 @@ impl ReplicationState {
             format!("leader_repl_id:{}", self.replid),
             format!("high_watermark:{}", self.hwm.load(Ordering::Relaxed)),
             format!("self_identifier:{}", self.self_identifier()),
+            format!("timed_out_writes:{}", self.timed_out_writes),
         ]
     }
 
@@ -91,6 +113,7 @@ impl ReplicationState {
             cluster_nodes: vec![],
             prev_log_index,
             prev_log_term,
+            read_seq: 0,
         }
     }
 
@@ -111,12 +134,16 @@ impl ReplicationState {
 
         self.election_state.become_candidate(replica_count);
     }
+    pub(crate) fn can_vote_for(&self, candidate_id: &PeerIdentifier, election_term: u64) -> bool {
+        self.election_state.is_votable(candidate_id) && self.term == election_term
+    }
+
     pub(crate) fn may_become_follower(
         &mut self,
         candidate_id: &PeerIdentifier,
         election_term: u64,
     ) -> bool {
-        if !(self.election_state.is_votable(candidate_id) && self.term < election_term) {
+        if !self.can_vote_for(candidate_id, election_term) {
             return false;
         }
         self.vote_for(Some(candidate_id.clone()));
@@ -210,3 +237,27 @@ fn test_cloning_replication_state() {
     //THEN
     assert_eq!(cloned.hwm.load(Ordering::Relaxed), 5);
 }
+
+#[test]
+fn test_restored_vote_prevents_voting_twice_in_same_term() {
+    //GIVEN
+    let hard_state = HardState {
+        term: 2,
+        voted_for: Some(PeerIdentifier::new("127.0.0.1", 6380)),
+        ..Default::default()
+    };
+
+    //WHEN
+    let replication_state = ReplicationState::new(
+        ReplicationId::Key("dsd".into()),
+        ReplicationRole::Follower,
+        "ads",
+        1231,
+    )
+    .with_hard_state(&hard_state);
+
+    //THEN
+    assert_eq!(replication_state.term, 2);
+    assert!(!replication_state.can_vote_for(&PeerIdentifier::new("127.0.0.1", 6381), 2));
+    assert_eq!(replication_state.voted_for(), hard_state.voted_for);
+}
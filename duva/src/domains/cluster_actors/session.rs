@@ -1,7 +1,9 @@
+use bincode::{Decode, Encode};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::domains::query_parsers::QueryIO;
 use crate::make_smart_pointer;
 
 #[derive(Default)]
@@ -10,6 +12,8 @@ pub(crate) struct ClientSessions(HashMap<Uuid, Session>);
 pub(crate) struct Session {
     last_accessed: DateTime<Utc>,
     processed_req_id: Option<u64>,
+    // what the processed request returned, handed out again when it is retried
+    response: QueryIO,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,30 +27,52 @@ impl SessionRequest {
     }
 }
 
+// Sent along with writes a follower forwards, so the leader deduplicates them too
+impl Encode for SessionRequest {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.request_id.encode(encoder)?;
+        self.client_id.as_u128().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for SessionRequest {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let request_id = u64::decode(decoder)?;
+        let client_id = Uuid::from_u128(u128::decode(decoder)?);
+        Ok(Self { request_id, client_id })
+    }
+}
+bincode::impl_borrow_decode!(SessionRequest);
+
 make_smart_pointer!(ClientSessions,HashMap<Uuid, Session>);
 
 impl ClientSessions {
-    pub(crate) fn is_processed(&self, client_req: &Option<SessionRequest>) -> bool {
-        let Some(client_req) = client_req else {
-            return false;
-        };
-        if self.get(&client_req.client_id).is_none() {
-            return false;
-        }
-        let session = self.get(&client_req.client_id).unwrap();
-        let Some(res) = session.processed_req_id.as_ref() else {
-            return false;
-        };
-
-        *res == client_req.request_id
+    /// The response of `client_req`, if it was already processed.
+    pub(crate) fn processed_response(
+        &self,
+        client_req: &Option<SessionRequest>,
+    ) -> Option<QueryIO> {
+        let client_req = client_req.as_ref()?;
+        let session = self.get(&client_req.client_id)?;
+        let res = session.processed_req_id.as_ref()?;
+
+        (*res == client_req.request_id).then(|| session.response.clone())
     }
-    pub(crate) fn set_response(&mut self, session_req: Option<SessionRequest>) {
+    pub(crate) fn set_response(&mut self, session_req: Option<SessionRequest>, response: &QueryIO) {
         let Some(session_req) = session_req else { return };
 
-        let entry = self
-            .entry(session_req.client_id)
-            .or_insert(Session { last_accessed: Default::default(), processed_req_id: None });
+        let entry = self.entry(session_req.client_id).or_insert(Session {
+            last_accessed: Default::default(),
+            processed_req_id: None,
+            response: QueryIO::Null,
+        });
         entry.last_accessed = Utc::now();
         entry.processed_req_id = Some(session_req.request_id);
+        entry.response = response.clone();
     }
 }
@@ -11,15 +11,6 @@ impl ConfigActor {
         }
     }
 
-    pub(crate) fn set_dir(&mut self, dir: &str) {
-        unsafe {
-            // Get the pointer to the str
-            let ptr: *const str = self.dir;
-            // Recreate the Box from the pointer and drop it
-            let _reclaimed_box: Box<str> = Box::from_raw(ptr as *mut str);
-            self.dir = Box::leak(dir.into());
-        }
-    }
     pub(crate) fn set_dbfilename(&mut self, dbfilename: &str) {
         unsafe {
             // Get the pointer to the str
@@ -30,4 +30,8 @@ pub trait TWriteAheadLog: Send + Sync + 'static {
 
     fn is_empty(&self) -> bool;
     fn truncate_after(&mut self, log_index: u64) -> impl Future<Output = ()> + Send;
+
+    /// Discards entries up to and including `log_index` once a snapshot covers them.
+    /// Implementations may keep some of those entries, but the most recent entry is never discarded.
+    fn compact_up_to(&mut self, log_index: u64) -> impl Future<Output = Result<()>> + Send;
 }
@@ -4,58 +4,104 @@ pub(crate) struct ReplicatedLogs<T: TWriteAheadLog> {
     pub(crate) target: T,
     pub(crate) last_log_index: u64,
     pub(crate) last_log_term: u64,
+    // Index and term of the last entry covered by a snapshot. The entry itself may already be gone from `target`.
+    pub(crate) last_included_index: u64,
+    pub(crate) last_included_term: u64,
 }
 
 impl<T: TWriteAheadLog> ReplicatedLogs<T> {
     pub fn new(target: T, last_log_index: u64, last_log_term: u64) -> Self {
-        Self { target, last_log_index, last_log_term }
+        Self {
+            target,
+            last_log_index,
+            last_log_term,
+            last_included_index: 0,
+            last_included_term: 0,
+        }
     }
 
-    pub(crate) async fn leader_write_entries(
-        &mut self,
-        log: &WriteRequest,
-        low_watermark: Option<u64>,
-        term: u64,
-    ) -> anyhow::Result<Vec<WriteOperation>> {
-        let current_idx = self.last_log_index;
-        self.write_single_entry(log, term).await?;
+    /// Rebuilds log metadata from the entries already persisted in `target`.
+    pub(crate) async fn restore(mut target: T) -> anyhow::Result<Self> {
+        let (mut last_log_index, mut last_log_term) = (0, 0);
+        target
+            .replay(|op| {
+                last_log_index = op.log_index;
+                last_log_term = op.term;
+            })
+            .await?;
+        Ok(Self::new(target, last_log_index, last_log_term))
+    }
 
-        if low_watermark.is_none() {
-            return Ok(self.from(current_idx));
+    /// Resumes from a snapshot ending at `last_included_index`.
+    /// Entries the log still holds past the snapshot keep their place; anything older is covered by it.
+    pub(crate) fn with_snapshot(
+        mut self,
+        last_included_index: u64,
+        last_included_term: u64,
+    ) -> Self {
+        self.last_included_index = last_included_index;
+        self.last_included_term = last_included_term;
+        if self.last_log_index < last_included_index {
+            self.last_log_index = last_included_index;
+            self.last_log_term = last_included_term;
         }
-
-        let mut logs = Vec::with_capacity((self.last_log_index - low_watermark.unwrap()) as usize);
-        logs.extend(self.from(low_watermark.unwrap()));
-
-        // ! Last log term must be updated because
-        // ! log consistency check is based on previous log term and index
-        self.last_log_term = term;
-        Ok(logs)
+        self
     }
 
-    async fn write_single_entry(&mut self, log: &WriteRequest, term: u64) -> anyhow::Result<()> {
+    pub(crate) async fn leader_write_entry(
+        &mut self,
+        log: &WriteRequest,
+        term: u64,
+    ) -> anyhow::Result<WriteOperation> {
         let op =
             WriteOperation { request: log.clone(), log_index: (self.last_log_index + 1), term };
+        self.write_single_entry(op.clone()).await?;
+        Ok(op)
+    }
+
+    async fn write_single_entry(&mut self, op: WriteOperation) -> anyhow::Result<()> {
+        let term = op.term;
         self.target.append(op).await?;
         self.last_log_index += 1;
+        // ! Last log term must be updated because
+        // ! log consistency check and election restriction are based on it
+        self.last_log_term = term;
         Ok(())
     }
 
     // FOLLOWER side operation
+    /// Appends the entries the log doesn't hold yet. An entry it holds with another term conflicts with the leader's,
+    /// so that entry and everything after it are dropped first.
+    /// Returns the index the log was truncated after, if an entry conflicted.
     pub(crate) async fn follower_write_entries(
         &mut self,
-        append_entries: Vec<WriteOperation>,
-    ) -> anyhow::Result<u64> {
-        // Filter and append entries in a single operation
-        let new_entries: Vec<_> =
-            append_entries.into_iter().filter(|log| log.log_index > self.last_log_index).collect();
+        append_entries: &[WriteOperation],
+    ) -> anyhow::Result<Option<u64>> {
+        let mut truncated = None;
+        for op in append_entries {
+            if op.log_index > self.last_log_index {
+                break;
+            }
+            // an entry covered by a snapshot is committed, so it agrees with the leader's
+            if self.term_at(op.log_index).await.is_some_and(|term| term != op.term) {
+                self.truncate_after(op.log_index - 1).await;
+                truncated = Some(op.log_index - 1);
+                break;
+            }
+        }
+
+        let new_entries: Vec<_> = append_entries
+            .iter()
+            .filter(|log| log.log_index > self.last_log_index)
+            .cloned()
+            .collect();
 
         self.update_metadata(&new_entries);
 
         self.target.append_many(new_entries).await?;
 
         println!("[INFO] Received log entry with log index up to {}", self.last_log_index);
-        Ok(self.last_log_index)
+        Ok(truncated)
     }
 
     pub(crate) async fn follower_install_logs(
@@ -66,18 +112,60 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
         self.target.overwrite(ops).await?;
         Ok(())
     }
-    pub(crate) fn range(&self, start_exclusive: u64, end_inclusive: u64) -> Vec<WriteOperation> {
-        self.target.range(start_exclusive, end_inclusive)
+    /// Discards every entry once a snapshot covering them has been installed.
+    /// The log continues from `last_included_index` so later entries line up with the leader's.
+    pub(crate) async fn install_snapshot(
+        &mut self,
+        last_included_index: u64,
+        last_included_term: u64,
+    ) -> anyhow::Result<()> {
+        self.target.overwrite(vec![]).await?;
+        self.last_log_index = last_included_index;
+        self.last_log_term = last_included_term;
+        self.last_included_index = last_included_index;
+        self.last_included_term = last_included_term;
+        Ok(())
     }
 
-    fn from(&self, start_exclusive: u64) -> Vec<WriteOperation> {
-        self.target.range(start_exclusive, self.last_log_index)
+    pub(crate) fn range(&self, start_exclusive: u64, end_inclusive: u64) -> Vec<WriteOperation> {
+        self.target.range(start_exclusive, end_inclusive)
     }
 
     pub(crate) async fn read_at(&self, prev_log_index: u64) -> Option<WriteOperation> {
         self.target.read_at(prev_log_index).await
     }
 
+    /// Term of the entry at `log_index`, including the last one covered by a snapshot.
+    pub(crate) async fn term_at(&self, log_index: u64) -> Option<u64> {
+        if log_index == self.last_included_index {
+            return Some(self.last_included_term);
+        }
+        self.read_at(log_index).await.map(|op| op.term)
+    }
+
+    /// First index holding an entry of `term`, or where such an entry would go.
+    pub(crate) async fn first_index_of_term(&self, term: u64) -> u64 {
+        self.partition_point(|t| t < term).await
+    }
+
+    pub(crate) async fn last_index_of_term(&self, term: u64) -> Option<u64> {
+        let idx = self.partition_point(|t| t <= term).await.checked_sub(1)?;
+        self.read_at(idx).await.filter(|op| op.term == term).map(|op| op.log_index)
+    }
+
+    // Terms never decrease along the log, so a binary search finds the first index whose term fails `pred`
+    async fn partition_point(&self, pred: impl Fn(u64) -> bool) -> u64 {
+        let (mut lo, mut hi) = (self.log_start_index(), self.last_log_index + 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.read_at(mid).await {
+                Some(op) if pred(op.term) => lo = mid + 1,
+                _ => hi = mid,
+            }
+        }
+        lo
+    }
+
     pub(crate) fn log_start_index(&self) -> u64 {
         self.target.log_start_index()
     }
@@ -86,8 +174,22 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
         self.target.is_empty()
     }
 
+    /// Drops every entry after `log_index`, so the log ends at that entry.
     pub(crate) async fn truncate_after(&mut self, log_index: u64) {
+        if log_index >= self.last_log_index {
+            return;
+        }
         self.target.truncate_after(log_index).await;
+        self.last_log_term = self.term_at(log_index).await.unwrap_or_default();
+        self.last_log_index = log_index;
+    }
+
+    /// Drops entries covered by a snapshot ending at `log_index`, whose entry is of `term`.
+    pub(crate) async fn compact_up_to(&mut self, log_index: u64, term: u64) -> anyhow::Result<()> {
+        self.target.compact_up_to(log_index).await?;
+        self.last_included_index = log_index;
+        self.last_included_term = term;
+        Ok(())
     }
 
     fn update_metadata(&mut self, new_entries: &[WriteOperation]) {
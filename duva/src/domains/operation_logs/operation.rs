@@ -1,5 +1,8 @@
-use crate::domains::query_parsers::{QueryIO, deserialize};
-use bytes::{Bytes, BytesMut};
+use crate::domains::caches::sorted_set::Score;
+use crate::domains::caches::stream::{GroupReadFrom, StreamId, StreamIdSpec};
+use crate::domains::peers::identifier::PeerIdentifier;
+use crate::domains::query_parsers::QueryIO;
+use bytes::Bytes;
 
 #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
 pub struct WriteOperation {
@@ -12,9 +15,112 @@ pub struct WriteOperation {
 /// Client request is converted to WriteOperation and then it turns into WriteOp when it gets offset
 #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
 pub enum WriteRequest {
-    Set { key: String, value: String },
-    SetWithExpiry { key: String, value: String, expires_at: u64 },
-    Delete { keys: Vec<String> },
+    Set {
+        key: String,
+        value: String,
+    },
+    SetWithExpiry {
+        key: String,
+        value: String,
+        expires_at: u64,
+    },
+    Delete {
+        keys: Vec<String>,
+    },
+    LPush {
+        key: String,
+        values: Vec<String>,
+    },
+    RPush {
+        key: String,
+        values: Vec<String>,
+    },
+    /// Pops a single element when `count` is not given.
+    LPop {
+        key: String,
+        count: Option<u64>,
+    },
+    RPop {
+        key: String,
+        count: Option<u64>,
+    },
+    HSet {
+        key: String,
+        entries: Vec<(String, String)>,
+    },
+    HDel {
+        key: String,
+        fields: Vec<String>,
+    },
+    HIncrBy {
+        key: String,
+        field: String,
+        increment: i64,
+    },
+    SAdd {
+        key: String,
+        members: Vec<String>,
+    },
+    SRem {
+        key: String,
+        members: Vec<String>,
+    },
+    /// Stores the combination of the sets at `keys` in `destination`, replacing what it held.
+    SInterStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+    SUnionStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+    SDiffStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+    ZAdd {
+        key: String,
+        entries: Vec<(Score, String)>,
+    },
+    ZRem {
+        key: String,
+        members: Vec<String>,
+    },
+    ZIncrBy {
+        key: String,
+        increment: Score,
+        member: String,
+    },
+    XAdd {
+        key: String,
+        id: StreamIdSpec,
+        fields: Vec<(String, String)>,
+    },
+    XGroupCreate {
+        key: String,
+        group: String,
+        start: Option<StreamId>,
+        mkstream: bool,
+    },
+    /// Delivering entries to a consumer changes the pending entries of its group, so reads by a group are writes.
+    XReadGroup {
+        group: String,
+        consumer: String,
+        streams: Vec<(String, GroupReadFrom)>,
+        count: Option<u64>,
+        now_ms: u64,
+    },
+    XAck {
+        key: String,
+        group: String,
+        ids: Vec<StreamId>,
+    },
+    /// Voting members of the cluster, effective once this entry is committed.
+    Configuration {
+        voters: Vec<PeerIdentifier>,
+    },
+    /// Appended by a new leader so entries from earlier terms get committed.
+    NoOp,
 }
 
 impl WriteOperation {
@@ -22,21 +128,3 @@ impl WriteOperation {
         QueryIO::WriteOperation(self).serialize()
     }
 }
-
-impl WriteRequest {
-    /// Deserialize `WriteOperation`s from the given bytes.
-    pub(crate) fn deserialize(mut bytes: BytesMut) -> anyhow::Result<Vec<WriteOperation>> {
-        let mut ops: Vec<WriteOperation> = Vec::new();
-
-        while !bytes.is_empty() {
-            let (query, consumed) = deserialize(bytes.clone())?;
-            bytes = bytes.split_off(consumed);
-
-            let QueryIO::WriteOperation(write_operation) = query else {
-                return Err(anyhow::anyhow!("expected replicate"));
-            };
-            ops.push(write_operation);
-        }
-        Ok(ops)
-    }
-}
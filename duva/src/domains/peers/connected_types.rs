@@ -1,12 +1,53 @@
+use crate::domains::IoError;
+use crate::domains::query_parsers::QueryIO;
+use crate::services::interface::TWrite;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{mpsc, oneshot};
 
+// Messages a peer's connection may have queued before senders have to wait or give up
+const OUTBOX_CAPACITY: usize = 64;
+
+type Outgoing = (QueryIO, Option<oneshot::Sender<Result<(), IoError>>>);
+
+/// Writes to a peer from a task of its own, in the order messages were queued,
+/// so a slow connection doesn't hold up the sender.
 #[derive(Debug)]
 pub(crate) struct WriteConnected {
-    pub(crate) stream: OwnedWriteHalf,
+    outbox: mpsc::Sender<Outgoing>,
 }
 impl WriteConnected {
-    pub(crate) fn new(stream: OwnedWriteHalf) -> Self {
-        Self { stream }
+    pub(crate) fn new(mut stream: OwnedWriteHalf) -> Self {
+        let (outbox, mut queued) = mpsc::channel::<Outgoing>(OUTBOX_CAPACITY);
+        tokio::spawn(async move {
+            while let Some((io, written)) = queued.recv().await {
+                let res = stream.write_io(io).await;
+                let failed = res.is_err();
+                if let Some(written) = written {
+                    let _ = written.send(res);
+                }
+                // Once the connection broke, later messages are refused rather than lost silently
+                if failed {
+                    break;
+                }
+            }
+        });
+        Self { outbox }
+    }
+
+    /// Writes `io` once everything queued before it went out.
+    pub(crate) async fn write(&self, io: QueryIO) -> Result<(), IoError> {
+        let (tx, rx) = oneshot::channel();
+        self.outbox.send((io, Some(tx))).await.map_err(|_| IoError::NotConnected)?;
+        rx.await.map_err(|_| IoError::NotConnected)?
+    }
+
+    /// Queues `io` without waiting for it to be written.
+    /// Fails when the queue is full or the connection broke.
+    pub(crate) fn try_write(&self, io: QueryIO) -> Result<(), IoError> {
+        self.outbox.try_send((io, None)).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => IoError::Custom("outbox full".into()),
+            mpsc::error::TrySendError::Closed(_) => IoError::NotConnected,
+        })
     }
 }
 
@@ -23,6 +64,6 @@ impl ReadConnected {
 
 impl From<OwnedWriteHalf> for WriteConnected {
     fn from(w: OwnedWriteHalf) -> WriteConnected {
-        WriteConnected { stream: w }
+        WriteConnected::new(w)
     }
 }
@@ -1,8 +1,8 @@
 use crate::domains::IoError;
+use crate::domains::cluster_actors::consensus::InflightWindow;
 use crate::domains::cluster_actors::replication::ReplicationId;
 use crate::domains::peers::connected_types::WriteConnected;
 use crate::domains::query_parsers::QueryIO;
-use crate::services::interface::TWrite;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
@@ -14,6 +14,8 @@ pub(crate) struct Peer {
     pub(crate) listener_kill_trigger: ListeningActorKillTrigger,
     pub(crate) last_seen: Instant,
     pub kind: PeerState,
+    // AppendEntries sent while this node leads, not acknowledged yet
+    pub(crate) inflight: InflightWindow,
 }
 
 impl Peer {
@@ -28,6 +30,7 @@ impl Peer {
             w_conn: WriteConnected::new(w),
             listener_kill_trigger,
             last_seen: Instant::now(),
+            inflight: InflightWindow::new(kind.match_index()),
             kind,
         }
     }
@@ -36,7 +39,12 @@ impl Peer {
         &mut self,
         io: impl Into<QueryIO> + Send,
     ) -> Result<(), IoError> {
-        self.w_conn.stream.write_io(io).await
+        self.w_conn.write(io.into()).await
+    }
+
+    /// Queues `io` for the peer without waiting for the connection.
+    pub(crate) fn try_send_to_peer(&self, io: impl Into<QueryIO>) -> Result<(), IoError> {
+        self.w_conn.try_write(io.into())
     }
 
     pub(crate) async fn kill(self) -> OwnedReadHalf {
@@ -51,10 +59,19 @@ pub enum PeerState {
 }
 
 impl PeerState {
-    pub(crate) fn decrease_match_index(&mut self) {
+    pub(crate) fn match_index(&self) -> u64 {
+        match self {
+            PeerState::Replica { match_index, .. } | PeerState::NonDataPeer { match_index, .. } => {
+                *match_index
+            },
+        }
+    }
+
+    pub(crate) fn decrease_match_index(&mut self, upper_bound: u64) {
         match self {
-            PeerState::Replica { match_index, .. } => *match_index -= 1,
-            PeerState::NonDataPeer { match_index, .. } => *match_index -= 1,
+            PeerState::Replica { match_index, .. } | PeerState::NonDataPeer { match_index, .. } => {
+                *match_index = match_index.saturating_sub(1).min(upper_bound)
+            },
         }
     }
 }
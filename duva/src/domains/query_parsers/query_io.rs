@@ -1,5 +1,8 @@
-use crate::domains::caches::cache_objects::CacheValue;
-use crate::domains::cluster_actors::commands::{ReplicationResponse, RequestVoteReply, SyncLogs};
+use crate::domains::caches::cache_objects::{CacheValue, WRONGTYPE_ERR};
+use crate::domains::cluster_actors::commands::{
+    ForwardReply, ForwardRequest, InstallSnapshot, PreVote, PreVoteReply, ReplicationResponse,
+    RequestVoteReply, SyncLogs, TimeoutNow,
+};
 use crate::domains::cluster_actors::heartbeats::heartbeat::{AppendEntriesRPC, ClusterHeartBeat};
 
 use crate::domains::{cluster_actors::commands::RequestVote, operation_logs::WriteOperation};
@@ -16,13 +19,21 @@ const ARRAY_PREFIX: char = '*';
 const APPEND_ENTRY_RPC_PREFIX: char = '^';
 const CLUSTER_HEARTBEAT_PREFIX: char = 'c';
 const TOPOLOGY_CHANGE_PREFIX: char = 't';
-const REPLICATE_PREFIX: char = '#';
+pub(crate) const REPLICATE_PREFIX: char = '#';
 const ACKS_PREFIX: char = '@';
 const REQUEST_VOTE_PREFIX: char = 'v';
 const REQUEST_VOTE_REPLY_PREFIX: char = 'r';
+const INSTALL_SNAPSHOT_PREFIX: char = 'i';
+const PRE_VOTE_PREFIX: char = 'p';
+const PRE_VOTE_REPLY_PREFIX: char = 'q';
+const TIMEOUT_NOW_PREFIX: char = 'n';
+const FORWARD_REQUEST_PREFIX: char = 'w';
+const FORWARD_REPLY_PREFIX: char = 'y';
 const SESSION_REQUEST_PREFIX: char = '!';
 const ERR_PREFIX: char = '-';
 const NULL_PREFIX: char = '\u{0000}';
+// error replies redirecting a client to the leader start with it, as in Redis cluster
+const MOVED: &str = "MOVED";
 pub(crate) const SERDE_CONFIG: bincode::config::Configuration = bincode::config::standard();
 
 #[macro_export]
@@ -53,6 +64,12 @@ pub enum QueryIO {
     ConsensusFollowerResponse(ReplicationResponse),
     RequestVote(RequestVote),
     RequestVoteReply(RequestVoteReply),
+    InstallSnapshot(InstallSnapshot),
+    PreVote(PreVote),
+    PreVoteReply(PreVoteReply),
+    TimeoutNow(TimeoutNow),
+    ForwardRequest(ForwardRequest),
+    ForwardReply(ForwardReply),
 
     TopologyChange(Vec<PeerIdentifier>),
 }
@@ -123,6 +140,20 @@ impl QueryIO {
             QueryIO::RequestVoteReply(request_vote_reply) => {
                 serialize_with_bincode(REQUEST_VOTE_REPLY_PREFIX, &request_vote_reply)
             },
+            QueryIO::InstallSnapshot(install_snapshot) => {
+                serialize_with_bincode(INSTALL_SNAPSHOT_PREFIX, &install_snapshot)
+            },
+            QueryIO::PreVote(pre_vote) => serialize_with_bincode(PRE_VOTE_PREFIX, &pre_vote),
+            QueryIO::PreVoteReply(pre_vote_reply) => {
+                serialize_with_bincode(PRE_VOTE_REPLY_PREFIX, &pre_vote_reply)
+            },
+            QueryIO::TimeoutNow(timeout_now) => {
+                serialize_with_bincode(TIMEOUT_NOW_PREFIX, &timeout_now)
+            },
+            QueryIO::ForwardRequest(request) => {
+                serialize_with_bincode(FORWARD_REQUEST_PREFIX, &request)
+            },
+            QueryIO::ForwardReply(reply) => serialize_with_bincode(FORWARD_REPLY_PREFIX, &reply),
             QueryIO::ClusterHeartBeat(heart_beat_message) => {
                 serialize_with_bincode(CLUSTER_HEARTBEAT_PREFIX, &heart_beat_message)
             },
@@ -158,6 +189,20 @@ impl QueryIO {
         }
         Ok(result)
     }
+
+    /// Error reply telling a client to send its writes to `leader` instead.
+    pub fn moved(leader: &PeerIdentifier) -> Self {
+        QueryIO::Err(format!("{} {}", MOVED, leader))
+    }
+
+    /// The leader a `MOVED` error reply points at.
+    pub fn moved_to(&self) -> Option<PeerIdentifier> {
+        let QueryIO::Err(err) = self else {
+            return None;
+        };
+        let leader = err.strip_prefix(MOVED)?.strip_prefix(' ')?;
+        Some(PeerIdentifier(leader.to_string()))
+    }
 }
 
 impl From<String> for QueryIO {
@@ -175,6 +220,7 @@ impl From<Option<CacheValue>> for QueryIO {
         match v {
             Some(CacheValue::Value(v)) => QueryIO::BulkString(v),
             Some(CacheValue::ValueWithExpiry { value: v, .. }) => QueryIO::BulkString(v), // todo need to revisit
+            Some(_) => QueryIO::Err(WRONGTYPE_ERR.into()),
             None => QueryIO::Null,
         }
     }
@@ -222,6 +268,12 @@ pub fn deserialize(buffer: BytesMut) -> Result<(QueryIO, usize)> {
         ACKS_PREFIX => parse_custom_type::<ReplicationResponse>(buffer),
         REQUEST_VOTE_PREFIX => parse_custom_type::<RequestVote>(buffer),
         REQUEST_VOTE_REPLY_PREFIX => parse_custom_type::<RequestVoteReply>(buffer),
+        INSTALL_SNAPSHOT_PREFIX => parse_custom_type::<InstallSnapshot>(buffer),
+        PRE_VOTE_PREFIX => parse_custom_type::<PreVote>(buffer),
+        PRE_VOTE_REPLY_PREFIX => parse_custom_type::<PreVoteReply>(buffer),
+        TIMEOUT_NOW_PREFIX => parse_custom_type::<TimeoutNow>(buffer),
+        FORWARD_REQUEST_PREFIX => parse_custom_type::<ForwardRequest>(buffer),
+        FORWARD_REPLY_PREFIX => parse_custom_type::<ForwardReply>(buffer),
         TOPOLOGY_CHANGE_PREFIX => parse_custom_type::<Vec<PeerIdentifier>>(buffer),
 
         _ => Err(anyhow::anyhow!("Not a known value type {:?}", buffer)),
@@ -406,6 +458,42 @@ impl From<RequestVoteReply> for QueryIO {
     }
 }
 
+impl From<InstallSnapshot> for QueryIO {
+    fn from(value: InstallSnapshot) -> Self {
+        QueryIO::InstallSnapshot(value)
+    }
+}
+
+impl From<PreVote> for QueryIO {
+    fn from(value: PreVote) -> Self {
+        QueryIO::PreVote(value)
+    }
+}
+
+impl From<PreVoteReply> for QueryIO {
+    fn from(value: PreVoteReply) -> Self {
+        QueryIO::PreVoteReply(value)
+    }
+}
+
+impl From<TimeoutNow> for QueryIO {
+    fn from(value: TimeoutNow) -> Self {
+        QueryIO::TimeoutNow(value)
+    }
+}
+
+impl From<ForwardRequest> for QueryIO {
+    fn from(value: ForwardRequest) -> Self {
+        QueryIO::ForwardRequest(value)
+    }
+}
+
+impl From<ForwardReply> for QueryIO {
+    fn from(value: ForwardReply) -> Self {
+        QueryIO::ForwardReply(value)
+    }
+}
+
 impl From<Vec<PeerIdentifier>> for QueryIO {
     fn from(value: Vec<PeerIdentifier>) -> Self {
         QueryIO::TopologyChange(value)
@@ -415,8 +503,11 @@ impl From<Vec<PeerIdentifier>> for QueryIO {
 mod test {
     use uuid::Uuid;
 
-    use crate::domains::cluster_actors::commands::RejectionReason;
+    use crate::domains::cluster_actors::commands::{
+        ForwardedKind, ForwardedResponse, LogConflict, RejectionReason,
+    };
     use crate::domains::cluster_actors::replication::{HeartBeatMessage, ReplicationId};
+    use crate::domains::cluster_actors::session::SessionRequest;
     use crate::domains::peers::cluster_peer::{ClusterNode, NodeKind};
     use crate::domains::peers::identifier::PeerIdentifier;
     use crate::domains::{cluster_actors::replication::BannedPeer, operation_logs::WriteRequest};
@@ -446,7 +537,7 @@ mod test {
 
         // THEN
         assert_eq!(len, 7);
-        assert_eq!(value, QueryIO::SimpleString("PING".to_string().into()));
+        assert_eq!(value, QueryIO::SimpleString("PING".to_string()));
     }
 
     #[test]
@@ -568,6 +659,8 @@ mod test {
             rej_reason: RejectionReason::None,
             log_idx: 2,
             from: PeerIdentifier("repl1".into()),
+            read_seq: 3,
+            conflict: Some(LogConflict { term: 1, first_index: 2 }),
         };
         let acks = QueryIO::ConsensusFollowerResponse(follower_res);
 
@@ -582,7 +675,7 @@ mod test {
     #[test]
     fn test_heartbeat_to_binary_back_to_heartbeat() {
         // GIVEN
-        let me = PeerIdentifier::new("me".into(), 6035);
+        let me = PeerIdentifier::new("me", 6035);
         let leader = ReplicationId::Undecided;
         let banned_list = vec![
             BannedPeer { p_id: PeerIdentifier("banned1".into()), ban_time: 3553 },
@@ -651,6 +744,7 @@ mod test {
                     NodeKind::Replica,
                 ),
             ],
+            read_seq: 0,
         };
         let replicate = QueryIO::AppendEntriesRPC(AppendEntriesRPC(heartbeat));
 
@@ -696,6 +790,115 @@ mod test {
         assert_eq!(deserialized, request_vote_reply);
     }
 
+    #[test]
+    fn test_pre_vote_to_binary_back_to_pre_vote() {
+        // GIVEN
+        let pre_vote = PreVote(RequestVote {
+            term: 2,
+            candidate_id: PeerIdentifier("me".into()),
+            last_log_index: 5,
+            last_log_term: 1,
+        });
+        let pre_vote = QueryIO::PreVote(pre_vote);
+
+        // WHEN
+        let serialized = pre_vote.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, pre_vote);
+    }
+
+    #[test]
+    fn test_pre_vote_reply_to_binary_back_to_pre_vote_reply() {
+        // GIVEN
+        let pre_vote_reply =
+            QueryIO::PreVoteReply(PreVoteReply(RequestVoteReply { term: 2, vote_granted: false }));
+
+        // WHEN
+        let serialized = pre_vote_reply.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, pre_vote_reply);
+    }
+
+    #[test]
+    fn test_timeout_now_to_binary_back_to_timeout_now() {
+        // GIVEN
+        let timeout_now =
+            QueryIO::TimeoutNow(TimeoutNow { term: 3, from: PeerIdentifier("leader".into()) });
+
+        // WHEN
+        let serialized = timeout_now.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, timeout_now);
+    }
+
+    #[test]
+    fn test_forward_request_to_binary_back_to_forward_request() {
+        // GIVEN
+        let forward_request = QueryIO::ForwardRequest(ForwardRequest {
+            seq: 7,
+            from: PeerIdentifier("follower".into()),
+            kind: ForwardedKind::Write {
+                log: WriteRequest::Set { key: "foo".into(), value: "bar".into() },
+                session_req: Some(SessionRequest::new(3, Uuid::now_v7())),
+                timeout_mills: Some(100),
+            },
+        });
+
+        // WHEN
+        let serialized = forward_request.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, forward_request);
+    }
+
+    #[test]
+    fn test_forward_reply_to_binary_back_to_forward_reply() {
+        // GIVEN
+        let forward_reply = QueryIO::ForwardReply(ForwardReply {
+            seq: 7,
+            response: ForwardedResponse::Written(
+                QueryIO::SimpleString("OK".into()).serialize().into(),
+            ),
+        });
+
+        // WHEN
+        let serialized = forward_reply.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, forward_reply);
+    }
+
+    #[test]
+    fn test_install_snapshot_to_binary_back_to_install_snapshot() {
+        // GIVEN
+        let install_snapshot = InstallSnapshot {
+            term: 2,
+            from: PeerIdentifier("me".into()),
+            last_included_index: 10,
+            last_included_term: 2,
+            offset: 0,
+            data: vec![0, 1, 2, 255],
+            done: true,
+            voters: vec![PeerIdentifier("me".into())],
+        };
+        let install_snapshot = QueryIO::InstallSnapshot(install_snapshot);
+
+        // WHEN
+        let serialized = install_snapshot.clone().serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, install_snapshot);
+    }
+
     #[test]
     fn test_topology_change_serde() {
         //GIVEN
@@ -712,4 +915,18 @@ mod test {
         //THEN
         assert_eq!(deserialized_topology, topology);
     }
+
+    #[test]
+    fn test_moved_reply_carries_leader_over_the_wire() {
+        // GIVEN
+        let leader = PeerIdentifier::new("127.0.0.1", 6380);
+
+        // WHEN
+        let serialized = QueryIO::moved(&leader).serialize();
+        let (deserialized, _) = deserialize(BytesMut::from(serialized)).unwrap();
+
+        // THEN
+        assert_eq!(deserialized.moved_to(), Some(leader));
+        assert_eq!(QueryIO::Err("Write operation failed".into()).moved_to(), None);
+    }
 }
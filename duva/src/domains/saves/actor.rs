@@ -65,6 +65,7 @@ impl SaveActor {
                     self.encode_chunk_queue().await?;
                     let checksum = encode_checksum(&[0; 8])?;
                     self.target.write(&checksum).await?;
+                    self.target.sync().await?;
                     return Ok(true);
                 }
             },
@@ -106,6 +107,13 @@ impl SaveTarget {
             },
         }
     }
+
+    pub async fn sync(&mut self) -> Result<(), IoError> {
+        match self {
+            SaveTarget::File(f) => f.sync_all().await.map_err(|e| e.kind().into()),
+            SaveTarget::InMemory(_) => Ok(()),
+        }
+    }
 }
 
 pub struct SaveMeta {
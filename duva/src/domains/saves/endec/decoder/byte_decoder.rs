@@ -1,17 +1,22 @@
 use super::states::{DecoderInit, HeaderReady, MetadataReady};
 
 use crate::domains::caches::cache_objects::CacheEntry;
+use crate::domains::caches::sorted_set::{Score, SortedSet};
+use crate::domains::caches::stream::{ConsumerGroup, PendingEntry, Stream, StreamId};
 use crate::domains::cluster_actors::replication::ReplicationId;
 use crate::domains::saves::endec::{
     DATABASE_SECTION_INDICATOR, DATABASE_TABLE_SIZE_INDICATOR,
-    EXPIRY_TIME_IN_MILLISECONDS_INDICATOR, EXPIRY_TIME_IN_SECONDS_INDICATOR, HEADER_MAGIC_STRING,
-    METADATA_SECTION_INDICATOR, STRING_VALUE_TYPE_INDICATOR, StoredDuration, VERSION,
+    EXPIRY_TIME_IN_MILLISECONDS_INDICATOR, EXPIRY_TIME_IN_SECONDS_INDICATOR,
+    HASH_VALUE_TYPE_INDICATOR, HEADER_MAGIC_STRING, LIST_VALUE_TYPE_INDICATOR,
+    METADATA_SECTION_INDICATOR, SET_VALUE_TYPE_INDICATOR, SORTED_SET_VALUE_TYPE_INDICATOR,
+    STREAM_VALUE_TYPE_INDICATOR, STRING_VALUE_TYPE_INDICATOR, StoredDuration, VERSION,
     extract_range,
 };
 use crate::domains::saves::snapshot::{Metadata, Snapshot, SubDatabase};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::{Deref, DerefMut};
 
 #[derive(Default)]
@@ -291,6 +296,26 @@ impl BytesDecoder<'_, MetadataReady> {
                         None => Ok(CacheEntry::KeyValue { key, value }),
                     };
                 },
+                LIST_VALUE_TYPE_INDICATOR => {
+                    let (key, list) = self.try_extract_key_list()?;
+                    return Ok(CacheEntry::KeyList { key, list });
+                },
+                HASH_VALUE_TYPE_INDICATOR => {
+                    let (key, hash) = self.try_extract_key_hash()?;
+                    return Ok(CacheEntry::KeyHash { key, hash });
+                },
+                SET_VALUE_TYPE_INDICATOR => {
+                    let (key, set) = self.try_extract_key_set()?;
+                    return Ok(CacheEntry::KeySet { key, set });
+                },
+                SORTED_SET_VALUE_TYPE_INDICATOR => {
+                    let (key, zset) = self.try_extract_key_sorted_set()?;
+                    return Ok(CacheEntry::KeySortedSet { key, zset });
+                },
+                STREAM_VALUE_TYPE_INDICATOR => {
+                    let (key, stream) = self.try_extract_key_stream()?;
+                    return Ok(CacheEntry::KeyStream { key, stream });
+                },
                 _ => {
                     return Err(anyhow::anyhow!("Invalid key value pair"));
                 },
@@ -331,6 +356,105 @@ impl BytesDecoder<'_, MetadataReady> {
         Ok((key_data, value_data))
     }
 
+    pub fn try_extract_key_list(&mut self) -> Result<(String, VecDeque<String>)> {
+        self.remove_identifier();
+        let key_data = self.string_decode().context("key decode fail")?;
+        let len = self.size_decode().context("list size decode fail")?;
+        let list = (0..len)
+            .map(|_| self.string_decode().context("list element decode fail"))
+            .collect::<Result<_>>()?;
+
+        Ok((key_data, list))
+    }
+
+    pub fn try_extract_key_hash(&mut self) -> Result<(String, HashMap<String, String>)> {
+        self.remove_identifier();
+        let key_data = self.string_decode().context("key decode fail")?;
+        let len = self.size_decode().context("hash size decode fail")?;
+        let hash = (0..len)
+            .map(|_| {
+                let field = self.string_decode().context("hash field decode fail")?;
+                let value = self.string_decode().context("hash value decode fail")?;
+                Ok((field, value))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok((key_data, hash))
+    }
+
+    pub fn try_extract_key_set(&mut self) -> Result<(String, HashSet<String>)> {
+        self.remove_identifier();
+        let key_data = self.string_decode().context("key decode fail")?;
+        let len = self.size_decode().context("set size decode fail")?;
+        let set = (0..len)
+            .map(|_| self.string_decode().context("set member decode fail"))
+            .collect::<Result<_>>()?;
+
+        Ok((key_data, set))
+    }
+
+    pub fn try_extract_key_sorted_set(&mut self) -> Result<(String, SortedSet)> {
+        self.remove_identifier();
+        let key_data = self.string_decode().context("key decode fail")?;
+        let len = self.size_decode().context("sorted set size decode fail")?;
+        let mut zset = SortedSet::default();
+        for _ in 0..len {
+            let member = self.string_decode().context("sorted set member decode fail")?;
+            let score = self.string_decode().context("sorted set score decode fail")?;
+            let score = score.parse().ok().and_then(Score::new).context("invalid score")?;
+            zset.insert(member, score);
+        }
+
+        Ok((key_data, zset))
+    }
+
+    pub fn try_extract_key_stream(&mut self) -> Result<(String, Stream)> {
+        self.remove_identifier();
+        let key_data = self.string_decode().context("key decode fail")?;
+        let mut stream = Stream { last_id: self.stream_id_decode()?, ..Default::default() };
+
+        let len = self.size_decode().context("stream size decode fail")?;
+        for _ in 0..len {
+            let id = self.stream_id_decode()?;
+            let field_count = self.size_decode().context("stream entry size decode fail")?;
+            let fields = (0..field_count)
+                .map(|_| {
+                    let field = self.string_decode().context("stream field decode fail")?;
+                    let value = self.string_decode().context("stream value decode fail")?;
+                    Ok((field, value))
+                })
+                .collect::<Result<_>>()?;
+            stream.entries.insert(id, fields);
+        }
+
+        let group_count = self.size_decode().context("stream group size decode fail")?;
+        for _ in 0..group_count {
+            let name = self.string_decode().context("stream group name decode fail")?;
+            let mut group =
+                ConsumerGroup { last_delivered: self.stream_id_decode()?, ..Default::default() };
+            let pending_count = self.size_decode().context("pending entries size decode fail")?;
+            for _ in 0..pending_count {
+                let id = self.stream_id_decode()?;
+                let consumer = self.string_decode().context("consumer decode fail")?;
+                let delivered_at = self.number_decode().context("delivery time decode fail")?;
+                let deliveries = self.number_decode().context("delivery count decode fail")?;
+                group.pending.insert(id, PendingEntry { consumer, delivered_at, deliveries });
+            }
+            stream.groups.insert(name, group);
+        }
+
+        Ok((key_data, stream))
+    }
+
+    fn stream_id_decode(&mut self) -> Result<StreamId> {
+        let id = self.string_decode().context("stream id decode fail")?;
+        StreamId::parse(&id, 0)
+    }
+
+    fn number_decode(&mut self) -> Result<u64> {
+        Ok(self.string_decode().context("number decode fail")?.parse()?)
+    }
+
     pub fn try_get_checksum(&mut self) -> Result<Vec<u8>> {
         self.remove_identifier();
         let checksum = extract_range(self.data, 0..=7)
@@ -363,11 +487,13 @@ impl<T> DerefMut for BytesDecoder<'_, T> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::collections::BTreeMap;
 
     fn as_str(cache_entry: &CacheEntry) -> &str {
         match cache_entry {
             CacheEntry::KeyValue { value, .. } => value,
             CacheEntry::KeyValueExpiry { value, .. } => value,
+            _ => panic!("Expected a string value"),
         }
     }
 
@@ -546,6 +672,122 @@ mod test {
         assert!(key_value.expiry().is_some());
     }
 
+    #[test]
+    fn test_key_list_pair() {
+        let mut bytes_handler = BytesDecoder::<MetadataReady> {
+            data: &[0x01, 0x03, 0x62, 0x61, 0x7A, 0x02, 0x01, 0x61, 0xC0, 0x07],
+            state: MetadataReady {
+                metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
+                header: "".into(),
+            },
+        };
+
+        let key_list = bytes_handler.try_key_value().unwrap();
+
+        let CacheEntry::KeyList { key, list } = key_list else {
+            panic!("Expected KeyList");
+        };
+        assert_eq!(key, "baz");
+        assert_eq!(list, VecDeque::from(["a".to_string(), "7".to_string()]));
+        assert!(bytes_handler.data.is_empty());
+    }
+
+    #[test]
+    fn test_key_hash_pair() {
+        let mut bytes_handler = BytesDecoder::<MetadataReady> {
+            data: &[0x04, 0x03, 0x62, 0x61, 0x7A, 0x01, 0x01, 0x66, 0xC0, 0x07],
+            state: MetadataReady {
+                metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
+                header: "".into(),
+            },
+        };
+
+        let key_hash = bytes_handler.try_key_value().unwrap();
+
+        let CacheEntry::KeyHash { key, hash } = key_hash else {
+            panic!("Expected KeyHash");
+        };
+        assert_eq!(key, "baz");
+        assert_eq!(hash, HashMap::from([("f".to_string(), "7".to_string())]));
+        assert!(bytes_handler.data.is_empty());
+    }
+
+    #[test]
+    fn test_key_set_pair() {
+        let mut bytes_handler = BytesDecoder::<MetadataReady> {
+            data: &[0x02, 0x03, 0x62, 0x61, 0x7A, 0x02, 0x01, 0x61, 0xC0, 0x07],
+            state: MetadataReady {
+                metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
+                header: "".into(),
+            },
+        };
+
+        let key_set = bytes_handler.try_key_value().unwrap();
+
+        let CacheEntry::KeySet { key, set } = key_set else {
+            panic!("Expected KeySet");
+        };
+        assert_eq!(key, "baz");
+        assert_eq!(set, HashSet::from(["a".to_string(), "7".to_string()]));
+        assert!(bytes_handler.data.is_empty());
+    }
+
+    #[test]
+    fn test_key_sorted_set_pair() {
+        let mut bytes_handler = BytesDecoder::<MetadataReady> {
+            data: &[0x03, 0x03, 0x62, 0x61, 0x7A, 0x01, 0x01, 0x61, 0x04, 0x2D, 0x69, 0x6E, 0x66],
+            state: MetadataReady {
+                metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
+                header: "".into(),
+            },
+        };
+
+        let key_sorted_set = bytes_handler.try_key_value().unwrap();
+
+        let CacheEntry::KeySortedSet { key, zset } = key_sorted_set else {
+            panic!("Expected KeySortedSet");
+        };
+        assert_eq!(key, "baz");
+        assert_eq!(zset.score("a"), Score::new(f64::NEG_INFINITY));
+        assert!(bytes_handler.data.is_empty());
+    }
+
+    #[test]
+    fn test_key_stream_pair() {
+        // key and last ID, then the entries, the groups and their pending entries
+        let mut bytes_handler = BytesDecoder::<MetadataReady> {
+            data: &[
+                0x05, 0x01, b's', 0x03, b'2', b'-', b'0', 0x01, 0x03, b'1', b'-', b'0', 0x01, 0x01,
+                b'f', 0x01, b'v', 0x01, 0x01, b'g', 0x03, b'1', b'-', b'0', 0x01, 0x03, b'1', b'-',
+                b'0', 0x01, b'c', 0xC0, 0x07, 0xC0, 0x01,
+            ],
+            state: MetadataReady {
+                metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
+                header: "".into(),
+            },
+        };
+
+        let key_stream = bytes_handler.try_key_value().unwrap();
+
+        let CacheEntry::KeyStream { key, stream } = key_stream else {
+            panic!("Expected KeyStream");
+        };
+        let one = StreamId { ms: 1, seq: 0 };
+        assert_eq!(key, "s");
+        assert_eq!(stream.last_id, StreamId { ms: 2, seq: 0 });
+        assert_eq!(stream.entries, BTreeMap::from([(one, vec![("f".into(), "v".into())])]));
+        let group = stream.group("g").unwrap();
+        assert_eq!(group.last_delivered, one);
+        assert_eq!(
+            group.pending,
+            BTreeMap::from([(
+                one,
+                PendingEntry { consumer: "c".into(), delivered_at: 7, deliveries: 1 }
+            )])
+        );
+        assert!(bytes_handler.data.is_empty());
+    }
+
     #[test]
     fn test_invalid_expiry_key_value_pair() {
         let mut bytes_handler = BytesDecoder::<MetadataReady> {
@@ -590,7 +832,7 @@ mod test {
             0x62, 0x61, 0x7A, 0x03, 0x71, 0x75, 0x78,
         ];
         let bytes_handler =
-            BytesDecoder::<HeaderReady> { data: data.as_slice().into(), state: Default::default() };
+            BytesDecoder::<HeaderReady> { data: data.as_slice(), state: Default::default() };
 
         let metadata = bytes_handler.load_metadata().unwrap();
         assert_eq!(
@@ -609,7 +851,7 @@ mod test {
             0x4E, 0xF8, 0x0F, 0x77, 0x19,
         ];
         let bytes_handler = BytesDecoder::<MetadataReady> {
-            data: data.as_slice().into(),
+            data: data.as_slice(),
             state: MetadataReady {
                 metadata: Metadata { repl_id: ReplicationId::Undecided, repl_offset: 0 },
                 header: "".into(),
@@ -652,7 +894,7 @@ mod test {
             0x9C, 0xF8, 0xFB, 0x2E, 0x7F, 0xEB,
         ]);
         let bytes_handler =
-            BytesDecoder::<DecoderInit> { data: data.as_slice().into(), state: Default::default() };
+            BytesDecoder::<DecoderInit> { data: data.as_slice(), state: Default::default() };
 
         let rdb_file =
             bytes_handler.load_header().unwrap().load_metadata().unwrap().load_database().unwrap();
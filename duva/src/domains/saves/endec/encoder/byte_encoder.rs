@@ -1,15 +1,17 @@
 use crate::domains::{
-    caches::cache_objects::CacheEntry,
+    caches::{cache_objects::CacheEntry, sorted_set::SortedSet, stream::Stream},
     saves::endec::{
         CHECKSUM_INDICATOR, DATABASE_SECTION_INDICATOR, DATABASE_TABLE_SIZE_INDICATOR,
-        EXPIRY_TIME_IN_MILLISECONDS_INDICATOR, HEADER_MAGIC_STRING, METADATA_SECTION_INDICATOR,
-        STRING_VALUE_TYPE_INDICATOR,
+        EXPIRY_TIME_IN_MILLISECONDS_INDICATOR, HASH_VALUE_TYPE_INDICATOR, HEADER_MAGIC_STRING,
+        LIST_VALUE_TYPE_INDICATOR, METADATA_SECTION_INDICATOR, SET_VALUE_TYPE_INDICATOR,
+        SORTED_SET_VALUE_TYPE_INDICATOR, STREAM_VALUE_TYPE_INDICATOR, STRING_VALUE_TYPE_INDICATOR,
     },
 };
 
 use crate::domains::saves::endec::VERSION;
 use crate::domains::saves::snapshot::Metadata;
 use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 impl CacheEntry {
     pub(crate) fn encode_with_key(&self) -> Result<Vec<u8>> {
@@ -27,6 +29,26 @@ impl CacheEntry {
                 result.push(STRING_VALUE_TYPE_INDICATOR);
                 result.extend_from_slice(&encode_key_value(key, value)?);
             },
+            CacheEntry::KeyList { key, list } => {
+                result.push(LIST_VALUE_TYPE_INDICATOR);
+                result.extend_from_slice(&encode_key_list(key, list)?);
+            },
+            CacheEntry::KeyHash { key, hash } => {
+                result.push(HASH_VALUE_TYPE_INDICATOR);
+                result.extend_from_slice(&encode_key_hash(key, hash)?);
+            },
+            CacheEntry::KeySet { key, set } => {
+                result.push(SET_VALUE_TYPE_INDICATOR);
+                result.extend_from_slice(&encode_key_set(key, set)?);
+            },
+            CacheEntry::KeySortedSet { key, zset } => {
+                result.push(SORTED_SET_VALUE_TYPE_INDICATOR);
+                result.extend_from_slice(&encode_key_sorted_set(key, zset)?);
+            },
+            CacheEntry::KeyStream { key, stream } => {
+                result.push(STREAM_VALUE_TYPE_INDICATOR);
+                result.extend_from_slice(&encode_key_stream(key, stream)?);
+            },
         }
         Ok(result)
     }
@@ -78,6 +100,76 @@ fn encode_key_value(key: &str, value: &str) -> Result<Vec<u8>> {
     Ok(result)
 }
 
+// The key, the number of elements, then every element from head to tail
+fn encode_key_list(key: &str, list: &VecDeque<String>) -> Result<Vec<u8>> {
+    let mut result = encode_string(key.len(), key)?;
+    result.extend_from_slice(&encode_size(list.len())?);
+    for value in list {
+        result.extend_from_slice(&encode_string(value.len(), value)?);
+    }
+    Ok(result)
+}
+
+// The key, the number of fields, then every field followed by its value
+fn encode_key_hash(key: &str, hash: &HashMap<String, String>) -> Result<Vec<u8>> {
+    let mut result = encode_string(key.len(), key)?;
+    result.extend_from_slice(&encode_size(hash.len())?);
+    for (field, value) in hash {
+        result.extend_from_slice(&encode_key_value(field, value)?);
+    }
+    Ok(result)
+}
+
+fn encode_key_set(key: &str, set: &HashSet<String>) -> Result<Vec<u8>> {
+    let mut result = encode_string(key.len(), key)?;
+    result.extend_from_slice(&encode_size(set.len())?);
+    for member in set {
+        result.extend_from_slice(&encode_string(member.len(), member)?);
+    }
+    Ok(result)
+}
+
+// Scores are stored as strings, like the member they follow
+fn encode_key_sorted_set(key: &str, zset: &SortedSet) -> Result<Vec<u8>> {
+    let mut result = encode_string(key.len(), key)?;
+    result.extend_from_slice(&encode_size(zset.len())?);
+    for (member, score) in zset.entries(false) {
+        result.extend_from_slice(&encode_key_value(member, &score.to_string())?);
+    }
+    Ok(result)
+}
+
+// Consumer groups go along with the entries, so that their pending entries survive a restart.
+// IDs and counters are stored as strings
+fn encode_key_stream(key: &str, stream: &Stream) -> Result<Vec<u8>> {
+    let mut result = encode_key_value(key, &stream.last_id.to_string())?;
+    result.extend_from_slice(&encode_size(stream.entries.len())?);
+    for (id, fields) in &stream.entries {
+        result.extend_from_slice(&encode_string_of(id)?);
+        result.extend_from_slice(&encode_size(fields.len())?);
+        for (field, value) in fields {
+            result.extend_from_slice(&encode_key_value(field, value)?);
+        }
+    }
+
+    result.extend_from_slice(&encode_size(stream.groups.len())?);
+    for (name, group) in &stream.groups {
+        result.extend_from_slice(&encode_key_value(name, &group.last_delivered.to_string())?);
+        result.extend_from_slice(&encode_size(group.pending.len())?);
+        for (id, pending) in &group.pending {
+            result.extend_from_slice(&encode_key_value(&id.to_string(), &pending.consumer)?);
+            result.extend_from_slice(&encode_string_of(&pending.delivered_at)?);
+            result.extend_from_slice(&encode_string_of(&pending.deliveries)?);
+        }
+    }
+    Ok(result)
+}
+
+fn encode_string_of(value: &impl ToString) -> Result<Vec<u8>> {
+    let value = value.to_string();
+    encode_string(value.len(), &value)
+}
+
 fn encode_integer(value: u32) -> Result<Vec<u8>> {
     let mut result = Vec::new();
     if value <= 0xFF {
@@ -131,6 +223,10 @@ fn encode_size(size: usize) -> Result<Vec<u8>> {
 #[cfg(test)]
 mod test {
     use crate::domains::{
+        caches::{
+            sorted_set::Score,
+            stream::{GroupReadFrom, StreamId, StreamIdSpec},
+        },
         cluster_actors::replication::ReplicationId,
         saves::endec::{
             StoredDuration,
@@ -342,6 +438,127 @@ mod test {
         assert_eq!(encoded, expected);
     }
 
+    #[test]
+    fn test_cache_list_encode() {
+        let list = CacheEntry::KeyList {
+            key: "key".to_string(),
+            list: VecDeque::from(["a".to_string(), "bc".to_string()]),
+        };
+        let encoded = list.encode_with_key().unwrap();
+        let expected = vec![
+            LIST_VALUE_TYPE_INDICATOR,
+            0x03,
+            b'k',
+            b'e',
+            b'y',
+            0x02,
+            0x01,
+            b'a',
+            0x02,
+            b'b',
+            b'c',
+        ];
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_cache_hash_encode() {
+        let hash = CacheEntry::KeyHash {
+            key: "key".to_string(),
+            hash: HashMap::from([("f".to_string(), "v".to_string())]),
+        };
+        let encoded = hash.encode_with_key().unwrap();
+        let expected =
+            vec![HASH_VALUE_TYPE_INDICATOR, 0x03, b'k', b'e', b'y', 0x01, 0x01, b'f', 0x01, b'v'];
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_cache_set_encode() {
+        let set =
+            CacheEntry::KeySet { key: "key".to_string(), set: HashSet::from(["m".to_string()]) };
+        let encoded = set.encode_with_key().unwrap();
+        let expected = vec![SET_VALUE_TYPE_INDICATOR, 0x03, b'k', b'e', b'y', 0x01, 0x01, b'm'];
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_cache_sorted_set_encode() {
+        let mut zset = SortedSet::default();
+        zset.insert("m".to_string(), Score::new(1.5).unwrap());
+        let sorted_set = CacheEntry::KeySortedSet { key: "key".to_string(), zset };
+        let encoded = sorted_set.encode_with_key().unwrap();
+        let expected = vec![
+            SORTED_SET_VALUE_TYPE_INDICATOR,
+            0x03,
+            b'k',
+            b'e',
+            b'y',
+            0x01,
+            0x01,
+            b'm',
+            0x03,
+            b'1',
+            b'.',
+            b'5',
+        ];
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_cache_stream_encode() {
+        let mut stream = Stream::default();
+        stream
+            .add(StreamIdSpec::Explicit(StreamId { ms: 1, seq: 0 }), vec![("f".into(), "v".into())])
+            .unwrap();
+        stream.create_group("g".to_string(), Some(StreamId::MIN)).unwrap();
+        stream.read_group("g", "c".to_string(), GroupReadFrom::New, None, 7).unwrap();
+        let stream = CacheEntry::KeyStream { key: "s".to_string(), stream };
+        let encoded = stream.encode_with_key().unwrap();
+        let expected = vec![
+            STREAM_VALUE_TYPE_INDICATOR,
+            0x01,
+            b's',
+            // last ID
+            0x03,
+            b'1',
+            b'-',
+            b'0',
+            // entries
+            0x01,
+            0x03,
+            b'1',
+            b'-',
+            b'0',
+            0x01,
+            0x01,
+            b'f',
+            0x01,
+            b'v',
+            // groups with their last delivered ID
+            0x01,
+            0x01,
+            b'g',
+            0x03,
+            b'1',
+            b'-',
+            b'0',
+            // pending entries with their consumer, delivery time and count
+            0x01,
+            0x03,
+            b'1',
+            b'-',
+            b'0',
+            0x01,
+            b'c',
+            0xC0,
+            0x07,
+            0xC0,
+            0x01,
+        ];
+        assert_eq!(encoded, expected);
+    }
+
     #[test]
     fn test_encode_header() {
         let encoded = encode_header().unwrap();
@@ -352,7 +569,7 @@ mod test {
     #[test]
     fn test_encode_metadata() {
         let metadata =
-            Metadata { repl_id: ReplicationId::Key("key1".to_string().into()), repl_offset: 123 };
+            Metadata { repl_id: ReplicationId::Key("key1".to_string()), repl_offset: 123 };
         let encoded = encode_metadata(metadata).unwrap();
         let expected = vec![
             METADATA_SECTION_INDICATOR,
@@ -87,6 +87,11 @@ const DATABASE_TABLE_SIZE_INDICATOR: u8 = 0xFB;
 const EXPIRY_TIME_IN_MILLISECONDS_INDICATOR: u8 = 0xFC;
 const EXPIRY_TIME_IN_SECONDS_INDICATOR: u8 = 0xFD;
 const STRING_VALUE_TYPE_INDICATOR: u8 = 0x00;
+const LIST_VALUE_TYPE_INDICATOR: u8 = 0x01;
+const SET_VALUE_TYPE_INDICATOR: u8 = 0x02;
+const SORTED_SET_VALUE_TYPE_INDICATOR: u8 = 0x03;
+const HASH_VALUE_TYPE_INDICATOR: u8 = 0x04;
+const STREAM_VALUE_TYPE_INDICATOR: u8 = 0x05;
 const CHECKSUM_INDICATOR: u8 = 0xFF;
 
 fn extract_range<const N: usize>(encoded: &[u8], range: RangeInclusive<usize>) -> Option<[u8; N]> {
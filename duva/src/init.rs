@@ -1,6 +1,9 @@
 use crate::{
     domains::{
-        cluster_actors::replication::{ReplicationId, ReplicationRole},
+        cluster_actors::{
+            hard_state::HardStateStore,
+            replication::{ReplicationId, ReplicationRole},
+        },
         peers::cluster_peer::{ClusterNode, NodeKind},
     },
     env_var,
@@ -21,7 +24,12 @@ pub struct Environment {
     pub hf_mills: u64,
     pub ttl_mills: u128,
     pub append_only: bool,
+    pub lease_reads: bool,
+    pub write_timeout_mills: u64,
+    pub forward_writes: bool,
+    pub forward_reads: bool,
     pub topology_writer: Option<tokio::fs::File>,
+    pub(crate) hard_state: Option<HardStateStore>,
 }
 
 impl Environment {
@@ -35,6 +43,10 @@ impl Environment {
                 hf: u64 = 1000,
                 ttl: u128 = 60000,
                 append_only: bool = false,
+                lease_reads: bool = false,
+                write_timeout: u64 = 5000,
+                forward_writes: bool = false,
+                forward_reads: bool = false,
                 tpp: String = "duva.tp".to_string()
             },
             optional: {
@@ -47,6 +59,7 @@ impl Environment {
         let repl_id = Self::determine_repl_id(replicaof.as_ref(), &pre_connected_peers);
         let role = Self::determine_role(replicaof.as_ref(), &pre_connected_peers);
         let topology_writer = Self::open_topology_file(tpp).await;
+        let hard_state = Self::open_hard_state(&dbfilename).await;
 
         Self {
             role,
@@ -59,7 +72,12 @@ impl Environment {
             hf_mills: hf,
             ttl_mills: ttl,
             append_only,
+            lease_reads,
+            write_timeout_mills: write_timeout,
+            forward_writes,
+            forward_reads,
             topology_writer: Some(topology_writer),
+            hard_state: Some(hard_state),
             pre_connected_peers,
         }
     }
@@ -96,6 +114,11 @@ impl Environment {
         OpenOptions::new().create(true).write(true).truncate(true).open(tpp).await.unwrap()
     }
 
+    // Persisted even without the WAL, or a restarted node could vote twice in the same term.
+    async fn open_hard_state(dbfilename: &str) -> HardStateStore {
+        HardStateStore::open(format!("{}.state", dbfilename)).await.unwrap()
+    }
+
     fn parse_replicaof(replicaof: Option<String>) -> Option<PeerIdentifier> {
         replicaof
             .and_then(|s| s.split_once(':').map(|(host, port)| format!("{}:{}", host, port).into()))
@@ -20,6 +20,7 @@ pub use init::Environment;
 use prelude::PeerIdentifier;
 use presentation::clients::ClientController;
 use presentation::clients::authenticate;
+use std::time::Duration;
 
 use presentation::clusters::communication_manager::ClusterCommunicationManager;
 
@@ -48,8 +49,10 @@ impl StartUpFacade {
         env: &mut Environment,
         wal: impl TWriteAheadLog,
     ) -> Self {
+        let hard_state = env.hard_state.take().unwrap_or_default();
         let replication_state =
-            ReplicationState::new(env.repl_id.clone(), env.role.clone(), &env.host, env.port);
+            ReplicationState::new(env.repl_id.clone(), env.role.clone(), &env.host, env.port)
+                .with_hard_state(hard_state.state());
         let cache_manager = CacheManager::run_cache_actors(replication_state.hwm.clone());
         let cluster_actor_handler = ClusterActor::run(
             env.ttl_mills,
@@ -57,7 +60,13 @@ impl StartUpFacade {
             env.hf_mills,
             replication_state,
             cache_manager.clone(),
+            config_manager.clone(),
             wal,
+            hard_state,
+            env.lease_reads,
+            Duration::from_millis(env.write_timeout_mills),
+            env.forward_writes,
+            env.forward_reads,
         );
 
         let registry = ActorRegistry {
@@ -175,6 +184,8 @@ impl StartUpFacade {
                 .await?;
             self.registry.cache_manager.apply_snapshot(snapshot).await?;
         }
-        Ok(())
+
+        // Entries committed after the snapshot was taken only exist in the WAL
+        self.registry.cluster_communication_manager.replay_committed_logs().await
     }
 }
@@ -1,7 +1,13 @@
 use super::request::ClientRequest;
 use crate::actor_registry::ActorRegistry;
 use crate::domains::caches::cache_manager::CacheManager;
-use crate::domains::caches::cache_objects::CacheEntry;
+use crate::domains::caches::cache_objects::{
+    CacheValue, SetOp, combine_sets, list_index, list_range,
+};
+use crate::domains::caches::sorted_set::{Score, SortedSet};
+use crate::domains::caches::stream::{
+    Stream, StreamId, entries_reply, pending_entries_reply, pending_summary_reply, streams_reply,
+};
 use crate::domains::cluster_actors::commands::{ClusterCommand, ConsensusClientResponse};
 use crate::domains::config_actors::command::ConfigResponse;
 use crate::domains::config_actors::config_manager::ConfigManager;
@@ -11,8 +17,11 @@ use crate::presentation::clients::request::ClientAction;
 use crate::presentation::clusters::communication_manager::ClusterCommunicationManager;
 
 use anyhow::Context;
-use futures::future::try_join_all;
+use chrono::Utc;
+use futures::future::{select_all, try_join_all};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 #[derive(Clone)]
 pub(crate) struct ClientController {
@@ -30,30 +39,11 @@ impl ClientController {
         }
     }
 
-    pub(crate) async fn handle(
-        &self,
-        cmd: ClientAction,
-        current_index: Option<u64>,
-    ) -> anyhow::Result<QueryIO> {
+    pub(crate) async fn handle(&self, cmd: ClientAction) -> anyhow::Result<QueryIO> {
         // TODO if it is persistence operation, get the key and hash, take the appropriate sender, send it;
         let response = match cmd {
             ClientAction::Ping => QueryIO::SimpleString("PONG".into()),
             ClientAction::Echo(val) => QueryIO::BulkString(val),
-            ClientAction::Set { key, value } => {
-                let cache_entry =
-                    CacheEntry::KeyValue { key: key.to_owned(), value: value.to_string() };
-                self.cache_manager.route_set(cache_entry).await?;
-                QueryIO::SimpleString(format!("s:{}|idx:{}", value, current_index.unwrap()))
-            },
-            ClientAction::SetWithExpiry { key, value, expiry } => {
-                let cache_entry = CacheEntry::KeyValueExpiry {
-                    key: key.to_owned(),
-                    value: value.to_string(),
-                    expiry,
-                };
-                self.cache_manager.route_set(cache_entry).await?;
-                QueryIO::SimpleString(format!("s:{}|idx:{}", value, current_index.unwrap()))
-            },
             ClientAction::Save => {
                 let file_path = self.config_manager.get_filepath().await?;
                 let file = tokio::fs::OpenOptions::new()
@@ -64,17 +54,32 @@ impl ClientController {
                     .await?;
 
                 let repl_info = self.cluster_communication_manager.replication_info().await?;
-                self.cache_manager
-                    .route_save(
-                        SaveTarget::File(file),
-                        repl_info.replid,
-                        repl_info.hwm.load(Ordering::Acquire),
-                    )
+                let snapshot_index = repl_info.hwm.load(Ordering::Acquire);
+                let save_handle = self
+                    .cache_manager
+                    .route_save(SaveTarget::File(file), repl_info.replid, snapshot_index)
                     .await?;
 
+                // Log entries can only be dropped once the snapshot covering them is on disk
+                let cluster_communication_manager = self.cluster_communication_manager.clone();
+                tokio::spawn(async move {
+                    if let Ok(Ok(_)) = save_handle.await {
+                        println!("[INFO] Snapshot saved up to log index {}", snapshot_index);
+                        let _ = cluster_communication_manager
+                            .send(ClusterCommand::CompactLogs { snapshot_index })
+                            .await;
+                    }
+                });
+
                 QueryIO::Null
             },
-            ClientAction::Get { key } => self.cache_manager.route_get(key).await?.into(),
+            ClientAction::Get { key } => {
+                match self.cluster_communication_manager.read_index().await {
+                    Ok(Some(index)) => self.cache_manager.route_index_get(key, index).await?.into(),
+                    Ok(None) => self.cache_manager.route_get(key).await?.into(),
+                    Err(err) => QueryIO::Err(err.to_string()),
+                }
+            },
             ClientAction::IndexGet { key, index } => {
                 self.cache_manager.route_index_get(key, index).await?.into()
             },
@@ -88,9 +93,6 @@ impl ClientController {
                     _ => QueryIO::Err("Invalid operation".into()),
                 }
             },
-            ClientAction::Delete { keys } => {
-                QueryIO::SimpleString(self.cache_manager.route_delete(keys).await?.to_string())
-            },
             ClientAction::Exists { keys } => {
                 QueryIO::SimpleString(self.cache_manager.route_exists(keys).await?.to_string())
             },
@@ -119,6 +121,25 @@ impl ClientController {
                     Err(e) => QueryIO::Err(e.to_string()),
                 }
             },
+            ClientAction::ClusterFailover(peer_identifier) => {
+                match self.cluster_communication_manager.transfer_leadership(peer_identifier).await
+                {
+                    Ok(()) => QueryIO::SimpleString("OK".into()),
+                    Err(e) => QueryIO::Err(e.to_string()),
+                }
+            },
+            ClientAction::ClusterPromote(peer_identifier) => {
+                match self.cluster_communication_manager.promote_learner(peer_identifier).await {
+                    Ok(()) => QueryIO::SimpleString("OK".into()),
+                    Err(e) => QueryIO::Err(e.to_string()),
+                }
+            },
+            ClientAction::ClusterDemote(peer_identifier) => {
+                match self.cluster_communication_manager.demote_voter(peer_identifier).await {
+                    Ok(()) => QueryIO::SimpleString("OK".into()),
+                    Err(e) => QueryIO::Err(e.to_string()),
+                }
+            },
             ClientAction::ReplicaOf(peer_identifier) => {
                 self.cluster_communication_manager.replicaof(peer_identifier.clone()).await;
 
@@ -133,12 +154,267 @@ impl ClientController {
             ClientAction::Ttl { key } => {
                 QueryIO::SimpleString(self.cache_manager.route_ttl(key).await?)
             },
+            ClientAction::LRange { key, start, stop } => {
+                self.read_list(key, move |list| list_range(list, start, stop).into()).await
+            },
+            ClientAction::LLen { key } => {
+                self.read_list(key, |list| QueryIO::SimpleString(list.len().to_string())).await
+            },
+            ClientAction::LIndex { key, index } => {
+                self.read_list(key, move |list| list_index(list, index).cloned().into()).await
+            },
+            ClientAction::HGet { key, field } => {
+                self.read_hash(key, move |hash| hash.get(&field).cloned().into()).await
+            },
+            ClientAction::HMGet { key, fields } => {
+                self.read_hash(key, move |hash| {
+                    QueryIO::Array(
+                        fields.iter().map(|field| hash.get(field).cloned().into()).collect(),
+                    )
+                })
+                .await
+            },
+            ClientAction::HGetAll { key } => {
+                self.read_hash(key, |hash| {
+                    hash.iter()
+                        .flat_map(|(field, value)| [field.clone(), value.clone()])
+                        .collect::<Vec<_>>()
+                        .into()
+                })
+                .await
+            },
+            ClientAction::SMembers { key } => {
+                self.read_set(key, |set| sorted_members(set.iter().cloned()).into()).await
+            },
+            ClientAction::SIsMember { key, member } => {
+                self.read_set(key, move |set| {
+                    QueryIO::SimpleString((set.contains(&member) as u8).to_string())
+                })
+                .await
+            },
+            ClientAction::SCard { key } => {
+                self.read_set(key, |set| QueryIO::SimpleString(set.len().to_string())).await
+            },
+            ClientAction::SInter { keys } => self.combine_read_sets(SetOp::Inter, keys).await,
+            ClientAction::SUnion { keys } => self.combine_read_sets(SetOp::Union, keys).await,
+            ClientAction::SDiff { keys } => self.combine_read_sets(SetOp::Diff, keys).await,
+            ClientAction::ZScore { key, member } => {
+                self.read_sorted_set(key, move |zset| {
+                    zset.score(&member).map(|score| score.to_string()).into()
+                })
+                .await
+            },
+            ClientAction::ZCard { key } => {
+                self.read_sorted_set(key, |zset| QueryIO::SimpleString(zset.len().to_string()))
+                    .await
+            },
+            ClientAction::ZRank { key, member, rev } => {
+                self.read_sorted_set(key, move |zset| match zset.rank(&member, rev) {
+                    Some(rank) => QueryIO::SimpleString(rank.to_string()),
+                    None => QueryIO::Null,
+                })
+                .await
+            },
+            ClientAction::ZRange { key, start, stop, rev, with_scores } => {
+                self.read_sorted_set(key, move |zset| {
+                    scored_reply(zset.range_by_rank(start, stop, rev), with_scores)
+                })
+                .await
+            },
+            ClientAction::ZRangeByScore { key, range, options } => {
+                self.read_sorted_set(key, move |zset| {
+                    let entries =
+                        zset.range_by_score(range.min, range.max, options.rev, options.limit);
+                    scored_reply(entries, options.with_scores)
+                })
+                .await
+            },
+            ClientAction::XLen { key } => match self.read_stream(key, |s| Ok(s.len())).await {
+                Ok(len) => QueryIO::SimpleString(len.to_string()),
+                Err(err) => QueryIO::Err(err.to_string()),
+            },
+            ClientAction::XRange { key, start, end, count } => {
+                match self.read_stream(key, move |s| Ok(s.range(start, end, count))).await {
+                    Ok(entries) => entries_reply(entries),
+                    Err(err) => QueryIO::Err(err.to_string()),
+                }
+            },
+            ClientAction::XRead { streams, options } => {
+                match self.read_streams(streams, options.count, options.block).await {
+                    Ok(res) => res,
+                    Err(err) => QueryIO::Err(err.to_string()),
+                }
+            },
+            ClientAction::XPending { key, group, range } => {
+                let now_ms = Utc::now().timestamp_millis() as u64;
+                let pending = self
+                    .read_stream(key, move |stream| {
+                        let group = stream.group(&group)?;
+                        Ok(match range {
+                            Some(range) => pending_entries_reply(group, range, now_ms),
+                            None => pending_summary_reply(group),
+                        })
+                    })
+                    .await;
+                pending.unwrap_or_else(|err| QueryIO::Err(err.to_string()))
+            },
             _ => QueryIO::Err("Invalid command".into()),
         };
 
         Ok(response)
     }
 
+    /// Runs `read` on the value at `key` in the cache, so only its reply is copied out.
+    /// Like GET, it reflects every write committed before the read. A wrong type becomes an error reply.
+    async fn read_with(
+        &self,
+        key: String,
+        read: impl FnOnce(Option<&CacheValue>) -> anyhow::Result<QueryIO> + Send + Sync + 'static,
+    ) -> QueryIO {
+        let read_idx = match self.cluster_communication_manager.read_index().await {
+            Ok(read_idx) => read_idx,
+            Err(err) => return QueryIO::Err(err.to_string()),
+        };
+        match self.cache_manager.route_read(key, read_idx, read).await {
+            Ok(Ok(res)) => res,
+            Ok(Err(err)) | Err(err) => QueryIO::Err(err.to_string()),
+        }
+    }
+
+    // An absent key reads as an empty list
+    async fn read_list(
+        &self,
+        key: String,
+        read: impl FnOnce(&VecDeque<String>) -> QueryIO + Send + Sync + 'static,
+    ) -> QueryIO {
+        self.read_with(key, |value| match value {
+            Some(value) => Ok(read(value.list()?)),
+            None => Ok(read(&VecDeque::new())),
+        })
+        .await
+    }
+
+    // An absent key reads as an empty hash
+    async fn read_hash(
+        &self,
+        key: String,
+        read: impl FnOnce(&HashMap<String, String>) -> QueryIO + Send + Sync + 'static,
+    ) -> QueryIO {
+        self.read_with(key, |value| match value {
+            Some(value) => Ok(read(value.hash()?)),
+            None => Ok(read(&HashMap::new())),
+        })
+        .await
+    }
+
+    // An absent key reads as an empty set
+    async fn read_set(
+        &self,
+        key: String,
+        read: impl FnOnce(&HashSet<String>) -> QueryIO + Send + Sync + 'static,
+    ) -> QueryIO {
+        self.read_with(key, |value| match value {
+            Some(value) => Ok(read(value.set()?)),
+            None => Ok(read(&HashSet::new())),
+        })
+        .await
+    }
+
+    // An absent key reads as an empty sorted set
+    async fn read_sorted_set(
+        &self,
+        key: String,
+        read: impl FnOnce(&SortedSet) -> QueryIO + Send + Sync + 'static,
+    ) -> QueryIO {
+        self.read_with(key, |value| match value {
+            Some(value) => Ok(read(value.sorted_set()?)),
+            None => Ok(read(&SortedSet::default())),
+        })
+        .await
+    }
+
+    // An absent key reads as an empty stream. Unlike the other types, `read` returns what it took
+    // from the stream rather than a reply, as XREAD goes over several streams before replying.
+    async fn read_stream<T: Send + 'static>(
+        &self,
+        key: String,
+        read: impl FnOnce(&Stream) -> anyhow::Result<T> + Send + Sync + 'static,
+    ) -> anyhow::Result<T> {
+        let read_idx = self.cluster_communication_manager.read_index().await?;
+        self.cache_manager
+            .route_read(key, read_idx, |value| match value {
+                Some(value) => read(value.stream()?),
+                None => read(&Stream::default()),
+            })
+            .await?
+    }
+
+    /// Reads the entries added to each stream after the given ID, or after its last entry when None.
+    /// With `block`, waits for one to be added while there are none, for at most that long or forever when it's zero.
+    async fn read_streams(
+        &self,
+        streams: Vec<(String, Option<StreamId>)>,
+        count: Option<usize>,
+        block: Option<Duration>,
+    ) -> anyhow::Result<QueryIO> {
+        let mut after = Vec::with_capacity(streams.len());
+        for (key, id) in streams {
+            let id = match id {
+                Some(id) => id,
+                None => self.read_stream(key.clone(), |stream| Ok(stream.last_id)).await?,
+            };
+            after.push((key, id));
+        }
+        let deadline =
+            block.filter(|block| !block.is_zero()).map(|block| tokio::time::Instant::now() + block);
+
+        loop {
+            let mut read = Vec::new();
+            for (key, id) in &after {
+                let id = *id;
+                let entries = self
+                    .read_stream(key.clone(), move |stream| Ok(stream.after(id, count)))
+                    .await?;
+                if !entries.is_empty() {
+                    read.push((key.clone(), entries));
+                }
+            }
+            if !read.is_empty() || block.is_none() {
+                return Ok(streams_reply(read));
+            }
+
+            let waits = try_join_all(
+                after
+                    .iter()
+                    .map(|(key, id)| self.cache_manager.route_wait_stream(key.clone(), *id)),
+            )
+            .await?;
+            let added = select_all(waits);
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, added).await.is_err() {
+                        return Ok(QueryIO::Null);
+                    }
+                },
+                None => {
+                    let _ = added.await;
+                },
+            }
+        }
+    }
+
+    // The keys may live on different shards, so all of them are read at one read index
+    async fn combine_read_sets(&self, op: SetOp, keys: Vec<String>) -> QueryIO {
+        let sets = match self.cluster_communication_manager.read_index().await {
+            Ok(read_idx) => self.cache_manager.route_gather_sets(keys, read_idx).await,
+            Err(err) => Err(err),
+        };
+        match sets {
+            Ok(sets) => sorted_members(combine_sets(op, sets)).into(),
+            Err(err) => QueryIO::Err(err.to_string()),
+        }
+    }
+
     // Manage the client requests & consensus
     pub(crate) async fn maybe_consensus_then_execute(
         &self,
@@ -150,13 +426,13 @@ impl ClientController {
         }))
         .await?;
 
-        // apply write operation to the state machine if it's a write request
+        // writes were applied to the state machine once committed
         let mut results = Vec::with_capacity(requests.len());
-        for (request, log_index_num) in requests.into_iter().zip(consensus.into_iter()) {
-            let (res, _) = tokio::try_join!(
-                self.handle(request.action, log_index_num),
-                self.maybe_send_commit(log_index_num)
-            )?;
+        for (request, committed) in requests.into_iter().zip(consensus) {
+            let res = match committed {
+                Some(res) => res,
+                None => self.handle(request.action).await?,
+            };
             results.push(res);
         }
         Ok(results)
@@ -170,7 +446,7 @@ impl ClientController {
                 if let Some(v) = self.cache_manager.route_get(&key).await? {
                     // Parse current value to u64, add 1, and handle errors
                     let num = v
-                        .value()
+                        .value()?
                         .parse::<i64>()
                         .context("ERR value is not an integer or out of range")?;
                     // Handle potential overflow
@@ -198,7 +474,7 @@ impl ClientController {
     pub(crate) async fn maybe_consensus(
         &self,
         request: &mut ClientRequest,
-    ) -> anyhow::Result<Option<u64>> {
+    ) -> anyhow::Result<Option<QueryIO>> {
         // If the request doesn't require consensus, return Ok
         let Some(log) = request.action.to_write_request() else {
             return Ok(None);
@@ -210,22 +486,32 @@ impl ClientController {
                 log,
                 callback: tx,
                 session_req: request.session_req.take(),
+                timeout: request.timeout,
             })
             .await?;
 
         match rx.await? {
-            //TODO remove option
-            ConsensusClientResponse::LogIndex(log_index) => Ok(log_index),
+            ConsensusClientResponse::Result(res) => Ok(Some(res)),
             ConsensusClientResponse::Err(err) => Err(anyhow::anyhow!(err)),
+            ConsensusClientResponse::Moved(leader) => Ok(Some(QueryIO::moved(&leader))),
         }
     }
+}
 
-    async fn maybe_send_commit(&self, log_index_num: Option<u64>) -> anyhow::Result<()> {
-        if let Some(log_idx) = log_index_num {
-            self.cluster_communication_manager
-                .send(ClusterCommand::SendCommitHeartBeat { log_idx })
-                .await?;
-        }
-        Ok(())
-    }
+// Members are sorted so that the same set always reads back the same way
+fn sorted_members(set: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut members = set.into_iter().collect::<Vec<_>>();
+    members.sort();
+    members
+}
+
+// Each member is followed by its score WITHSCORES
+fn scored_reply(entries: Vec<(&str, Score)>, with_scores: bool) -> QueryIO {
+    entries
+        .into_iter()
+        .flat_map(|(member, score)| {
+            std::iter::once(member.to_string()).chain(with_scores.then(|| score.to_string()))
+        })
+        .collect::<Vec<_>>()
+        .into()
 }
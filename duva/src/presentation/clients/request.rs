@@ -1,9 +1,14 @@
 use crate::domains::{
-    cluster_actors::session::SessionRequest, operation_logs::WriteRequest,
-    peers::identifier::PeerIdentifier, query_parsers::QueryIO,
+    caches::sorted_set::{Score, ScoreBound, ScoreRange},
+    caches::stream::{GroupReadFrom, PendingRange, StreamId, StreamIdSpec},
+    cluster_actors::session::SessionRequest,
+    operation_logs::WriteRequest,
+    peers::identifier::PeerIdentifier,
+    query_parsers::QueryIO,
 };
 use anyhow::Context;
 use chrono::{DateTime, Utc};
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub enum ClientAction {
@@ -21,12 +26,55 @@ pub enum ClientAction {
     ClusterInfo,
     ClusterNodes,
     ClusterForget(PeerIdentifier),
+    ClusterFailover(PeerIdentifier),
+    ClusterPromote(PeerIdentifier),
+    ClusterDemote(PeerIdentifier),
     ReplicaOf(PeerIdentifier),
     Exists { keys: Vec<String> },
     Role,
     Incr { key: String },
     Decr { key: String },
     Ttl { key: String },
+    LPush { key: String, values: Vec<String> },
+    RPush { key: String, values: Vec<String> },
+    LPop { key: String, count: Option<u64> },
+    RPop { key: String, count: Option<u64> },
+    LRange { key: String, start: i64, stop: i64 },
+    LLen { key: String },
+    LIndex { key: String, index: i64 },
+    HSet { key: String, entries: Vec<(String, String)> },
+    HGet { key: String, field: String },
+    HMGet { key: String, fields: Vec<String> },
+    HDel { key: String, fields: Vec<String> },
+    HGetAll { key: String },
+    HIncrBy { key: String, field: String, increment: i64 },
+    SAdd { key: String, members: Vec<String> },
+    SRem { key: String, members: Vec<String> },
+    SMembers { key: String },
+    SIsMember { key: String, member: String },
+    SCard { key: String },
+    SInter { keys: Vec<String> },
+    SUnion { keys: Vec<String> },
+    SDiff { keys: Vec<String> },
+    SInterStore { destination: String, keys: Vec<String> },
+    SUnionStore { destination: String, keys: Vec<String> },
+    SDiffStore { destination: String, keys: Vec<String> },
+    ZAdd { key: String, entries: Vec<(Score, String)> },
+    ZRem { key: String, members: Vec<String> },
+    ZIncrBy { key: String, increment: Score, member: String },
+    ZScore { key: String, member: String },
+    ZCard { key: String },
+    ZRank { key: String, member: String, rev: bool },
+    ZRange { key: String, start: i64, stop: i64, rev: bool, with_scores: bool },
+    ZRangeByScore { key: String, range: ScoreRange, options: RangeOptions },
+    XAdd { key: String, id: StreamIdSpec, fields: Vec<(String, String)> },
+    XLen { key: String },
+    XRange { key: String, start: StreamId, end: StreamId, count: Option<usize> },
+    XRead { streams: Vec<(String, Option<StreamId>)>, options: StreamReadOptions },
+    XGroupCreate { key: String, group: String, start: Option<StreamId>, mkstream: bool },
+    XReadGroup { group: String, consumer: String, read: GroupRead },
+    XAck { key: String, group: String, ids: Vec<StreamId> },
+    XPending { key: String, group: String, range: Option<PendingRange> },
 }
 
 impl ClientAction {
@@ -45,6 +93,81 @@ impl ClientAction {
                 })
             },
             ClientAction::Delete { keys } => Some(WriteRequest::Delete { keys: keys.clone() }),
+            ClientAction::LPush { key, values } => {
+                Some(WriteRequest::LPush { key: key.clone(), values: values.clone() })
+            },
+            ClientAction::RPush { key, values } => {
+                Some(WriteRequest::RPush { key: key.clone(), values: values.clone() })
+            },
+            ClientAction::LPop { key, count } => {
+                Some(WriteRequest::LPop { key: key.clone(), count: *count })
+            },
+            ClientAction::RPop { key, count } => {
+                Some(WriteRequest::RPop { key: key.clone(), count: *count })
+            },
+            ClientAction::HSet { key, entries } => {
+                Some(WriteRequest::HSet { key: key.clone(), entries: entries.clone() })
+            },
+            ClientAction::HDel { key, fields } => {
+                Some(WriteRequest::HDel { key: key.clone(), fields: fields.clone() })
+            },
+            ClientAction::HIncrBy { key, field, increment } => Some(WriteRequest::HIncrBy {
+                key: key.clone(),
+                field: field.clone(),
+                increment: *increment,
+            }),
+            ClientAction::SAdd { key, members } => {
+                Some(WriteRequest::SAdd { key: key.clone(), members: members.clone() })
+            },
+            ClientAction::SRem { key, members } => {
+                Some(WriteRequest::SRem { key: key.clone(), members: members.clone() })
+            },
+            ClientAction::SInterStore { destination, keys } => Some(WriteRequest::SInterStore {
+                destination: destination.clone(),
+                keys: keys.clone(),
+            }),
+            ClientAction::SUnionStore { destination, keys } => Some(WriteRequest::SUnionStore {
+                destination: destination.clone(),
+                keys: keys.clone(),
+            }),
+            ClientAction::SDiffStore { destination, keys } => Some(WriteRequest::SDiffStore {
+                destination: destination.clone(),
+                keys: keys.clone(),
+            }),
+            ClientAction::ZAdd { key, entries } => {
+                Some(WriteRequest::ZAdd { key: key.clone(), entries: entries.clone() })
+            },
+            ClientAction::ZRem { key, members } => {
+                Some(WriteRequest::ZRem { key: key.clone(), members: members.clone() })
+            },
+            ClientAction::ZIncrBy { key, increment, member } => Some(WriteRequest::ZIncrBy {
+                key: key.clone(),
+                increment: *increment,
+                member: member.clone(),
+            }),
+            ClientAction::XAdd { key, id, fields } => {
+                Some(WriteRequest::XAdd { key: key.clone(), id: *id, fields: fields.clone() })
+            },
+            ClientAction::XGroupCreate { key, group, start, mkstream } => {
+                Some(WriteRequest::XGroupCreate {
+                    key: key.clone(),
+                    group: group.clone(),
+                    start: *start,
+                    mkstream: *mkstream,
+                })
+            },
+            ClientAction::XReadGroup { group, consumer, read } => Some(WriteRequest::XReadGroup {
+                group: group.clone(),
+                consumer: consumer.clone(),
+                streams: read.streams.clone(),
+                count: read.count.map(|count| count as u64),
+                now_ms: read.now_ms,
+            }),
+            ClientAction::XAck { key, group, ids } => Some(WriteRequest::XAck {
+                key: key.clone(),
+                group: group.clone(),
+                ids: ids.clone(),
+            }),
             _ => None,
         }
     }
@@ -61,6 +184,8 @@ impl ClientAction {
 pub struct ClientRequest {
     pub(crate) action: ClientAction,
     pub(crate) session_req: Option<SessionRequest>,
+    // overrides how long the write may wait for consensus
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl ClientRequest {
@@ -70,13 +195,15 @@ impl ClientRequest {
     ) -> anyhow::Result<Self> {
         let mut values = value.into_iter().flat_map(|v| v.unpack_single_entry::<String>());
         let command = values.next().ok_or(anyhow::anyhow!("Unexpected command format"))?;
-        let (command, args) = (command, values.collect::<Vec<_>>());
+        let args = values.collect::<Vec<_>>();
+        let args = args.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        let (command, args, timeout) = extract_write_timeout(&command, &args)?;
+        let action = extract_action(command, args).map_err(|e| anyhow::anyhow!(e))?;
+        if timeout.is_some() && action.to_write_request().is_none() {
+            return Err(anyhow::anyhow!("ERR WITHTIMEOUT only applies to writes"));
+        }
 
-        Ok(ClientRequest {
-            action: extract_action(&command, &args.iter().map(|s| s.as_str()).collect::<Vec<_>>())
-                .map_err(|e| anyhow::anyhow!(e))?,
-            session_req,
-        })
+        Ok(ClientRequest { action, session_req, timeout })
     }
 }
 
@@ -95,6 +222,16 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
             Ok(())
         }
     };
+    let require_args_between = |min: usize, max: usize| {
+        if args.len() < min || args.len() > max {
+            Err(anyhow::anyhow!(
+                "(error) ERR wrong number of arguments for '{}' command",
+                cmd.to_lowercase()
+            ))
+        } else {
+            Ok(())
+        }
+    };
     let require_non_empty_args = || {
         if args.is_empty() {
             Err(anyhow::anyhow!(
@@ -132,9 +269,7 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
             } else if args.len() == 2 {
                 Ok(ClientAction::IndexGet { key: args[0].to_string(), index: args[1].parse()? })
             } else {
-                return Err(anyhow::anyhow!(
-                    "(error) ERR wrong number of arguments for 'get' command"
-                ));
+                Err(anyhow::anyhow!("(error) ERR wrong number of arguments for 'get' command"))
             }
         },
 
@@ -182,6 +317,31 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
                     }
                     Ok(ClientAction::ClusterForget(args[1].to_string().into()))
                 },
+                sub @ ("FAILOVER" | "TRANSFER-LEADER") => {
+                    if args.len() != 2 {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR wrong number of arguments for 'cluster {}' command",
+                            sub.to_lowercase()
+                        ));
+                    }
+                    Ok(ClientAction::ClusterFailover(args[1].to_string().into()))
+                },
+                "PROMOTE" => {
+                    if args.len() != 2 {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR wrong number of arguments for 'cluster promote' command"
+                        ));
+                    }
+                    Ok(ClientAction::ClusterPromote(args[1].to_string().into()))
+                },
+                "DEMOTE" => {
+                    if args.len() != 2 {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR wrong number of arguments for 'cluster demote' command"
+                        ));
+                    }
+                    Ok(ClientAction::ClusterDemote(args[1].to_string().into()))
+                },
                 _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
             }
         },
@@ -213,6 +373,320 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
             require_exact_args(1)?;
             Ok(ClientAction::Ttl { key: args[0].to_string() })
         },
+        "LPUSH" | "RPUSH" => {
+            require_args_between(2, usize::MAX)?;
+            let key = args[0].to_string();
+            let values = args[1..].iter().map(|s| s.to_string()).collect();
+            if cmd == "LPUSH" {
+                Ok(ClientAction::LPush { key, values })
+            } else {
+                Ok(ClientAction::RPush { key, values })
+            }
+        },
+        "LPOP" | "RPOP" => {
+            require_args_between(1, 2)?;
+            let key = args[0].to_string();
+            let count = match args.get(1) {
+                Some(count) => Some(
+                    count
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .context("ERR value is out of range, must be positive")?,
+                ),
+                None => None,
+            };
+            if cmd == "LPOP" {
+                Ok(ClientAction::LPop { key, count })
+            } else {
+                Ok(ClientAction::RPop { key, count })
+            }
+        },
+        "LRANGE" => {
+            require_exact_args(3)?;
+            Ok(ClientAction::LRange {
+                key: args[0].to_string(),
+                start: parse_integer(args[1])?,
+                stop: parse_integer(args[2])?,
+            })
+        },
+        "LLEN" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::LLen { key: args[0].to_string() })
+        },
+        "LINDEX" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::LIndex { key: args[0].to_string(), index: parse_integer(args[1])? })
+        },
+        "HSET" => {
+            if args.len() < 3 || args.len().is_multiple_of(2) {
+                return Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'hset' command"
+                ));
+            }
+            let entries =
+                args[1..].chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string()));
+            Ok(ClientAction::HSet { key: args[0].to_string(), entries: entries.collect() })
+        },
+        "HGET" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::HGet { key: args[0].to_string(), field: args[1].to_string() })
+        },
+        "HMGET" | "HDEL" => {
+            require_args_between(2, usize::MAX)?;
+            let key = args[0].to_string();
+            let fields = args[1..].iter().map(|s| s.to_string()).collect();
+            if cmd == "HMGET" {
+                Ok(ClientAction::HMGet { key, fields })
+            } else {
+                Ok(ClientAction::HDel { key, fields })
+            }
+        },
+        "HGETALL" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::HGetAll { key: args[0].to_string() })
+        },
+        "HINCRBY" => {
+            require_exact_args(3)?;
+            Ok(ClientAction::HIncrBy {
+                key: args[0].to_string(),
+                field: args[1].to_string(),
+                increment: parse_integer(args[2])?,
+            })
+        },
+        "SADD" | "SREM" => {
+            require_args_between(2, usize::MAX)?;
+            let key = args[0].to_string();
+            let members = args[1..].iter().map(|s| s.to_string()).collect();
+            if cmd == "SADD" {
+                Ok(ClientAction::SAdd { key, members })
+            } else {
+                Ok(ClientAction::SRem { key, members })
+            }
+        },
+        "SMEMBERS" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::SMembers { key: args[0].to_string() })
+        },
+        "SISMEMBER" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::SIsMember { key: args[0].to_string(), member: args[1].to_string() })
+        },
+        "SCARD" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::SCard { key: args[0].to_string() })
+        },
+        "SINTER" | "SUNION" | "SDIFF" => {
+            require_args_between(1, usize::MAX)?;
+            let keys = args.iter().map(|s| s.to_string()).collect();
+            match cmd.as_str() {
+                "SINTER" => Ok(ClientAction::SInter { keys }),
+                "SUNION" => Ok(ClientAction::SUnion { keys }),
+                _ => Ok(ClientAction::SDiff { keys }),
+            }
+        },
+        "SINTERSTORE" | "SUNIONSTORE" | "SDIFFSTORE" => {
+            require_args_between(2, usize::MAX)?;
+            let destination = args[0].to_string();
+            let keys = args[1..].iter().map(|s| s.to_string()).collect();
+            match cmd.as_str() {
+                "SINTERSTORE" => Ok(ClientAction::SInterStore { destination, keys }),
+                "SUNIONSTORE" => Ok(ClientAction::SUnionStore { destination, keys }),
+                _ => Ok(ClientAction::SDiffStore { destination, keys }),
+            }
+        },
+        "ZADD" => {
+            if args.len() < 3 || args.len().is_multiple_of(2) {
+                return Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'zadd' command"
+                ));
+            }
+            let entries = args[1..]
+                .chunks(2)
+                .map(|pair| Ok((parse_score(pair[0])?, pair[1].to_string())))
+                .collect::<anyhow::Result<_>>()?;
+            Ok(ClientAction::ZAdd { key: args[0].to_string(), entries })
+        },
+        "ZREM" => {
+            require_args_between(2, usize::MAX)?;
+            let members = args[1..].iter().map(|s| s.to_string()).collect();
+            Ok(ClientAction::ZRem { key: args[0].to_string(), members })
+        },
+        "ZINCRBY" => {
+            require_exact_args(3)?;
+            Ok(ClientAction::ZIncrBy {
+                key: args[0].to_string(),
+                increment: parse_score(args[1])?,
+                member: args[2].to_string(),
+            })
+        },
+        "ZSCORE" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::ZScore { key: args[0].to_string(), member: args[1].to_string() })
+        },
+        "ZCARD" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::ZCard { key: args[0].to_string() })
+        },
+        "ZRANK" | "ZREVRANK" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::ZRank {
+                key: args[0].to_string(),
+                member: args[1].to_string(),
+                rev: cmd == "ZREVRANK",
+            })
+        },
+        "ZRANGE" => {
+            require_args_between(3, 5)?;
+            let options = parse_range_options(&args[3..])?;
+            if options.limit.is_some() {
+                return Err(anyhow::anyhow!("ERR syntax error"));
+            }
+            Ok(ClientAction::ZRange {
+                key: args[0].to_string(),
+                start: parse_integer(args[1])?,
+                stop: parse_integer(args[2])?,
+                rev: options.rev,
+                with_scores: options.with_scores,
+            })
+        },
+        "ZRANGEBYSCORE" => {
+            require_args_between(3, 8)?;
+            let options = parse_range_options(&args[3..])?;
+            Ok(ClientAction::ZRangeByScore {
+                key: args[0].to_string(),
+                range: ScoreRange {
+                    min: parse_score_bound(args[1])?,
+                    max: parse_score_bound(args[2])?,
+                },
+                options,
+            })
+        },
+        "XADD" => {
+            if args.len() < 4 || !args.len().is_multiple_of(2) {
+                return Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'xadd' command"
+                ));
+            }
+            let id = match args[1] {
+                "*" => StreamIdSpec::Auto { now_ms: Utc::now().timestamp_millis() as u64 },
+                id => StreamIdSpec::Explicit(StreamId::parse(id, 0)?),
+            };
+            let fields = args[2..].chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string()));
+            Ok(ClientAction::XAdd { key: args[0].to_string(), id, fields: fields.collect() })
+        },
+        "XLEN" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::XLen { key: args[0].to_string() })
+        },
+        "XRANGE" => {
+            if args.len() != 3 && !(args.len() == 5 && args[3].eq_ignore_ascii_case("COUNT")) {
+                return Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'xrange' command"
+                ));
+            }
+            let start = match args[1] {
+                "-" => StreamId::MIN,
+                start => StreamId::parse(start, 0)?,
+            };
+            let end = match args[2] {
+                "+" => StreamId::MAX,
+                end => StreamId::parse(end, u64::MAX)?,
+            };
+            let count = args.get(4).map(|count| parse_count(count)).transpose()?;
+            Ok(ClientAction::XRange { key: args[0].to_string(), start, end, count })
+        },
+        "XREAD" => {
+            let (options, streams) = parse_stream_read_options(args)?;
+            let streams = streams
+                .into_iter()
+                .map(|(key, id)| match id {
+                    "$" => Ok((key, None)),
+                    id => Ok((key, Some(StreamId::parse(id, 0)?))),
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(ClientAction::XRead { streams, options })
+        },
+        "XGROUP" => {
+            require_args_between(4, 5)?;
+            if !args[0].eq_ignore_ascii_case("CREATE") {
+                return Err(anyhow::anyhow!("ERR unknown subcommand '{}'", args[0]));
+            }
+            let mkstream = match args.get(4) {
+                Some(option) if option.eq_ignore_ascii_case("MKSTREAM") => true,
+                Some(_) => return Err(anyhow::anyhow!("ERR syntax error")),
+                None => false,
+            };
+            let start = match args[3] {
+                "$" => None,
+                start => Some(StreamId::parse(start, 0)?),
+            };
+            Ok(ClientAction::XGroupCreate {
+                key: args[1].to_string(),
+                group: args[2].to_string(),
+                start,
+                mkstream,
+            })
+        },
+        "XREADGROUP" => {
+            if args.len() < 3 || !args[0].eq_ignore_ascii_case("GROUP") {
+                return Err(anyhow::anyhow!("ERR syntax error"));
+            }
+            let (options, streams) = parse_stream_read_options(&args[3..])?;
+            // a read by a group goes through consensus, which it can't hold up waiting for entries
+            if options.block.is_some() {
+                return Err(anyhow::anyhow!("ERR BLOCK is not supported for XREADGROUP"));
+            }
+            let streams = streams
+                .into_iter()
+                .map(|(key, id)| match id {
+                    ">" => Ok((key, GroupReadFrom::New)),
+                    id => Ok((key, GroupReadFrom::Pending(StreamId::parse(id, 0)?))),
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(ClientAction::XReadGroup {
+                group: args[1].to_string(),
+                consumer: args[2].to_string(),
+                read: GroupRead {
+                    streams,
+                    count: options.count,
+                    now_ms: Utc::now().timestamp_millis() as u64,
+                },
+            })
+        },
+        "XACK" => {
+            require_args_between(3, usize::MAX)?;
+            let ids =
+                args[2..].iter().map(|id| StreamId::parse(id, 0)).collect::<anyhow::Result<_>>()?;
+            Ok(ClientAction::XAck { key: args[0].to_string(), group: args[1].to_string(), ids })
+        },
+        "XPENDING" => {
+            let range = match args.len() {
+                2 => None,
+                5 | 6 => Some(PendingRange {
+                    start: match args[2] {
+                        "-" => StreamId::MIN,
+                        start => StreamId::parse(start, 0)?,
+                    },
+                    end: match args[3] {
+                        "+" => StreamId::MAX,
+                        end => StreamId::parse(end, u64::MAX)?,
+                    },
+                    count: parse_count(args[4])?,
+                    consumer: args.get(5).map(|consumer| consumer.to_string()),
+                }),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'xpending' command"
+                    ));
+                },
+            };
+            Ok(ClientAction::XPending {
+                key: args[0].to_string(),
+                group: args[1].to_string(),
+                range,
+            })
+        },
         // Add other commands as needed
         unknown_cmd => Err(anyhow::anyhow!(
             "(error) ERR unknown command '{unknown_cmd}', with args beginning with {}",
@@ -221,6 +695,131 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
     }
 }
 
+/// Unwraps `WITHTIMEOUT <milliseconds> <command> [args ...]`, which overrides the write timeout of the command it wraps.
+/// As a prefix, it can't be mistaken for values of the command, so `RPUSH key TIMEOUT 10` pushes all three.
+pub fn extract_write_timeout<'a>(
+    action: &'a str,
+    args: &'a [&'a str],
+) -> anyhow::Result<(&'a str, &'a [&'a str], Option<Duration>)> {
+    if !action.eq_ignore_ascii_case("WITHTIMEOUT") {
+        return Ok((action, args, None));
+    }
+    let [timeout, action, args @ ..] = args else {
+        return Err(anyhow::anyhow!(
+            "(error) ERR wrong number of arguments for 'withtimeout' command"
+        ));
+    };
+
+    let timeout = timeout.parse::<u64>().context("ERR timeout is not an integer")?;
+    Ok((action, args, Some(Duration::from_millis(timeout))))
+}
+
+fn parse_integer(arg: &str) -> anyhow::Result<i64> {
+    arg.parse::<i64>().context("ERR value is not an integer or out of range")
+}
+
+fn parse_score(arg: &str) -> anyhow::Result<Score> {
+    arg.parse::<f64>().ok().and_then(Score::new).context("ERR value is not a valid float")
+}
+
+// A leading `(` excludes the bound itself
+fn parse_score_bound(arg: &str) -> anyhow::Result<ScoreBound> {
+    let bound = match arg.strip_prefix('(') {
+        Some(score) => score.parse::<f64>().ok().and_then(Score::new).map(ScoreBound::Exclusive),
+        None => arg.parse::<f64>().ok().and_then(Score::new).map(ScoreBound::Inclusive),
+    };
+    bound.context("ERR min or max is not a float")
+}
+
+/// Trailing options of a sorted set range. `limit` is the offset and count of the members to take.
+#[derive(Debug, Clone, Default)]
+pub struct RangeOptions {
+    pub(crate) rev: bool,
+    pub(crate) with_scores: bool,
+    pub(crate) limit: Option<(usize, usize)>,
+}
+
+/// Parses the trailing `REV`, `WITHSCORES` and `LIMIT offset count` options of a sorted set range.
+/// A negative count takes every member past the offset, while a negative offset takes none.
+fn parse_range_options(options: &[&str]) -> anyhow::Result<RangeOptions> {
+    let mut parsed = RangeOptions::default();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "REV" => parsed.rev = true,
+            "WITHSCORES" => parsed.with_scores = true,
+            "LIMIT" => {
+                let (Some(offset), Some(count)) = (options.next(), options.next()) else {
+                    return Err(anyhow::anyhow!("ERR syntax error"));
+                };
+                let offset = usize::try_from(parse_integer(offset)?).unwrap_or(usize::MAX);
+                let count = usize::try_from(parse_integer(count)?).unwrap_or(usize::MAX);
+                parsed.limit = Some((offset, count));
+            },
+            _ => return Err(anyhow::anyhow!("ERR syntax error")),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_count(arg: &str) -> anyhow::Result<usize> {
+    arg.parse::<usize>()
+        .ok()
+        .filter(|count| *count > 0)
+        .context("ERR value is out of range, must be positive")
+}
+
+/// `COUNT` and `BLOCK` options of XREAD and XREADGROUP.
+#[derive(Debug, Clone, Default)]
+pub struct StreamReadOptions {
+    pub(crate) count: Option<usize>,
+    pub(crate) block: Option<Duration>,
+}
+
+/// What a consumer of a group reads: the streams with where to start in each, at most `count`
+/// entries per stream, and the time the read is stamped with in the pending entries.
+#[derive(Debug, Clone)]
+pub struct GroupRead {
+    pub(crate) streams: Vec<(String, GroupReadFrom)>,
+    pub(crate) count: Option<usize>,
+    pub(crate) now_ms: u64,
+}
+
+/// Parses `[COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]` of XREAD and XREADGROUP
+/// into the options and each stream key paired with its unparsed id.
+fn parse_stream_read_options<'a>(
+    args: &[&'a str],
+) -> anyhow::Result<(StreamReadOptions, Vec<(String, &'a str)>)> {
+    let mut options = StreamReadOptions::default();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        match option.to_uppercase().as_str() {
+            "COUNT" => options.count = Some(parse_count(args.next().context("ERR syntax error")?)?),
+            "BLOCK" => {
+                let block = args.next().context("ERR syntax error")?;
+                let millis = block
+                    .parse::<u64>()
+                    .context("ERR timeout is not an integer or out of range")?;
+                options.block = Some(Duration::from_millis(millis));
+            },
+            "STREAMS" => {
+                let streams = args.as_slice();
+                if streams.is_empty() || !streams.len().is_multiple_of(2) {
+                    return Err(anyhow::anyhow!(
+                        "ERR Unbalanced list of streams: for each stream key an ID must be specified"
+                    ));
+                }
+                let (keys, ids) = streams.split_at(streams.len() / 2);
+                let streams =
+                    keys.iter().map(|key| key.to_string()).zip(ids.iter().copied()).collect();
+                return Ok((options, streams));
+            },
+            _ => return Err(anyhow::anyhow!("ERR syntax error")),
+        }
+    }
+    Err(anyhow::anyhow!("ERR syntax error"))
+}
+
 pub fn extract_expiry(expiry: &str) -> anyhow::Result<DateTime<Utc>> {
     let expiry = expiry.parse::<i64>().context("Invalid expiry")?;
     Ok(Utc::now() + chrono::Duration::milliseconds(expiry))
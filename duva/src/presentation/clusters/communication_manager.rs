@@ -30,6 +30,12 @@ impl ClusterCommunicationManager {
         Ok(())
     }
 
+    pub(crate) async fn replay_committed_logs(&self) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send(ClusterCommand::ReplayCommittedLogs(tx)).await?;
+        Ok(rx.await?)
+    }
+
     pub(crate) async fn replication_info(&self) -> anyhow::Result<ReplicationState> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.send(ClusterCommand::ReplicationInfo(tx)).await?;
@@ -70,6 +76,31 @@ impl ClusterCommunicationManager {
         let _ = rx.await;
     }
 
+    pub(crate) async fn transfer_leadership(&self, target: PeerIdentifier) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send(ClusterCommand::TransferLeadership { target, callback: tx }).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn promote_learner(&self, learner: PeerIdentifier) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send(ClusterCommand::PromoteLearner { learner, callback: tx }).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn demote_voter(&self, voter: PeerIdentifier) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send(ClusterCommand::DemoteVoter { voter, callback: tx }).await?;
+        rx.await?
+    }
+
+    /// Commit index a linearizable read has to observe, or `None` when this node is not the leader.
+    pub(crate) async fn read_index(&self) -> anyhow::Result<Option<u64>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send(ClusterCommand::ReadIndex(tx)).await?;
+        rx.await?
+    }
+
     pub(crate) async fn cluster_nodes(&self) -> anyhow::Result<Vec<ClusterNode>> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.send(ClusterCommand::ClusterNodes(tx)).await?;
@@ -1,11 +1,18 @@
 use crate::domains::caches::actor::CacheActor;
 use crate::domains::caches::cache_objects::CacheEntry;
 use crate::domains::caches::command::CacheCommand;
-use crate::domains::caches::read_queue::{DeferredRead, ReadQueue};
+use crate::domains::caches::read_queue::{DeferredRead, ReadFn, ReadQueue};
+use crate::domains::caches::stream::StreamWaiters;
 use crate::domains::query_parsers::QueryIO;
 use crate::domains::saves::command::SaveCommand;
 use anyhow::Result;
+use std::time::Duration;
+use tokio::select;
 use tokio::sync::mpsc::Receiver;
+use tokio::time::interval;
+
+// how often XREADs that stopped waiting are dropped, even on keys no one waits on or adds to again
+const STREAM_WAITERS_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
 
 impl CacheActor {
     pub(crate) async fn handle(
@@ -13,7 +20,19 @@ impl CacheActor {
         mut recv: Receiver<CacheCommand>,
         mut rq: ReadQueue,
     ) -> Result<Self> {
-        while let Some(command) = recv.recv().await {
+        let mut stream_waiters = StreamWaiters::default();
+        let mut sweep = interval(STREAM_WAITERS_SWEEP_INTERVAL);
+        loop {
+            let command = select! {
+                command = recv.recv() => match command {
+                    Some(command) => command,
+                    None => break,
+                },
+                _ = sweep.tick() => {
+                    stream_waiters.prune();
+                    continue;
+                },
+            };
             match command {
                 CacheCommand::Set { cache_entry } => {
                     let _ = self.try_send_ttl(&cache_entry).await;
@@ -23,8 +42,20 @@ impl CacheActor {
                     self.get(&key, callback);
                 },
                 CacheCommand::IndexGet { key, read_idx, callback } => {
-                    if let Some(callback) = rq.defer_if_stale(read_idx, &key, callback) {
-                        self.get(&key, callback);
+                    let read: ReadFn = Box::new(move |value| {
+                        let _ = callback.send(value.cloned());
+                    });
+                    if let Some(read) = rq.defer_if_stale(read_idx, &key, read) {
+                        self.read(&key, read);
+                    }
+                },
+                CacheCommand::Read { key, read_idx, read } => {
+                    let read = match read_idx {
+                        Some(read_idx) => rq.defer_if_stale(read_idx, &key, read),
+                        None => Some(read),
+                    };
+                    if let Some(read) = read {
+                        self.read(&key, read);
                     }
                 },
                 CacheCommand::Keys { pattern, callback: sender } => {
@@ -40,6 +71,68 @@ impl CacheActor {
                 CacheCommand::Exists { key, callback } => {
                     self.exists(key, callback);
                 },
+                CacheCommand::ListPush { key, values, end, callback } => {
+                    let _ = callback.send(self.push(key, values, end));
+                },
+                CacheCommand::ListPop { key, count, end, callback } => {
+                    let _ = callback.send(self.pop(&key, count, end));
+                },
+                CacheCommand::HashSet { key, entries, callback } => {
+                    let _ = callback.send(self.hset(key, entries));
+                },
+                CacheCommand::HashDel { key, fields, callback } => {
+                    let _ = callback.send(self.hdel(&key, fields));
+                },
+                CacheCommand::HashIncrBy { key, field, increment, callback } => {
+                    let _ = callback.send(self.hincrby(key, field, increment));
+                },
+                CacheCommand::SetAdd { key, members, callback } => {
+                    let _ = callback.send(self.sadd(key, members));
+                },
+                CacheCommand::SetRem { key, members, callback } => {
+                    let _ = callback.send(self.srem(&key, members));
+                },
+                CacheCommand::SortedSetAdd { key, entries, callback } => {
+                    let _ = callback.send(self.zadd(key, entries));
+                },
+                CacheCommand::SortedSetRem { key, members, callback } => {
+                    let _ = callback.send(self.zrem(&key, members));
+                },
+                CacheCommand::SortedSetIncrBy { key, increment, member, callback } => {
+                    let _ = callback.send(self.zincrby(key, increment, member));
+                },
+                CacheCommand::StreamAdd { key, id, fields, callback } => {
+                    let added = self.xadd(key.clone(), id, fields);
+                    if added.is_ok() {
+                        stream_waiters.wake(&key);
+                    }
+                    let _ = callback.send(added);
+                },
+                CacheCommand::StreamGroupCreate { key, group, start, mkstream, callback } => {
+                    let _ = callback.send(self.xgroup_create(key, group, start, mkstream));
+                },
+                CacheCommand::StreamReadGroup {
+                    key,
+                    group,
+                    consumer,
+                    from,
+                    count,
+                    now_ms,
+                    callback,
+                } => {
+                    let _ =
+                        callback.send(self.xreadgroup(&key, &group, consumer, from, count, now_ms));
+                },
+                CacheCommand::StreamAck { key, group, ids, callback } => {
+                    let _ = callback.send(self.xack(&key, &group, ids));
+                },
+                CacheCommand::StreamWait { key, after, callback } => {
+                    if self.has_stream_entries_after(&key, after) {
+                        let _ = callback.send(());
+                    } else {
+                        stream_waiters.wait(key, callback);
+                    }
+                },
                 CacheCommand::Save { outbox } => {
                     outbox
                         .send(SaveCommand::LocalShardSize {
@@ -55,8 +148,8 @@ impl CacheActor {
                 },
                 CacheCommand::Ping => {
                     if let Some(pending_rqs) = rq.take_pending_requests() {
-                        for DeferredRead { key, callback } in pending_rqs {
-                            self.get(&key, callback);
+                        for DeferredRead { key, read } in pending_rqs {
+                            self.read(&key, read);
                         }
                     };
                 },
@@ -76,8 +169,9 @@ mod test {
     use crate::domains::caches::actor::CacheDb;
     use crate::domains::caches::cache_objects::CacheEntry;
     use crate::domains::caches::cache_objects::CacheValue;
+    use crate::domains::caches::cache_objects::ListEnd;
     use crate::domains::caches::command::CacheCommand;
-    use crate::domains::caches::read_queue::ReadQueue;
+    use crate::domains::caches::read_queue::{ReadFn, ReadQueue};
     use std::sync::Arc;
     use std::sync::atomic::AtomicU64;
     use std::time::Duration;
@@ -104,6 +198,48 @@ mod test {
         ) {
             self.0.send(CacheCommand::IndexGet { key, read_idx, callback }).await.unwrap();
         }
+        async fn push(&self, key: &str, values: &[&str], end: ListEnd) -> anyhow::Result<usize> {
+            let (callback, rx) = oneshot::channel();
+            let values = values.iter().map(|v| v.to_string()).collect();
+            self.0
+                .send(CacheCommand::ListPush { key: key.into(), values, end, callback })
+                .await
+                .unwrap();
+            rx.await.unwrap()
+        }
+        async fn pop(&self, key: &str, count: usize, end: ListEnd) -> anyhow::Result<Vec<String>> {
+            let (callback, rx) = oneshot::channel();
+            self.0
+                .send(CacheCommand::ListPop { key: key.into(), count, end, callback })
+                .await
+                .unwrap();
+            rx.await.unwrap()
+        }
+        async fn hincrby(&self, key: &str, field: &str, increment: i64) -> anyhow::Result<i64> {
+            let (callback, rx) = oneshot::channel();
+            let (key, field) = (key.into(), field.into());
+            self.0
+                .send(CacheCommand::HashIncrBy { key, field, increment, callback })
+                .await
+                .unwrap();
+            rx.await.unwrap()
+        }
+        async fn set_update(
+            &self,
+            key: &str,
+            members: &[&str],
+            add: bool,
+        ) -> anyhow::Result<usize> {
+            let (callback, rx) = oneshot::channel();
+            let (key, members) = (key.into(), members.iter().map(|m| m.to_string()).collect());
+            let command = if add {
+                CacheCommand::SetAdd { key, members, callback }
+            } else {
+                CacheCommand::SetRem { key, members, callback }
+            };
+            self.0.send(command).await.unwrap();
+            rx.await.unwrap()
+        }
         async fn ping(&self) {
             self.0.send(CacheCommand::Ping).await.unwrap();
         }
@@ -178,6 +314,36 @@ mod test {
         assert_eq!(task.await.unwrap().unwrap(), Some(CacheValue::Value(value.clone())));
     }
 
+    #[tokio::test]
+    async fn test_read_runs_in_the_actor_once_its_index_is_applied() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor { cache: CacheDb::default(), self_handler: cache.clone() }
+                .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+        cache.push("jobs", &["a", "b", "c"], ListEnd::Tail).await.unwrap();
+
+        // WHEN
+        let (tx, rx) = oneshot::channel();
+        let read: ReadFn = Box::new(move |value| {
+            let _ = tx.send(value.map(|value| value.list().unwrap().len()));
+        });
+        cache
+            .0
+            .send(CacheCommand::Read { key: "jobs".into(), read_idx: Some(1), read })
+            .await
+            .unwrap();
+        let task = tokio::spawn(rx);
+        hwm.store(1, std::sync::atomic::Ordering::Relaxed);
+        cache.ping().await;
+
+        // THEN
+        assert_eq!(task.await.unwrap().unwrap(), Some(3));
+    }
+
     #[tokio::test]
     async fn test_drop_cache() {
         // GIVEN
@@ -205,4 +371,97 @@ mod test {
         let result = rx.await.unwrap();
         assert_eq!(result, None);
     }
+
+    #[tokio::test]
+    async fn test_list_push_and_pop_from_both_ends() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor { cache: CacheDb::default(), self_handler: cache.clone() }
+                .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+
+        // WHEN
+        assert_eq!(cache.push("jobs", &["b", "c"], ListEnd::Tail).await.unwrap(), 2);
+        assert_eq!(cache.push("jobs", &["a"], ListEnd::Head).await.unwrap(), 3);
+
+        // THEN
+        assert_eq!(cache.pop("jobs", 1, ListEnd::Head).await.unwrap(), vec!["a"]);
+        assert_eq!(cache.pop("jobs", 5, ListEnd::Tail).await.unwrap(), vec!["c", "b"]);
+
+        let (tx, rx) = oneshot::channel();
+        cache.get("jobs".to_string(), tx).await;
+        assert_eq!(rx.await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_commands_on_string_key_fail_without_change() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor { cache: CacheDb::default(), self_handler: cache.clone() }
+                .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+        cache.set("key".to_string(), "value".to_string()).await;
+
+        // WHEN
+        let pushed = cache.push("key", &["a"], ListEnd::Tail).await;
+        let popped = cache.pop("key", 1, ListEnd::Head).await;
+
+        // THEN
+        assert!(pushed.is_err());
+        assert!(popped.is_err());
+        let (tx, rx) = oneshot::channel();
+        cache.get("key".to_string(), tx).await;
+        assert_eq!(rx.await.unwrap(), Some(CacheValue::Value("value".into())));
+    }
+
+    #[tokio::test]
+    async fn test_hash_incrby_counts_missing_field_as_zero() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor { cache: CacheDb::default(), self_handler: cache.clone() }
+                .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+
+        // WHEN
+        assert_eq!(cache.hincrby("user:1", "visits", 5).await.unwrap(), 5);
+        assert_eq!(cache.hincrby("user:1", "visits", -2).await.unwrap(), 3);
+
+        // THEN
+        let (tx, rx) = oneshot::channel();
+        cache.get("user:1".to_string(), tx).await;
+        let hash = rx.await.unwrap().unwrap();
+        assert_eq!(hash.hash().unwrap().get("visits").map(String::as_str), Some("3"));
+        assert!(cache.hincrby("user:1", "visits", i64::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_removes_key_once_emptied() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor { cache: CacheDb::default(), self_handler: cache.clone() }
+                .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+
+        // WHEN
+        assert_eq!(cache.set_update("tags", &["a", "b", "a"], true).await.unwrap(), 2);
+        assert_eq!(cache.set_update("tags", &["b", "c"], true).await.unwrap(), 1);
+        assert_eq!(cache.set_update("tags", &["a", "b", "c", "d"], false).await.unwrap(), 3);
+
+        // THEN
+        let (tx, rx) = oneshot::channel();
+        cache.get("tags".to_string(), tx).await;
+        assert_eq!(rx.await.unwrap(), None);
+    }
 }
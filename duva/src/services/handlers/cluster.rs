@@ -1,10 +1,16 @@
 use crate::domains::caches::cache_manager::CacheManager;
-use crate::domains::cluster_actors::commands::{ClusterCommand, ConsensusClientResponse};
+use crate::domains::cluster_actors::commands::{
+    ClusterCommand, ConsensusClientResponse, ForwardRequest, ForwardedKind,
+};
+use crate::domains::cluster_actors::consensus::ForwardCallback;
+use crate::domains::cluster_actors::hard_state::{HardState, HardStateStore};
 use crate::domains::cluster_actors::replication::ReplicationState;
 use crate::domains::cluster_actors::session::ClientSessions;
 use crate::domains::cluster_actors::{ClusterActor, FANOUT};
+use crate::domains::config_actors::config_manager::ConfigManager;
 use crate::domains::operation_logs::interfaces::TWriteAheadLog;
 use crate::domains::operation_logs::logger::ReplicatedLogs;
+use std::time::Duration;
 use tokio::sync::mpsc::Sender;
 
 impl ClusterActor {
@@ -12,9 +18,15 @@ impl ClusterActor {
         mut self,
         wal: impl TWriteAheadLog,
         cache_manager: CacheManager,
+        config_manager: ConfigManager,
         mut client_sessions: ClientSessions,
     ) -> anyhow::Result<Self> {
-        let mut logger = ReplicatedLogs::new(wal, 0, 0);
+        let HardState { last_included_index, last_included_term, .. } = self.hard_state.state();
+        let mut logger = ReplicatedLogs::restore(wal)
+            .await
+            .expect("[ERROR] Failed to restore logs from WAL")
+            .with_snapshot(*last_included_index, *last_included_term);
+        self.restore_membership(&logger);
 
         while let Some(command) = self.receiver.recv().await {
             match command {
@@ -43,10 +55,21 @@ impl ClusterActor {
                 ClusterCommand::SetReplicationInfo { replid: leader_repl_id, hwm } => {
                     self.set_replication_info(leader_repl_id, hwm);
                 },
+                ClusterCommand::CompactLogs { snapshot_index } => {
+                    self.compact_logs(&mut logger, &cache_manager, snapshot_index).await;
+                },
+                ClusterCommand::ReplayCommittedLogs(callback) => {
+                    self.replay_committed_logs(&logger, &cache_manager).await;
+                    let _ = callback.send(());
+                },
                 ClusterCommand::SendClusterHeatBeat => {
                     // ! remove idle peers based on ttl.
                     // ! The following may need to be moved else where to avoid blocking the main loop
                     self.remove_idle_peers().await;
+                    self.expire_leadership_transfer();
+                    self.read_index.expire();
+                    self.expire_consensus_requests();
+                    self.forwarded.expire();
                     let hop_count = Self::hop_count(FANOUT, self.members.len());
                     self.send_cluster_heartbeat(hop_count, &logger).await;
                 },
@@ -66,14 +89,21 @@ impl ClusterActor {
                         let _ = sender.send(None);
                     }
                 },
-                ClusterCommand::LeaderReqConsensus { log, callback, session_req } => {
-                    if client_sessions.is_processed(&session_req) {
-                        // TODO is it okay to send current log index?
-                        let _ = callback
-                            .send(ConsensusClientResponse::LogIndex(Some(logger.last_log_index)));
+                ClusterCommand::LeaderReqConsensus { log, callback, session_req, timeout } => {
+                    if let Some(res) = client_sessions.processed_response(&session_req) {
+                        let _ = callback.send(ConsensusClientResponse::Result(res));
                         continue;
                     };
-                    self.req_consensus(&mut logger, log, callback, session_req).await;
+                    if self.forwards_writes() {
+                        let timeout_mills = timeout.map(|timeout| timeout.as_millis() as u64);
+                        let kind = ForwardedKind::Write { log, session_req, timeout_mills };
+                        self.forward_to_leader(kind, ForwardCallback::Write(callback), timeout)
+                            .await;
+                        continue;
+                    }
+                    self.req_consensus(&mut logger, log, callback, session_req, timeout).await;
+                    // a lone voter commits right away
+                    self.advance_commit_index(&logger, &cache_manager, &mut client_sessions).await;
                 },
                 ClusterCommand::AppendEntriesRPC(heartbeat) => {
                     if self.check_term_outdated(&heartbeat, &logger).await {
@@ -81,22 +111,36 @@ impl ClusterActor {
                     };
 
                     self.reset_election_timeout(&heartbeat.from);
-                    self.maybe_update_term(heartbeat.term);
+                    self.maybe_update_term(heartbeat.term).await;
                     self.replicate(&mut logger, heartbeat, &cache_manager).await;
                 },
+                ClusterCommand::ReplicationResponse(repl_res) if repl_res.read_seq != 0 => {
+                    self.confirm_read_index(repl_res);
+                },
                 ClusterCommand::ReplicationResponse(repl_res) => {
                     if !repl_res.is_granted() {
-                        self.handle_repl_rejection(repl_res).await;
+                        self.handle_repl_rejection(repl_res, &logger, &cache_manager).await;
                         continue;
                     }
                     self.update_on_hertbeat_message(&repl_res.from, repl_res.log_idx);
-                    self.track_replication_progress(repl_res, &mut client_sessions);
-                },
-                ClusterCommand::SendCommitHeartBeat { log_idx: offset } => {
-                    self.send_commit_heartbeat(offset).await;
+                    self.advance_commit_index(&logger, &cache_manager, &mut client_sessions).await;
+                    self.continue_replication(
+                        &repl_res.from,
+                        repl_res.log_idx,
+                        &logger,
+                        &cache_manager,
+                    )
+                    .await;
+                    self.continue_leadership_transfer(&logger, &cache_manager).await;
                 },
                 ClusterCommand::SendAppendEntriesRPC => {
-                    self.send_leader_heartbeat(&logger).await;
+                    self.check_quorum().await;
+                    if self.replication.is_leader_mode {
+                        self.send_leader_heartbeat(&logger).await;
+                    }
+                    self.resume_replication(&logger, &cache_manager).await;
+                    // entries the leader appended by itself, like a no-op, may need nobody else to commit
+                    self.advance_commit_index(&logger, &cache_manager, &mut client_sessions).await;
                 },
                 ClusterCommand::InstallLeaderState(logs) => {
                     if logger.follower_install_logs(logs.clone()).await.is_err() {
@@ -104,17 +148,82 @@ impl ClusterActor {
                     }
                     self.install_leader_state(logs, &cache_manager).await;
                 },
+                ClusterCommand::SendInstallSnapshot {
+                    to,
+                    last_included_index,
+                    last_included_term,
+                    snapshot,
+                } => {
+                    self.send_install_snapshot(
+                        to,
+                        last_included_index,
+                        last_included_term,
+                        snapshot,
+                    )
+                    .await;
+                },
+                ClusterCommand::InstallSnapshot(chunk) => {
+                    self.install_snapshot(chunk, &mut logger, &cache_manager, &config_manager)
+                        .await;
+                },
                 ClusterCommand::StartLeaderElection => {
-                    self.run_for_election(&mut logger).await;
+                    self.start_pre_vote(&logger).await;
                 },
                 ClusterCommand::VoteElection(request_vote) => {
-                    self.vote_election(request_vote, logger.last_log_index).await;
+                    self.vote_election(request_vote, &logger).await;
                 },
                 ClusterCommand::ApplyElectionVote(request_vote_reply) => {
-                    if !request_vote_reply.vote_granted {
+                    self.tally_vote(request_vote_reply, &mut logger).await;
+                },
+                ClusterCommand::PreVoteElection(request_vote) => {
+                    self.pre_vote_election(request_vote, &logger).await;
+                },
+                ClusterCommand::ApplyPreVote(reply) => {
+                    self.tally_pre_vote(reply, &mut logger).await;
+                },
+                ClusterCommand::TransferLeadership { target, callback } => {
+                    self.transfer_leadership(target, callback, &logger, &cache_manager).await;
+                },
+                ClusterCommand::TimeoutNow(timeout_now) => {
+                    self.timeout_now(timeout_now, &mut logger).await;
+                },
+                ClusterCommand::ReadIndex(callback) => {
+                    if self.forwards_reads() {
+                        let callback = ForwardCallback::ReadIndex(callback);
+                        self.forward_to_leader(ForwardedKind::ReadIndex, callback, None).await;
                         continue;
                     }
-                    self.tally_vote(&logger).await;
+                    self.read_index(callback, &logger).await;
+                },
+                ClusterCommand::ForwardRequest(ForwardRequest { seq, from, kind }) => match kind {
+                    // Handled like the leader's own clients' requests, but never forwarded again
+                    ForwardedKind::Write { log, session_req, timeout_mills } => {
+                        let callback = self.reply_to_forwarded(from, seq);
+                        if let Some(res) = client_sessions.processed_response(&session_req) {
+                            let _ = callback.send(ConsensusClientResponse::Result(res));
+                            continue;
+                        };
+                        let timeout = timeout_mills.map(Duration::from_millis);
+                        self.req_consensus(&mut logger, log, callback, session_req, timeout).await;
+                        self.advance_commit_index(&logger, &cache_manager, &mut client_sessions)
+                            .await;
+                    },
+                    ForwardedKind::ReadIndex => {
+                        let callback = self.reply_to_forwarded(from, seq);
+                        self.read_index(callback, &logger).await;
+                    },
+                },
+                ClusterCommand::ForwardReply(reply) => {
+                    self.forwarded.resolve(reply);
+                },
+                ClusterCommand::SendForwardReply { to, reply } => {
+                    self.send_forward_reply(&to, reply).await;
+                },
+                ClusterCommand::PromoteLearner { learner, callback } => {
+                    self.promote_learner(learner, callback, &mut logger).await;
+                },
+                ClusterCommand::DemoteVoter { voter, callback } => {
+                    self.demote_voter(voter, callback, &mut logger).await;
                 },
                 ClusterCommand::ReplicaOf(peer_addr, callback) => {
                     cache_manager.drop_cache().await;
@@ -132,19 +241,40 @@ impl ClusterActor {
         Ok(self)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn run(
         node_timeout: u128,
         topology_writer: tokio::fs::File,
         heartbeat_interval: u64,
         init_replication: ReplicationState,
         cache_manager: CacheManager,
+        config_manager: ConfigManager,
         wal: impl TWriteAheadLog,
+        hard_state: HardStateStore,
+        lease_reads: bool,
+        write_timeout: Duration,
+        forward_writes: bool,
+        forward_reads: bool,
     ) -> Sender<ClusterCommand> {
-        let cluster_actor =
-            ClusterActor::new(node_timeout, init_replication, heartbeat_interval, topology_writer);
+        let cluster_actor = ClusterActor::new(
+            node_timeout,
+            init_replication,
+            heartbeat_interval,
+            topology_writer,
+            hard_state,
+            lease_reads,
+            write_timeout,
+            forward_writes,
+            forward_reads,
+        );
 
         let actor_handler = cluster_actor.self_handler.clone();
-        tokio::spawn(cluster_actor.handle(wal, cache_manager, ClientSessions::default()));
+        tokio::spawn(cluster_actor.handle(
+            wal,
+            cache_manager,
+            config_manager,
+            ClientSessions::default(),
+        ));
         actor_handler
     }
 }
@@ -1,11 +1,17 @@
 mod test_config_get_dir;
 mod test_del;
 mod test_exists;
+mod test_hash;
 
 mod test_decr;
 mod test_incr;
 mod test_keys;
+mod test_list;
 mod test_replication_info;
 mod test_set_get;
+mod test_set_type;
 mod test_snapshot_persists_and_recovers_state;
+mod test_sorted_set;
+mod test_stream;
 mod test_ttl;
+mod test_wal_recovers_state_on_restart;
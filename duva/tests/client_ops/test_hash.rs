@@ -0,0 +1,56 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_hash_field_writes_and_reads() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("HSET user:1 name duva lang rust", 1), vec!["(integer) 2"]);
+    assert_eq!(h.send_and_get("HSET user:1 lang rs", 1), vec!["(integer) 0"]);
+    assert_eq!(h.send_and_get("HINCRBY user:1 visits 3", 1), vec!["(integer) 3"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("HGET user:1 lang", 1), vec!["rs"]);
+    assert_eq!(h.send_and_get("HGET user:1 missing", 1), vec!["(nil)"]);
+    assert_eq!(
+        h.send_and_get("HMGET user:1 name missing visits", 3),
+        vec!["0) \"duva\"", "1) (nil)", "2) \"3\""]
+    );
+    assert_eq!(
+        h.send_and_get("HINCRBY user:1 name 1", 1),
+        vec!["(error) ERR hash value is not an integer"]
+    );
+
+    // WHEN
+    assert_eq!(h.send_and_get("HDEL user:1 name lang missing", 1), vec!["(integer) 2"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("HGETALL user:1", 2), vec!["0) \"visits\"", "1) \"3\""]);
+    assert_eq!(h.send_and_get("HDEL user:1 visits", 1), vec!["(integer) 1"]);
+    assert_eq!(h.send_and_get("HGETALL user:1", 1), vec!["(empty array)"]);
+    assert_eq!(h.send_and_get("EXISTS user:1", 1), vec!["(integer) 0"]);
+}
+
+#[test]
+fn test_hash_commands_reject_other_types() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET name duva", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("HSET user:1 name duva", 1), vec!["(integer) 1"]);
+
+    // WHEN
+    let wrong_type = "(error) WRONGTYPE Operation against a key holding the wrong kind of value";
+
+    // THEN
+    assert_eq!(h.send_and_get("HSET name f v", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("HGET name f", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("GET user:1", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("LPUSH user:1 a", 1), vec![wrong_type]);
+}
@@ -0,0 +1,50 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_list_push_pop_and_read() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("RPUSH jobs b c", 1), vec!["(integer) 2"]);
+    assert_eq!(h.send_and_get("LPUSH jobs a", 1), vec!["(integer) 3"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("LLEN jobs", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("LRANGE jobs 0 -1", 3), vec!["0) \"a\"", "1) \"b\"", "2) \"c\""]);
+    assert_eq!(h.send_and_get("LINDEX jobs -1", 1), vec!["c"]);
+    assert_eq!(h.send_and_get("LINDEX jobs 5", 1), vec!["(nil)"]);
+
+    // WHEN
+    assert_eq!(h.send_and_get("LPOP jobs", 1), vec!["a"]);
+    assert_eq!(h.send_and_get("RPOP jobs 2", 2), vec!["0) \"c\"", "1) \"b\""]);
+
+    // THEN - the emptied list is gone
+    assert_eq!(h.send_and_get("LPOP jobs", 1), vec!["(nil)"]);
+    assert_eq!(h.send_and_get("EXISTS jobs", 1), vec!["(integer) 0"]);
+}
+
+#[test]
+fn test_list_commands_reject_other_types() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET name duva", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("RPUSH jobs a", 1), vec!["(integer) 1"]);
+
+    // WHEN
+    let wrong_type = "(error) WRONGTYPE Operation against a key holding the wrong kind of value";
+
+    // THEN
+    assert_eq!(h.send_and_get("LPUSH name a", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("RPOP name", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("LRANGE name 0 -1", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("GET jobs", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("INCR jobs", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("GET name", 1), vec!["duva"]);
+}
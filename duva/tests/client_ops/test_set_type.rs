@@ -0,0 +1,70 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_set_member_writes_and_reads() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SADD tags rust raft rust", 1), vec!["(integer) 2"]);
+    assert_eq!(h.send_and_get("SADD tags cache", 1), vec!["(integer) 1"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("SCARD tags", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("SISMEMBER tags raft", 1), vec!["(integer) 1"]);
+    assert_eq!(h.send_and_get("SISMEMBER tags go", 1), vec!["(integer) 0"]);
+    assert_eq!(
+        h.send_and_get("SMEMBERS tags", 3),
+        vec!["0) \"cache\"", "1) \"raft\"", "2) \"rust\""]
+    );
+
+    // WHEN
+    assert_eq!(h.send_and_get("SREM tags rust raft go", 1), vec!["(integer) 2"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("SMEMBERS tags", 1), vec!["0) \"cache\""]);
+    assert_eq!(h.send_and_get("SREM tags cache", 1), vec!["(integer) 1"]);
+    assert_eq!(h.send_and_get("EXISTS tags", 1), vec!["(integer) 0"]);
+}
+
+#[test]
+fn test_set_algebra_across_keys() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SADD s1 a b c", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("SADD s2 b c d", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("SADD s3 c", 1), vec!["(integer) 1"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("SINTER s1 s2 s3", 1), vec!["0) \"c\""]);
+    assert_eq!(h.send_and_get("SDIFF s1 s2", 1), vec!["0) \"a\""]);
+    assert_eq!(
+        h.send_and_get("SUNION s1 s2 missing", 4),
+        vec!["0) \"a\"", "1) \"b\"", "2) \"c\"", "3) \"d\""]
+    );
+    assert_eq!(h.send_and_get("SINTER s1 missing", 1), vec!["(empty array)"]);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SINTERSTORE both s1 s2", 1), vec!["(integer) 2"]);
+    assert_eq!(h.send_and_get("SDIFFSTORE s3 s1 s1", 1), vec!["(integer) 0"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("SMEMBERS both", 2), vec!["0) \"b\"", "1) \"c\""]);
+    assert_eq!(h.send_and_get("EXISTS s3", 1), vec!["(integer) 0"]);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET name duva", 1), vec!["OK"]);
+    let wrong_type = "(error) WRONGTYPE Operation against a key holding the wrong kind of value";
+
+    // THEN
+    assert_eq!(h.send_and_get("SADD name a", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("SUNION s1 name", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("SUNIONSTORE out s1 name", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("GET both", 1), vec![wrong_type]);
+}
@@ -1,7 +1,5 @@
 use crate::common::Client;
-use crate::common::{ServerEnv, spawn_server_process};
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
+use crate::common::{ServerEnv, create_unique_file_name, spawn_server_process};
 
 // TODO response cannot be deterministic!
 #[tokio::test]
@@ -24,7 +22,7 @@ async fn test_snapshot_persists_and_recovers_state() {
     assert_eq!(h.send_and_get("KEYS *", 2), vec!["0) \"foo2\"", "1) \"foo\""]);
 
     // check replication info
-    let info = h.send_and_get("INFO replication", 4);
+    let info = h.send_and_get("INFO replication", 5);
 
     // WHEN
     assert_eq!(h.send_and_get("SAVE", 1), vec!["(nil)"]);
@@ -45,14 +43,8 @@ async fn test_snapshot_persists_and_recovers_state() {
     assert_eq!(client.send_and_get("KEYS *", 2), vec!["0) \"foo2\"", "1) \"foo\""]);
 
     // replication info
-    let info2 = client.send_and_get("INFO replication", 4);
+    let info2 = client.send_and_get("INFO replication", 5);
 
     // THEN
     assert_eq!(info, info2);
 }
-
-fn create_unique_file_name(function_name: &str) -> String {
-    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
-
-    format!("test_{}_{}.rdb", function_name, timestamp)
-}
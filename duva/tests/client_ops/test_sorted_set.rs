@@ -0,0 +1,76 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_sorted_set_ranks_members_by_score() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("ZADD board 10 alice 20 bob 15 carol", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("ZADD board 5 alice", 1), vec!["(integer) 0"]);
+    assert_eq!(h.send_and_get("ZINCRBY board 2.5 carol", 1), vec!["17.5"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("ZCARD board", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("ZSCORE board alice", 1), vec!["5"]);
+    assert_eq!(h.send_and_get("ZSCORE board dave", 1), vec!["(nil)"]);
+    assert_eq!(h.send_and_get("ZRANK board carol", 1), vec!["(integer) 1"]);
+    assert_eq!(h.send_and_get("ZREVRANK board carol", 1), vec!["(integer) 1"]);
+    assert_eq!(h.send_and_get("ZRANK board dave", 1), vec!["(nil)"]);
+    assert_eq!(
+        h.send_and_get("ZRANGE board 0 -1", 3),
+        vec!["0) \"alice\"", "1) \"carol\"", "2) \"bob\""]
+    );
+    assert_eq!(
+        h.send_and_get("ZRANGE board 0 0 REV WITHSCORES", 2),
+        vec!["0) \"bob\"", "1) \"20\""]
+    );
+
+    // WHEN
+    assert_eq!(h.send_and_get("ZREM board alice dave", 1), vec!["(integer) 1"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("ZRANGE board 0 -1", 2), vec!["0) \"carol\"", "1) \"bob\""]);
+    assert_eq!(h.send_and_get("ZREM board carol bob", 1), vec!["(integer) 2"]);
+    assert_eq!(h.send_and_get("EXISTS board", 1), vec!["(integer) 0"]);
+}
+
+#[test]
+fn test_sorted_set_range_by_score() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("ZADD jobs 100 a 200 b 300 c 400 d", 1), vec!["(integer) 4"]);
+
+    // THEN
+    assert_eq!(h.send_and_get("ZRANGEBYSCORE jobs 150 300", 2), vec!["0) \"b\"", "1) \"c\""]);
+    assert_eq!(h.send_and_get("ZRANGEBYSCORE jobs (100 (300", 1), vec!["0) \"b\""]);
+    assert_eq!(
+        h.send_and_get("ZRANGEBYSCORE jobs -inf +inf LIMIT 1 2", 2),
+        vec!["0) \"b\"", "1) \"c\""]
+    );
+    assert_eq!(
+        h.send_and_get("ZRANGEBYSCORE jobs -inf 300 REV WITHSCORES LIMIT 0 1", 2),
+        vec!["0) \"c\"", "1) \"300\""]
+    );
+    assert_eq!(h.send_and_get("ZRANGEBYSCORE jobs 500 +inf", 1), vec!["(empty array)"]);
+    assert_eq!(h.send_and_get("ZRANGEBYSCORE jobs -inf +inf LIMIT -1 2", 1), vec!["(empty array)"]);
+    assert_eq!(
+        h.send_and_get("ZRANGEBYSCORE jobs abc 1", 1),
+        vec!["ERR min or max is not a float"]
+    );
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET name duva", 1), vec!["OK"]);
+    let wrong_type = "(error) WRONGTYPE Operation against a key holding the wrong kind of value";
+
+    // THEN
+    assert_eq!(h.send_and_get("ZADD name 1 a", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("ZRANGE name 0 -1", 1), vec![wrong_type]);
+    assert_eq!(h.send_and_get("GET jobs", 1), vec![wrong_type]);
+}
@@ -0,0 +1,191 @@
+use crate::common::{Client, ServerEnv, create_unique_file_name, spawn_server_process};
+use std::time::Duration;
+
+#[test]
+fn test_stream_entries_writes_and_reads() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("XADD events 1-1 kind login", 1), vec!["1-1"]);
+    assert_eq!(h.send_and_get("XADD events 1-2 kind logout user duva", 1), vec!["1-2"]);
+    let auto_id = h.send_and_get("XADD events * kind login", 1).remove(0);
+
+    // THEN
+    assert!(auto_id.ends_with("-0") && auto_id.as_str() > "1-2", "{auto_id}");
+    assert_eq!(
+        h.send_and_get("XADD events 1-2 kind late", 1),
+        vec![
+            "(error) ERR The ID specified in XADD is equal or smaller than the target stream top item"
+        ]
+    );
+    assert_eq!(h.send_and_get("XLEN events", 1), vec!["(integer) 3"]);
+    assert_eq!(
+        h.send_and_get("XRANGE events - 1 COUNT 2", 8),
+        vec![
+            "0) 0) \"1-1\"",
+            "1) 0) \"kind\"",
+            "1) \"login\"",
+            "1) 0) \"1-2\"",
+            "1) 0) \"kind\"",
+            "1) \"logout\"",
+            "2) \"user\"",
+            "3) \"duva\"",
+        ]
+    );
+    assert_eq!(
+        h.send_and_get("XREAD COUNT 1 STREAMS events missing 1-1 0", 6),
+        vec![
+            "0) 0) \"events\"",
+            "1) 0) 0) \"1-2\"",
+            "1) 0) \"kind\"",
+            "1) \"logout\"",
+            "2) \"user\"",
+            "3) \"duva\"",
+        ]
+    );
+    assert_eq!(h.send_and_get("XREAD STREAMS events $", 1), vec!["(nil)"]);
+    assert_eq!(h.send_and_get("XREAD BLOCK 100 STREAMS events $", 1), vec!["(nil)"]);
+}
+
+#[test]
+fn test_blocking_xread_returns_entry_added_later() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut reader = Client::new(process.port);
+    let mut writer = Client::new(process.port);
+    assert_eq!(writer.send_and_get("XADD jobs 1-0 task old", 1), vec!["1-0"]);
+
+    // WHEN
+    let port = process.port;
+    let adding = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(500));
+        writer.send_and_get("XADD jobs 2-0 task new", 1)
+    });
+    let read = reader.send_and_get("XREAD BLOCK 0 STREAMS jobs $", 4);
+
+    // THEN
+    assert_eq!(adding.join().unwrap(), vec!["2-0"]);
+    assert_eq!(read, vec!["0) 0) \"jobs\"", "1) 0) 0) \"2-0\"", "1) 0) \"task\"", "1) \"new\""]);
+    assert_eq!(Client::new(port).send_and_get("XLEN jobs", 1), vec!["(integer) 2"]);
+}
+
+#[test]
+fn test_consumer_group_tracks_pending_entries_until_acked() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("XADD jobs 1-0 task a", 1), vec!["1-0"]);
+    assert_eq!(h.send_and_get("XADD jobs 2-0 task b", 1), vec!["2-0"]);
+    assert_eq!(h.send_and_get("XGROUP CREATE jobs workers 0", 1), vec!["OK"]);
+    assert_eq!(
+        h.send_and_get("XGROUP CREATE jobs workers $", 1),
+        vec!["(error) BUSYGROUP Consumer Group name already exists"]
+    );
+
+    // WHEN
+    assert_eq!(
+        h.send_and_get("XREADGROUP GROUP workers alice COUNT 1 STREAMS jobs >", 4),
+        vec!["0) 0) \"jobs\"", "1) 0) 0) \"1-0\"", "1) 0) \"task\"", "1) \"a\""]
+    );
+    assert_eq!(
+        h.send_and_get("XREADGROUP GROUP workers bob STREAMS jobs >", 4),
+        vec!["0) 0) \"jobs\"", "1) 0) 0) \"2-0\"", "1) 0) \"task\"", "1) \"b\""]
+    );
+
+    // THEN
+    assert_eq!(h.send_and_get("XREADGROUP GROUP workers bob STREAMS jobs >", 1), vec!["(nil)"]);
+    assert_eq!(
+        h.send_and_get("XPENDING jobs workers", 7),
+        vec![
+            "0) (integer) 2",
+            "1) \"1-0\"",
+            "2) \"2-0\"",
+            "3) 0) 0) \"alice\"",
+            "1) \"1\"",
+            "1) 0) \"bob\"",
+            "1) \"1\"",
+        ]
+    );
+    let pending = h.send_and_get("XPENDING jobs workers - + 10 bob", 4);
+    assert_eq!(pending[..2], ["0) 0) \"2-0\"", "1) \"bob\""]);
+    assert_eq!(pending[3], "3) (integer) 1");
+
+    // WHEN
+    assert_eq!(h.send_and_get("XACK jobs workers 1-0 2-0 3-0", 1), vec!["(integer) 2"]);
+
+    // THEN
+    assert_eq!(
+        h.send_and_get("XPENDING jobs workers", 4),
+        vec!["0) (integer) 0", "1) (nil)", "2) (nil)", "3) (nil)"]
+    );
+    assert_eq!(
+        h.send_and_get("XREADGROUP GROUP missing alice STREAMS jobs >", 1),
+        vec!["(error) NOGROUP No such key or consumer group 'missing'"]
+    );
+}
+
+#[test]
+fn test_group_read_delivers_nothing_when_a_later_stream_has_no_group() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("XGROUP CREATE jobs workers 0 MKSTREAM", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("XADD jobs 1-0 task a", 1), vec!["1-0"]);
+    assert_eq!(h.send_and_get("XADD audits 1-0 task b", 1), vec!["1-0"]);
+
+    // WHEN
+    let read = h.send_and_get("XREADGROUP GROUP workers alice STREAMS jobs audits > >", 1);
+
+    // THEN
+    assert_eq!(read, vec!["(error) NOGROUP No such key or consumer group 'workers'"]);
+    assert_eq!(
+        h.send_and_get("XPENDING jobs workers", 4),
+        vec!["0) (integer) 0", "1) (nil)", "2) (nil)", "3) (nil)"]
+    );
+    assert_eq!(
+        h.send_and_get("XREADGROUP GROUP workers alice STREAMS jobs >", 4),
+        vec!["0) 0) \"jobs\"", "1) 0) 0) \"1-0\"", "1) 0) \"task\"", "1) \"a\""]
+    );
+}
+
+#[tokio::test]
+async fn test_pending_entries_survive_snapshot() {
+    // GIVEN
+    let env = ServerEnv::default().with_file_name(create_unique_file_name("stream_dump"));
+    let process = spawn_server_process(&env);
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("XGROUP CREATE jobs workers $ MKSTREAM", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("XADD jobs 1-0 task a", 1), vec!["1-0"]);
+    assert_eq!(h.send_and_get("XREADGROUP GROUP workers alice STREAMS jobs >", 4).len(), 4);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SAVE", 1), vec!["(nil)"]);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    drop(process);
+
+    let new_process = spawn_server_process(&env);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let mut client = Client::new(new_process.port);
+
+    // THEN
+    assert_eq!(
+        client.send_and_get("XPENDING jobs workers", 5),
+        vec!["0) (integer) 1", "1) \"1-0\"", "2) \"1-0\"", "3) 0) 0) \"alice\"", "1) \"1\""]
+    );
+    assert_eq!(client.send_and_get("XLEN jobs", 1), vec!["(integer) 1"]);
+    assert_eq!(
+        client.send_and_get("XRANGE jobs - +", 3),
+        vec!["0) 0) \"1-0\"", "1) 0) \"task\"", "1) \"a\""]
+    );
+}
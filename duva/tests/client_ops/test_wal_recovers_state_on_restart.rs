@@ -0,0 +1,30 @@
+use crate::common::Client;
+use crate::common::{ServerEnv, create_unique_file_name, spawn_server_process};
+
+#[tokio::test]
+async fn test_wal_recovers_state_on_restart() {
+    // GIVEN
+    let env = ServerEnv::default()
+        .with_file_name(create_unique_file_name("test_wal_recovery"))
+        .with_use_wal(true);
+    let leader_process = spawn_server_process(&env);
+
+    let mut h = Client::new(leader_process.port);
+    assert_eq!(h.send_and_get("SET foo bar", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("SET foo2 bar2", 1), vec!["OK"]);
+    assert_eq!(h.send_and_get("DEL foo2", 1), vec!["(integer) 1"]);
+
+    // WHEN - restart without taking a snapshot
+    drop(leader_process);
+    let new_process = spawn_server_process(&env);
+    let mut client = Client::new(new_process.port);
+
+    // THEN
+    assert_eq!(client.send_and_get("GET foo", 1), vec!["bar"]);
+    assert_eq!(client.send_and_get("KEYS *", 1), vec!["0) \"foo\""]);
+
+    // log indexes continue from where the previous run stopped
+    assert_eq!(client.send_and_get("SET foo3 bar3", 1), vec!["OK"]);
+    let info = client.send_and_get("INFO replication", 5);
+    assert!(info.contains(&"high_watermark:4".to_string()));
+}
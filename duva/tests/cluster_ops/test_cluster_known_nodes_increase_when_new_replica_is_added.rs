@@ -8,7 +8,7 @@ async fn test_cluster_topology_change_when_new_node_added() {
 
     let cmd = "cluster info";
 
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut repl_p = spawn_server_process(&repl_env);
     repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
     leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
@@ -18,7 +18,7 @@ async fn test_cluster_topology_change_when_new_node_added() {
     assert_eq!(cluster_info, vec!["cluster_known_nodes:1".to_string()]);
 
     // // WHEN -- new replica is added
-    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut new_repl_p = spawn_server_process(&repl_env2);
     new_repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
 
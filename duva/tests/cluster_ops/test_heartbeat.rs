@@ -11,17 +11,17 @@ async fn test_heartbeat_hop_count_decreases_over_time() {
     let env = ServerEnv::default();
     let mut leader_p = spawn_server_process(&env);
     let leader_bind_addr = leader_p.bind_addr().clone();
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone());
     let mut follower_p1 = spawn_server_process(&repl_env);
 
-    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone().into());
+    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone());
     let mut follower_p2 = spawn_server_process(&repl_env2);
     let mut processes = vec![&mut leader_p, &mut follower_p1, &mut follower_p2];
 
     check_internodes_communication(&mut processes, DEFAULT_HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
 
     // WHEN run Third follower
-    let repl_env3 = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone().into());
+    let repl_env3 = ServerEnv::default().with_leader_bind_addr(leader_bind_addr.clone());
     let mut follower_p3 = spawn_server_process(&repl_env3);
     processes.push(&mut follower_p3);
 
@@ -41,9 +41,9 @@ async fn test_heartbeat_hop_count_starts_with_0() {
     let mut leader_p = spawn_server_process(&env);
 
     // WHEN
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p1 = spawn_server_process(&repl_env);
-    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p2 = spawn_server_process(&repl_env2);
 
     let processes = &mut [&mut leader_p, &mut follower_p1, &mut follower_p2];
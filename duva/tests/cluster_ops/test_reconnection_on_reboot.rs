@@ -8,7 +8,7 @@ async fn test() {
 
     let mut leader_p = spawn_server_process(&env);
 
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut repl_p = spawn_server_process(&repl_env);
 
     repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
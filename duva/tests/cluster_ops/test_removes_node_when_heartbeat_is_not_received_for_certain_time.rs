@@ -7,7 +7,7 @@ async fn test_removes_node_when_heartbeat_is_not_received_for_certain_time() {
 
     let mut leader_p = spawn_server_process(&env);
 
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut repl_p = spawn_server_process(&repl_env);
 
     repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
@@ -7,7 +7,7 @@ use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpListener;
 use std::process::{Child, Command, Stdio};
 use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 pub struct ServerEnv {
@@ -17,6 +17,9 @@ pub struct ServerEnv {
     pub hf: u128,
     pub ttl: u128,
     pub use_wal: bool,
+    pub lease_reads: bool,
+    pub forward_writes: bool,
+    pub forward_reads: bool,
     pub topology_path: TopologyPath,
 }
 
@@ -24,11 +27,15 @@ impl Default for ServerEnv {
     fn default() -> Self {
         ServerEnv {
             port: get_available_port(),
-            file_name: FileName(None),
+            // every server gets its own files, as hard state is always persisted next to them
+            file_name: FileName(Some(format!("test_dump_{}.rdb", Uuid::now_v7()))),
             leader_bind_addr: None,
             hf: 100,
             ttl: 1500,
             use_wal: false,
+            lease_reads: false,
+            forward_writes: false,
+            forward_reads: false,
             topology_path: TopologyPath(Uuid::now_v7().to_string()),
         }
     }
@@ -52,6 +59,19 @@ impl ServerEnv {
         self.ttl = ttl;
         self
     }
+    pub fn with_use_wal(mut self, use_wal: bool) -> Self {
+        self.use_wal = use_wal;
+        self
+    }
+    pub fn with_lease_reads(mut self, lease_reads: bool) -> Self {
+        self.lease_reads = lease_reads;
+        self
+    }
+    pub fn with_forwarding(mut self, forward_writes: bool, forward_reads: bool) -> Self {
+        self.forward_writes = forward_writes;
+        self.forward_reads = forward_reads;
+        self
+    }
     pub fn with_topology_path(mut self, topology_path: impl Into<String>) -> Self {
         self.topology_path = TopologyPath(topology_path.into());
         self
@@ -81,10 +101,13 @@ impl Drop for FileName {
         if let Some(file_name) = self.0.as_ref() {
             // remove if exists
             let _ = std::fs::remove_file(file_name);
-            let _ = std::fs::remove_file(format!("{}.wal", file_name));
+            let _ = std::fs::remove_dir_all(format!("{}.wal", file_name));
+            let _ = std::fs::remove_file(format!("{}.state", file_name));
+            let _ = std::fs::remove_file(format!("{}.state.tmp", file_name));
         } else {
             // remove if exists
-            let _ = std::fs::remove_file("dump.rdb.wal");
+            let _ = std::fs::remove_dir_all("dump.rdb.wal");
+            let _ = std::fs::remove_file("dump.rdb.state");
         }
     }
 }
@@ -96,9 +119,15 @@ impl Drop for TopologyPath {
     }
 }
 
+pub fn create_unique_file_name(function_name: &str) -> String {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+
+    format!("test_{}_{}.rdb", function_name, timestamp)
+}
+
 pub fn spawn_server_process(env: &ServerEnv) -> TestProcessChild {
     println!("Starting server on port {}", env.port);
-    let mut process = run_server_process(&env);
+    let mut process = run_server_process(env);
 
     wait_for_message(
         process.process.stdout.as_mut().unwrap(),
@@ -193,17 +222,23 @@ pub fn run_server_process(env: &ServerEnv) -> TestProcessChild {
         &env.hf.to_string(),
         "--ttl",
         &env.ttl.to_string(),
-        "--use_wal",
+        "--append_only",
         &env.use_wal.to_string(),
+        "--lease_reads",
+        &env.lease_reads.to_string(),
+        "--forward_writes",
+        &env.forward_writes.to_string(),
+        "--forward_reads",
+        &env.forward_reads.to_string(),
         "--tpp",
         &env.topology_path.0,
     ]);
 
     if let Some(replicaof) = env.leader_bind_addr.as_ref() {
-        command.args(["--replicaof", &replicaof]);
+        command.args(["--replicaof", replicaof]);
     }
     if let Some(file_name) = env.file_name.0.as_ref() {
-        command.args(["--dbfilename", &file_name]);
+        command.args(["--dbfilename", file_name]);
     }
 
     TestProcessChild::new(
@@ -239,10 +274,10 @@ fn wait_for_message<T: Read>(
             }
         }
 
-        if let Some(timeout) = timeout_in_millis {
-            if internal_count.elapsed().as_millis() > timeout {
-                return Err(anyhow::anyhow!("Timeout waiting for message"));
-            }
+        if let Some(timeout) = timeout_in_millis
+            && internal_count.elapsed().as_millis() > timeout
+        {
+            return Err(anyhow::anyhow!("Timeout waiting for message"));
         }
     }
 
@@ -250,14 +285,13 @@ fn wait_for_message<T: Read>(
 }
 
 pub fn array(arr: Vec<&str>) -> Bytes {
-    QueryIO::Array(arr.iter().map(|s| QueryIO::BulkString(s.to_string().into())).collect())
-        .serialize()
+    QueryIO::Array(arr.iter().map(|s| QueryIO::BulkString(s.to_string())).collect()).serialize()
 }
 
 pub fn session_request(request_id: u64, arr: Vec<&str>) -> Bytes {
     QueryIO::SessionRequest {
         request_id,
-        value: arr.iter().map(|s| QueryIO::BulkString(s.to_string().into())).collect(),
+        value: arr.iter().map(|s| QueryIO::BulkString(s.to_string())).collect(),
     }
     .serialize()
 }
@@ -274,13 +308,9 @@ pub fn check_internodes_communication(
             .iter()
             .enumerate()
             .filter(|&(j, _)| j != i)
-            .map(|(_, target)| {
-                (0..hop_count + 1)
-                    .into_iter()
-                    .map(|_| target.heartbeat_msg(hop_count))
-                    .collect::<Vec<String>>()
+            .flat_map(|(_, target)| {
+                (0..hop_count + 1).map(|_| target.heartbeat_msg(hop_count)).collect::<Vec<String>>()
             })
-            .flatten()
             .collect();
 
         // Then wait for all messages
@@ -291,6 +321,25 @@ pub fn check_internodes_communication(
     Ok(())
 }
 
+/// Promotes `learners` to voters through `leader`, retrying while they are still catching up.
+pub fn promote_to_voters(
+    leader: &TestProcessChild,
+    learners: &[&TestProcessChild],
+) -> anyhow::Result<()> {
+    let mut client = Client::new(leader.port);
+    for learner in learners {
+        let command = format!("cluster promote {}", learner.bind_addr());
+        let start = Instant::now();
+        while client.send_and_get(&command, 1) != vec!["OK"] {
+            if start.elapsed() > Duration::from_secs(5) {
+                return Err(anyhow::anyhow!("Failed to promote {}", learner.bind_addr()));
+            }
+            sleep(Duration::from_millis(100));
+        }
+    }
+    Ok(())
+}
+
 pub struct Client {
     pub child: Child,
     reader: Option<BufReader<std::process::ChildStdout>>,
@@ -332,7 +381,7 @@ impl Client {
         Ok(())
     }
 
-    pub fn read(&mut self) -> Result<String, ()> {
+    pub fn read(&mut self) -> std::io::Result<String> {
         // Initialize reader if it doesn't exist
         if self.reader.is_none() {
             self.reader = Some(BufReader::new(self.child.stdout.take().unwrap()));
@@ -340,7 +389,7 @@ impl Client {
 
         let reader = self.reader.as_mut().unwrap();
         let mut line = String::new();
-        reader.read_line(&mut line).map_err(|_| ())?;
+        reader.read_line(&mut line)?;
         Ok(line.trim().to_string())
     }
 
@@ -1,3 +1,10 @@
+mod test_check_quorum;
 mod test_leader_election;
+mod test_leader_redirect;
+mod test_leadership_transfer;
+mod test_learner_promotion;
+mod test_lease_reads;
 mod test_raft_happy_case;
 mod test_sync;
+mod test_write_forwarding;
+mod test_write_timeout;
@@ -0,0 +1,24 @@
+use crate::common::{Client, ServerEnv, promote_to_voters, spawn_server_process};
+
+#[tokio::test]
+async fn test_leader_steps_down_without_quorum() {
+    // GIVEN - peers are only dropped long after the election timeout
+    let leader_env = ServerEnv::default().with_ttl(10000);
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
+
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+
+    // WHEN
+    repl_p.kill().unwrap();
+
+    // THEN
+    leader_p.timed_wait_for_message(vec!["[INFO] Heard from 0 of 1"], 1, 5000).unwrap();
+    assert_eq!(leader_cli.send_and_get("role", 1), vec!["follower"]);
+}
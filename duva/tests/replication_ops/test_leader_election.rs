@@ -1,7 +1,13 @@
-use std::{thread::sleep, time::Duration};
-
-use crate::common::{Client, ServerEnv, check_internodes_communication, spawn_server_process};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use crate::common::{
+    Client, ServerEnv, check_internodes_communication, promote_to_voters, spawn_server_process,
+};
 use duva::domains::cluster_actors::heartbeats::scheduler::LEADER_HEARTBEAT_INTERVAL_MAX;
+use uuid::Uuid;
 
 #[tokio::test]
 async fn test_leader_election() {
@@ -9,16 +15,17 @@ async fn test_leader_election() {
     let leaver_env = ServerEnv::default();
     let mut leader_p = spawn_server_process(&leaver_env);
 
-    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p1 = spawn_server_process(&follower_env1);
 
-    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
 
     let mut follower_p2 = spawn_server_process(&follower_env2);
     const DEFAULT_HOP_COUNT: usize = 0;
     const TIMEOUT_IN_MILLIS: u128 = 2000;
     let processes = &mut [&mut leader_p, &mut follower_p1, &mut follower_p2];
     check_internodes_communication(processes, DEFAULT_HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
+    promote_to_voters(&leader_p, &[&follower_p1, &follower_p2]).unwrap();
 
     // WHEN
     leader_p.kill().unwrap();
@@ -28,7 +35,7 @@ async fn test_leader_election() {
     let mut flag = false;
     for f in [&follower_p1, &follower_p2] {
         let mut handler = Client::new(f.port);
-        let response1 = handler.send_and_get("info replication", 4);
+        let response1 = handler.send_and_get("info replication", 5);
         if response1.contains(&"role:leader".to_string()) {
             flag = true;
             break;
@@ -46,15 +53,16 @@ async fn test_set_twice_after_election() {
     let leaver_env = ServerEnv::default();
     let mut leader_p = spawn_server_process(&leaver_env);
 
-    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p1 = spawn_server_process(&follower_env1);
 
-    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p2 = spawn_server_process(&follower_env2);
     const DEFAULT_HOP_COUNT: usize = 0;
     const TIMEOUT_IN_MILLIS: u128 = 2000;
     let processes = &mut [&mut leader_p, &mut follower_p1, &mut follower_p2];
     check_internodes_communication(processes, DEFAULT_HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
+    promote_to_voters(&leader_p, &[&follower_p1, &follower_p2]).unwrap();
 
     // WHEN
     leader_p.kill().unwrap();
@@ -63,7 +71,7 @@ async fn test_set_twice_after_election() {
     let mut flag = false;
     for f in [&follower_p1, &follower_p2] {
         let mut handler = Client::new(f.port);
-        let res = handler.send_and_get("info replication", 4);
+        let res = handler.send_and_get("info replication", 5);
         if res.contains(&"role:leader".to_string()) {
             // THEN - one of the replicas should become the leader
             assert_eq!(handler.send_and_get("set 1 2", 1).first().unwrap(), "OK");
@@ -83,17 +91,19 @@ async fn test_leader_election_twice() {
     let leaver_env = ServerEnv::default();
     let mut leader_p = spawn_server_process(&leaver_env);
 
-    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p1 = spawn_server_process(&follower_env1);
 
-    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
     let mut follower_p2 = spawn_server_process(&follower_env2);
     const DEFAULT_HOP_COUNT: usize = 0;
     const TIMEOUT_IN_MILLIS: u128 = 2000;
     let processes = &mut [&mut leader_p, &mut follower_p1, &mut follower_p2];
     check_internodes_communication(processes, DEFAULT_HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
+    promote_to_voters(&leader_p, &[&follower_p1, &follower_p2]).unwrap();
 
     // !first leader is killed -> election happens
+    let mut killed_leader = leader_p.bind_addr();
     leader_p.kill().unwrap();
     sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
 
@@ -101,19 +111,25 @@ async fn test_leader_election_twice() {
 
     for mut f in [follower_p1, follower_p2] {
         let mut handler = Client::new(f.port);
-        let res = handler.send_and_get("info replication", 4);
+        let res = handler.send_and_get("info replication", 5);
         if !res.contains(&"role:leader".to_string()) {
             processes.push(f);
             continue;
         }
 
-        let follower_env3 = ServerEnv::default().with_leader_bind_addr(f.bind_addr().into());
+        // the killed leader is gone for good, so it has to give up its vote
+        let demote = format!("cluster demote {killed_leader}");
+        assert_eq!(handler.send_and_get(demote, 1), vec!["OK"]);
+
+        let follower_env3 = ServerEnv::default().with_leader_bind_addr(f.bind_addr());
         let new_process = spawn_server_process(&follower_env3);
+        promote_to_voters(&f, &[&new_process]).unwrap();
         sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
 
         // WHEN
         // ! second leader is killed -> election happens
         f.kill().unwrap();
+        killed_leader = f.bind_addr();
         sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
         processes.push(new_process);
     }
@@ -122,7 +138,7 @@ async fn test_leader_election_twice() {
     let mut flag = false;
     for f in processes.iter() {
         let mut handler = Client::new(f.port);
-        let res = handler.send_and_get("info replication", 4);
+        let res = handler.send_and_get("info replication", 5);
         if res.contains(&"role:leader".to_string()) {
             flag = true;
             break;
@@ -130,3 +146,88 @@ async fn test_leader_election_twice() {
     }
     assert!(flag, "No leader found after the second leader was killed");
 }
+
+/// A replica that missed committed entries must not win an election, even once a quorum is left without the leader.
+#[tokio::test]
+async fn test_candidate_with_stale_log_loses_election() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let follower_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut follower_p = spawn_server_process(&follower_env);
+
+    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut follower_p2 = spawn_server_process(&follower_env2);
+
+    let stale_env = ServerEnv::default()
+        .with_leader_bind_addr(leader_p.bind_addr())
+        .with_file_name(format!("test_stale_candidate_{}.rdb", Uuid::now_v7()))
+        .with_use_wal(true);
+    let mut stale_p = spawn_server_process(&stale_env);
+
+    // with four nodes, heartbeats go out with hop count 1 rather than 0
+    const HOP_COUNT: usize = 1;
+    // four nodes take longer to all hear from each other, more so while other tests run alongside
+    const TIMEOUT_IN_MILLIS: u128 = 5000;
+    let processes = &mut [&mut leader_p, &mut follower_p, &mut follower_p2, &mut stale_p];
+    check_internodes_communication(processes, HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
+    promote_to_voters(&leader_p, &[&follower_p, &follower_p2, &stale_p]).unwrap();
+
+    let mut leader_h = Client::new(leader_p.port);
+    assert_eq!(leader_h.send_and_get("set 1 a", 1), vec!["OK"]);
+    let mut stale_h = Client::new(stale_p.port);
+    wait_until(|| stale_h.send_and_get("get 1", 1) == vec!["a"]);
+
+    // the stale node keeps its snapshot, WAL and hard state across the restart
+    assert_eq!(stale_h.send_and_get("SAVE", 1), vec!["(nil)"]);
+    stale_p.timed_wait_for_message(vec!["[INFO] Snapshot saved"], 1, 5000).unwrap();
+    stale_p.kill().unwrap();
+
+    // commit an entry it never sees; the stale node keeps its vote while it is away
+    assert_eq!(leader_h.send_and_get("set 2 b", 1), vec!["OK"]);
+    let mut follower_hs = [Client::new(follower_p.port), Client::new(follower_p2.port)];
+    for h in follower_hs.iter_mut() {
+        wait_until(|| h.send_and_get("get 2", 1) == vec!["b"]);
+    }
+
+    // WHEN - the leader goes away and the stale node comes back
+    leader_p.kill().unwrap();
+    let mut restarted_env = ServerEnv::default()
+        .with_leader_bind_addr(follower_p.bind_addr())
+        .with_file_name(stale_env.file_name.0.clone().unwrap())
+        .with_use_wal(true);
+    restarted_env.port = stale_env.port;
+    let restarted_p = spawn_server_process(&restarted_env);
+
+    // THEN - only the nodes holding the committed entry can be elected
+    let mut restarted_h = Client::new(restarted_p.port);
+    // every vote needs the stale node, so split votes can take a few rounds to settle
+    let start = Instant::now();
+    let elected = loop {
+        sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
+        assert!(
+            restarted_h.send_and_get("info replication", 5).contains(&"role:follower".to_string()),
+            "Candidate with a stale log was elected"
+        );
+        if let Some(elected) = follower_hs.iter_mut().position(|h| {
+            h.send_and_get("info replication", 5).contains(&"role:leader".to_string())
+        }) {
+            break elected;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "Node with the up-to-date log was not elected"
+        );
+    };
+    assert_eq!(follower_hs[elected].send_and_get("get 2", 1), vec!["b"]);
+}
+
+// Polls `condition` until it holds, failing the test when it doesn't within a few seconds
+fn wait_until(mut condition: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(5), "Condition not met in time");
+        sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[tokio::test]
+async fn test_client_follows_redirect_to_leader() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let leader_p = spawn_server_process(&leader_env);
+    let mut leader_cli = Client::new(leader_p.port);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    // the follower learns who leads from the AppendEntries carrying this write
+    assert_eq!(leader_cli.send_and_get("set 0 x", 1), vec!["OK"]);
+    repl_p.wait_for_message("[INFO] Received commit offset 1", 1).unwrap();
+
+    // WHEN - the client only knows the follower
+    let mut cli = Client::new(repl_p.port);
+    let res = cli.send_and_get("set 1 a", 2);
+
+    // THEN - the refused write points at the leader, and the next one goes there
+    assert_eq!(
+        res,
+        vec![
+            format!("Connected to a new leader: {}", leader_p.bind_addr()),
+            format!("(error) MOVED {}", leader_p.bind_addr()),
+        ]
+    );
+    assert_eq!(cli.send_and_get("set 1 b", 1), vec!["OK"]);
+    assert_eq!(leader_cli.send_and_get("get 1", 1), vec!["b"]);
+}
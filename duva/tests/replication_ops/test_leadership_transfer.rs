@@ -0,0 +1,53 @@
+use crate::common::{
+    Client, ServerEnv, check_internodes_communication, promote_to_voters, spawn_server_process,
+};
+
+#[tokio::test]
+async fn test_cluster_failover_hands_leadership_to_target() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let follower_env1 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut follower_p1 = spawn_server_process(&follower_env1);
+
+    let follower_env2 = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut follower_p2 = spawn_server_process(&follower_env2);
+    const DEFAULT_HOP_COUNT: usize = 0;
+    const TIMEOUT_IN_MILLIS: u128 = 2000;
+    let processes = &mut [&mut leader_p, &mut follower_p1, &mut follower_p2];
+    check_internodes_communication(processes, DEFAULT_HOP_COUNT, TIMEOUT_IN_MILLIS).unwrap();
+    promote_to_voters(&leader_p, &[&follower_p1, &follower_p2]).unwrap();
+
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+
+    // WHEN
+    let res = leader_cli.send_and_get(format!("cluster failover {}", follower_p1.bind_addr()), 1);
+
+    // THEN
+    assert_eq!(res, vec!["OK"]);
+    assert!(leader_cli.send_and_get("info replication", 5).contains(&"role:follower".to_string()));
+
+    let mut new_leader_cli = Client::new(follower_p1.port);
+    assert!(
+        new_leader_cli.send_and_get("info replication", 5).contains(&"role:leader".to_string())
+    );
+    assert_eq!(new_leader_cli.send_and_get("set 2 b", 1), vec!["OK"]);
+    assert_eq!(new_leader_cli.send_and_get("get 1", 1), vec!["a"]);
+}
+
+#[tokio::test]
+async fn test_cluster_failover_to_unknown_replica() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let leader_p = spawn_server_process(&leader_env);
+    let mut leader_cli = Client::new(leader_p.port);
+
+    // WHEN
+    let res = leader_cli.send_and_get("cluster failover 127.0.0.1:1", 1);
+
+    // THEN
+    assert_eq!(res, vec!["(error) ERR no such replica"]);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+}
@@ -0,0 +1,56 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[tokio::test]
+async fn test_cluster_promote_turns_learner_into_voter() {
+    // GIVEN - a replica joins as a learner
+    let leader_env = ServerEnv::default();
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+
+    // - writes commit without waiting for the learner
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+
+    // WHEN
+    let command = format!("cluster promote {}", repl_p.bind_addr());
+    let mut res = leader_cli.send_and_get(&command, 1);
+    for _ in 0..10 {
+        if res != vec!["(error) ERR learner has not caught up"] {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        res = leader_cli.send_and_get(&command, 1);
+    }
+
+    // THEN
+    assert_eq!(res, vec!["OK"]);
+    assert_eq!(leader_cli.send_and_get(&command, 1), vec!["(error) ERR already a voter"]);
+    assert_eq!(leader_cli.send_and_get("set 2 b", 1), vec!["OK"]);
+
+    // WHEN - the voter is taken back out of the configuration
+    let command = format!("cluster demote {}", repl_p.bind_addr());
+    let res = leader_cli.send_and_get(&command, 1);
+
+    // THEN
+    assert_eq!(res, vec!["OK"]);
+    assert_eq!(leader_cli.send_and_get(&command, 1), vec!["(error) ERR not a voter"]);
+    assert_eq!(leader_cli.send_and_get("set 3 c", 1), vec!["OK"]);
+}
+
+#[tokio::test]
+async fn test_cluster_promote_unknown_replica() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let leader_p = spawn_server_process(&leader_env);
+    let mut leader_cli = Client::new(leader_p.port);
+
+    // WHEN
+    let res = leader_cli.send_and_get("cluster promote 127.0.0.1:1", 1);
+
+    // THEN
+    assert_eq!(res, vec!["(error) ERR no such replica"]);
+}
@@ -0,0 +1,62 @@
+use crate::common::{Client, ServerEnv, promote_to_voters, spawn_server_process};
+
+#[tokio::test]
+async fn test_leader_serves_reads_under_lease() {
+    // GIVEN
+    let leader_env = ServerEnv::default().with_lease_reads(true);
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
+    // - the lease holds once the new voter acknowledged a heartbeat round
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // WHEN
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+
+    // THEN - the voter keeps acknowledging heartbeats, so the lease holds
+    for _ in 0..3 {
+        assert_eq!(leader_cli.send_and_get("get 1", 1), vec!["a"]);
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+}
+
+#[tokio::test]
+async fn test_leader_refuses_reads_once_lease_lapses() {
+    // GIVEN - the leader only steps down long after the lease lapses
+    let leader_env = ServerEnv::default().with_lease_reads(true).with_ttl(3000);
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
+    // - the lease holds once the new voter acknowledged a heartbeat round
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // - reads are served while the voter keeps acknowledging heartbeats
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+    assert_eq!(leader_cli.send_and_get("get 1", 1), vec!["a"]);
+
+    // WHEN - the voter stops acknowledging heartbeats but keeps its connection open
+    std::process::Command::new("kill").args(["-STOP", &repl_p.id().to_string()]).status().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+
+    // THEN
+    assert_eq!(
+        leader_cli.send_and_get("get 1", 1),
+        vec!["(error) ERR leader lease expired, retry on the current leader"]
+    );
+
+    // WHEN - the voter stays silent past the node timeout
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+
+    // THEN - CheckQuorum makes the leader step down
+    assert_eq!(leader_cli.send_and_get("set 2 b", 1), vec!["(error) Write given to follower"]);
+}
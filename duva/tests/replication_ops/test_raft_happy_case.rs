@@ -1,4 +1,4 @@
-use crate::common::{Client, ServerEnv, spawn_server_process};
+use crate::common::{Client, ServerEnv, promote_to_voters, spawn_server_process};
 
 #[tokio::test]
 async fn test_set_operation_reaches_to_all_replicas() {
@@ -11,23 +11,25 @@ async fn test_set_operation_reaches_to_all_replicas() {
     let mut client_handler = Client::new(leader_p.port);
 
     let repl_env = ServerEnv::default()
-        .with_leader_bind_addr(leader_p.bind_addr().into())
+        .with_leader_bind_addr(leader_p.bind_addr())
         .with_file_name("follower_dbfilename");
 
     let mut repl_p = spawn_server_process(&repl_env);
 
     repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
     leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
 
     // WHEN -- set operation is made
     client_handler.send_and_get("SET foo bar", 1);
 
     //THEN - run the following together
+    // * log index 1 holds the configuration that promoted the replica to a voter
     let h = std::thread::spawn(move || {
         repl_p.timed_wait_for_message(
             vec![
-                "[INFO] Received log entry with log index up to 1",
-                "[INFO] Received commit offset 1",
+                "[INFO] Received log entry with log index up to 2",
+                "[INFO] Received commit offset 2",
             ],
             1,
             2000,
@@ -35,11 +37,7 @@ async fn test_set_operation_reaches_to_all_replicas() {
     });
 
     let h2 = std::thread::spawn(move || {
-        leader_p.timed_wait_for_message(
-            vec!["[INFO] Received acks for log index num: 1", "[INFO] log 1 commited"],
-            1,
-            2000,
-        )
+        leader_p.timed_wait_for_message(vec!["[INFO] log 2 commited"], 1, 2000)
     });
 
     h.join().unwrap().unwrap();
@@ -11,7 +11,7 @@ async fn test_full_sync_on_newly_added_replica() {
     h.send_and_get("SET foo bar", 1);
 
     // WHEN run replica
-    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr().into());
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
 
     let mut replica_process = spawn_server_process(&repl_env);
     check_internodes_communication(&mut [&mut leader_p, &mut replica_process], 0, 1000).unwrap();
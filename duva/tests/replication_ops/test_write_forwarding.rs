@@ -0,0 +1,47 @@
+use crate::common::{Client, ServerEnv, promote_to_voters, spawn_server_process};
+
+#[tokio::test]
+async fn test_follower_forwards_writes_and_reads_to_leader() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default()
+        .with_leader_bind_addr(leader_p.bind_addr())
+        .with_forwarding(true, true);
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
+
+    // WHEN - the client only knows the follower
+    let mut repl_cli = Client::new(repl_p.port);
+    assert_eq!(repl_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+
+    // THEN
+    assert_eq!(repl_cli.send_and_get("get 1", 1), vec!["a"]);
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("get 1", 1), vec!["a"]);
+}
+
+#[tokio::test]
+async fn test_follower_refuses_writes_without_forwarding() {
+    // GIVEN
+    let leader_env = ServerEnv::default();
+    let leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("set 0 x", 1), vec!["OK"]);
+    repl_p.wait_for_message("[INFO] Received commit offset 1", 1).unwrap();
+
+    // WHEN
+    let mut repl_cli = Client::new(repl_p.port);
+    let res = repl_cli.send_and_get("set 1 a", 2);
+
+    // THEN - the write is refused, and the client is sent to the leader instead
+    assert_eq!(res[1], format!("(error) MOVED {}", leader_p.bind_addr()));
+    assert_eq!(repl_cli.send_and_get("set 1 a", 1), vec!["OK"]);
+}
@@ -0,0 +1,48 @@
+use crate::common::{Client, ServerEnv, promote_to_voters, spawn_server_process};
+
+#[test]
+fn test_values_spelling_a_timeout_are_stored_as_given() {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env);
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("RPUSH q job TIMEOUT 100", 1), vec!["(integer) 3"]);
+    assert_eq!(h.send_and_get("HSET h f v TIMEOUT 30", 1), vec!["(integer) 2"]);
+
+    // THEN
+    assert_eq!(
+        h.send_and_get("LRANGE q 0 -1", 3),
+        vec!["0) \"job\"", "1) \"TIMEOUT\"", "2) \"100\""]
+    );
+    assert_eq!(h.send_and_get("HGET h TIMEOUT", 1), vec!["30"]);
+    assert_eq!(
+        h.send_and_get("WITHTIMEOUT 100 GET h", 1),
+        vec!["(error) ERR WITHTIMEOUT only applies to writes"]
+    );
+}
+
+#[tokio::test]
+async fn test_write_times_out_after_its_own_timeout() {
+    // GIVEN - the leader keeps leading long after the voter goes silent
+    let leader_env = ServerEnv::default().with_ttl(10000);
+    let mut leader_p = spawn_server_process(&leader_env);
+
+    let repl_env = ServerEnv::default().with_leader_bind_addr(leader_p.bind_addr());
+    let mut repl_p = spawn_server_process(&repl_env);
+    repl_p.wait_for_message(&leader_p.heartbeat_msg(0), 1).unwrap();
+    leader_p.wait_for_message(&repl_p.heartbeat_msg(0), 1).unwrap();
+    promote_to_voters(&leader_p, &[&repl_p]).unwrap();
+
+    let mut leader_cli = Client::new(leader_p.port);
+    assert_eq!(leader_cli.send_and_get("WITHTIMEOUT 500 rpush list a", 1), vec!["(integer) 1"]);
+
+    // WHEN - the voter stops acknowledging but keeps its connection open
+    std::process::Command::new("kill").args(["-STOP", &repl_p.id().to_string()]).status().unwrap();
+    let res = leader_cli.send_and_get("WITHTIMEOUT 500 rpush list b", 1);
+
+    // THEN
+    assert_eq!(res, vec!["(error) ERR write timed out; outcome unknown"]);
+    assert_eq!(leader_cli.send_and_get("INFO replication", 5)[4], "timed_out_writes:1");
+}